@@ -1,7 +1,10 @@
 use std::{path::PathBuf, str::FromStr};
 
-use ed25519_dalek::ed25519::signature::SignerMut;
-use hl_core::{Context, Intent, Key, Rhex, Signature, rhex::signature::SigType, to_base64};
+use hl_core::{
+    Context, Intent, Key, Rhex, Signature,
+    rhex::{rhex::RHEX_MAGIC, signature::SigType},
+    to_base64,
+};
 use hl_io::{
     fs::{self, rhex::DirSink},
     sink::RhexSink,
@@ -19,9 +22,9 @@ pub fn create(create_args: &CreateArgs) -> Result<(), anyhow::Error> {
     if key.is_err() {
         anyhow::bail!("Invalid keyfile")
     }
-    let key = Key::from_bytes(key.unwrap());
+    let key = Key::from_secret(key.unwrap());
     let mut genesis_rhex = Rhex {
-        magic: *b"RHEX\x00\x00",
+        magic: RHEX_MAGIC,
         intent: Intent {
             previous_hash: None,
             scope: "".to_string(),
@@ -36,6 +39,7 @@ pub fn create(create_args: &CreateArgs) -> Result<(), anyhow::Error> {
                     .as_millis(),
                 "public_key": to_base64(&key.pk.unwrap())
             }),
+            delegation: None,
         },
         context: Context {
             at: 0,
@@ -49,14 +53,13 @@ pub fn create(create_args: &CreateArgs) -> Result<(), anyhow::Error> {
     };
 
     // Hand sign our genesis with the same key all the way through
-    let mut sig = ed25519_dalek::SigningKey::from_bytes(&key.sk.unwrap());
     let author_hash = genesis_rhex
         .author_hash()
         .expect("failed to get author hash");
     let author_sig = Signature {
         sig_type: SigType::Author,
         public_key: key.pk.unwrap(),
-        sig: sig.sign(&author_hash).to_bytes(),
+        sig: key.sign(&author_hash)?,
     };
     genesis_rhex.signatures.push(author_sig.clone());
 
@@ -66,7 +69,7 @@ pub fn create(create_args: &CreateArgs) -> Result<(), anyhow::Error> {
     let usher_sig = Signature {
         sig_type: SigType::Usher,
         public_key: key.pk.unwrap(),
-        sig: sig.sign(&usher_hash).to_bytes(),
+        sig: key.sign(&usher_hash)?,
     };
     genesis_rhex.signatures.push(usher_sig.clone());
 
@@ -76,7 +79,7 @@ pub fn create(create_args: &CreateArgs) -> Result<(), anyhow::Error> {
     let quorum_sig = Signature {
         sig_type: SigType::Quorum,
         public_key: key.pk.unwrap(),
-        sig: sig.sign(&quorum_hash).to_bytes(),
+        sig: key.sign(&quorum_hash)?,
     };
     genesis_rhex.signatures.push(quorum_sig);
 
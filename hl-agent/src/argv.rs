@@ -0,0 +1,13 @@
+use clap::Parser;
+
+#[derive(Parser, Debug)]
+#[command(name = "hl-agent", about = "HodeauxLedger Signing Agent")]
+pub struct Cli {
+    /// Unix socket to listen on
+    #[arg(short, long, default_value = "./hl-agent.sock")]
+    pub socket: String,
+
+    /// JSON list of signer specs (hot or hkyv) to hold
+    #[arg(short, long)]
+    pub keys: String,
+}
@@ -0,0 +1,40 @@
+use std::{fs, path::Path, process};
+
+use clap::Parser;
+use hl_core::{
+    keymaster::{keymaster::Keymaster, signer::SignerSpec},
+    to_base64,
+};
+use hl_io::signer;
+
+mod argv;
+mod serve;
+
+fn print_banner() {
+    println!("HodeauxLedger Signing Agent");
+    println!("===========================");
+}
+
+fn run(args: &argv::Cli) -> Result<(), anyhow::Error> {
+    let specs: Vec<SignerSpec> = serde_json::from_str(&fs::read_to_string(&args.keys)?)?;
+    let mut keymaster = Keymaster::new();
+    for spec in specs.iter() {
+        if let SignerSpec::Agent { .. } = spec {
+            anyhow::bail!("An agent can't hold keys from another agent");
+        }
+        for key in signer::open(spec)? {
+            println!("Holding key {}", to_base64(&key.public_key_bytes()?));
+            keymaster.hot_keys.push(key);
+        }
+    }
+    serve::serve(Path::new(&args.socket), keymaster)
+}
+
+fn main() {
+    print_banner();
+    let parsed = argv::Cli::parse();
+    if let Err(e) = run(&parsed) {
+        println!("Error: {}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,98 @@
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    os::unix::{
+        fs::{DirBuilderExt, PermissionsExt},
+        net::{UnixListener, UnixStream},
+    },
+    path::Path,
+    sync::Arc,
+    thread,
+};
+
+use hl_core::{keymaster::keymaster::Keymaster, to_base64};
+use hl_io::agent::proto::{AgentRequest, AgentResponse};
+
+/// Serve sign requests on `socket` until killed.
+pub fn serve(socket: &Path, keymaster: Keymaster) -> Result<(), anyhow::Error> {
+    // A socket left over from a previous run
+    if socket.exists() {
+        fs::remove_file(socket)?;
+    }
+    let listener = bind_private(socket)?;
+    println!("Listening on {:?}", socket);
+
+    let keymaster = Arc::new(keymaster);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Error accepting connection: {}", e);
+                continue;
+            }
+        };
+        let keymaster = keymaster.clone();
+        thread::spawn(move || {
+            if let Err(e) = handle(stream, &keymaster) {
+                eprintln!("Error handling request: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Bind inside a fresh 0700 directory, tighten the socket to 0600 and only
+/// then move it into place, so no other user can connect in between.
+fn bind_private(socket: &Path) -> Result<UnixListener, anyhow::Error> {
+    let parent = socket
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let staging = parent.join(format!(".hl-agent.{}", std::process::id()));
+    fs::DirBuilder::new().mode(0o700).create(&staging)?;
+    let staged = staging.join("s");
+    let bound = UnixListener::bind(&staged).and_then(|listener| {
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o600))?;
+        fs::rename(&staged, socket)?;
+        Ok(listener)
+    });
+    let _ = fs::remove_file(&staged);
+    fs::remove_dir(&staging)?;
+    Ok(bound?)
+}
+
+fn handle(stream: UnixStream, keymaster: &Keymaster) -> Result<(), anyhow::Error> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let response = match serde_json::from_str(&line?) {
+            Ok(request) => answer(&request, keymaster),
+            Err(e) => AgentResponse::Error {
+                message: format!("bad request: {}", e),
+            },
+        };
+        let mut out = serde_json::to_vec(&response)?;
+        out.push(b'\n');
+        writer.write_all(&out)?;
+    }
+    Ok(())
+}
+
+fn answer(request: &AgentRequest, keymaster: &Keymaster) -> AgentResponse {
+    match request {
+        AgentRequest::Keys => AgentResponse::Keys {
+            keys: keymaster.hot_keys.iter().filter_map(|key| key.pk).collect(),
+        },
+        AgentRequest::Sign { pk, hash } => {
+            let signed = keymaster.get_matching(pk).and_then(|key| key.sign(hash));
+            match signed {
+                Ok(sig) => {
+                    println!("Signed for {}", to_base64(pk));
+                    AgentResponse::Signature { sig }
+                }
+                Err(e) => AgentResponse::Error {
+                    message: format!("{}: {}", to_base64(pk), e),
+                },
+            }
+        }
+    }
+}
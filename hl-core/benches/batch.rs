@@ -0,0 +1,52 @@
+//! Batch against one-at-a-time verification over a replay-sized run.
+//!
+//!     cargo bench -p hl-core --bench batch
+
+use std::time::{Duration, Instant};
+
+use hl_core::key::{
+    batch::{BatchItem, verify_batch},
+    secret::SecretKey,
+};
+
+const SIGNATURES: usize = 256;
+const ROUNDS: u32 = 20;
+
+fn time(mut f: impl FnMut() -> bool) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        assert!(f());
+    }
+    start.elapsed() / ROUNDS
+}
+
+fn main() {
+    let items: Vec<BatchItem> = (0..SIGNATURES)
+        .map(|i| {
+            let sk = SecretKey::generate();
+            let message = [i as u8; 32];
+            BatchItem {
+                public_key: sk.public_key(),
+                message,
+                sig: sk.sign(&message),
+            }
+        })
+        .collect();
+
+    let single = time(|| {
+        items.iter().all(|item| {
+            let key = ed25519_dalek::VerifyingKey::from_bytes(&item.public_key).unwrap();
+            let sig = ed25519_dalek::Signature::from_bytes(&item.sig);
+            key.verify_strict(&item.message, &sig).is_ok()
+        })
+    });
+    let batch = time(|| verify_batch(&items));
+
+    println!("{} signatures", SIGNATURES);
+    println!("  one at a time  {:>10.2?}", single);
+    println!("  batched        {:>10.2?}", batch);
+    println!(
+        "  speedup        {:>10.2}x",
+        single.as_secs_f64() / batch.as_secs_f64()
+    );
+}
@@ -0,0 +1,255 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_with::{Bytes, serde_as};
+
+use crate::{
+    Key,
+    cbor::canonical::{from_canonical_cbor, hash_canonical, to_canonical_cbor},
+    error,
+    policy::rule::type_matches,
+    to_base64,
+};
+
+const CONTEXT: &str = "HodeauxLedger delegation v1";
+
+/// An offline grant: `issuer_pk` lets `subject_pk` author `record_types`
+/// in `scope` until micromark `exp`, and with `quorum` also sign their
+/// quorum. Certificates chain, each issued by the previous one's subject,
+/// back to a key the ledger granted.
+#[serde_as]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Delegation {
+    #[serde_as(as = "Bytes")]
+    pub issuer_pk: [u8; 32],
+    #[serde_as(as = "Bytes")]
+    pub subject_pk: [u8; 32],
+    pub scope: String,
+    pub record_types: Vec<String>,
+    pub exp: u64,
+    pub quorum: bool,
+    #[serde_as(as = "Bytes")]
+    pub sig: [u8; 64],
+}
+
+impl Delegation {
+    pub fn new(subject_pk: [u8; 32], scope: &str, record_types: Vec<String>, exp: u64) -> Self {
+        Self {
+            issuer_pk: [0u8; 32],
+            subject_pk,
+            scope: scope.to_string(),
+            record_types,
+            exp,
+            quorum: false,
+            sig: [0u8; 64],
+        }
+    }
+
+    /// What the issuer signs: everything but the signature.
+    pub fn preimage(&self) -> anyhow::Result<[u8; 32]> {
+        let mut hasher = blake3::Hasher::new_derive_key(CONTEXT);
+        hash_canonical(
+            &mut hasher,
+            &(
+                serde_bytes::Bytes::new(&self.issuer_pk),
+                serde_bytes::Bytes::new(&self.subject_pk),
+                &self.scope,
+                &self.record_types,
+                self.exp,
+                self.quorum,
+            ),
+        )?;
+        Ok(*hasher.finalize().as_bytes())
+    }
+
+    pub fn sign(&mut self, issuer: &Key) -> anyhow::Result<()> {
+        let Some(issuer_pk) = issuer.pk else {
+            anyhow::bail!("issuer key has no public key");
+        };
+        self.issuer_pk = issuer_pk;
+        self.sig = issuer.sign(&self.preimage()?)?;
+        Ok(())
+    }
+
+    pub fn verify(&self) -> bool {
+        let Ok(preimage) = self.preimage() else {
+            return false;
+        };
+        Key::from_pk_bytes(self.issuer_pk)
+            .verify(&preimage, &self.sig)
+            .unwrap_or(false)
+    }
+
+    pub fn allows(&self, record_type: &str) -> bool {
+        self.record_types
+            .iter()
+            .any(|pattern| type_matches(pattern, record_type))
+    }
+
+    pub fn to_cbor(&self) -> anyhow::Result<Vec<u8>> {
+        to_canonical_cbor(self)
+    }
+
+    pub fn from_cbor(bytes: &[u8]) -> anyhow::Result<Self> {
+        from_canonical_cbor(bytes)
+    }
+}
+
+/// A link in a delegation chain that doesn't hold up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DelegationError {
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl DelegationError {
+    pub fn new(code: &'static str, message: String) -> Self {
+        Self { code, message }
+    }
+}
+
+impl fmt::Display for DelegationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for DelegationError {}
+
+/// Follow `certs` from `pk` back to the key that started its chain,
+/// checking every link for `record_type` in `scope` at micromark `at`.
+/// `None` if no certificate names `pk`.
+pub fn resolve(
+    certs: &[Delegation],
+    pk: &[u8; 32],
+    scope: &str,
+    record_type: &str,
+    at: u64,
+) -> Result<Option<[u8; 32]>, DelegationError> {
+    follow(certs, pk, scope, record_type, at, false)
+}
+
+/// `resolve` for a quorum signer: every link must also grant `quorum`.
+pub fn resolve_quorum(
+    certs: &[Delegation],
+    pk: &[u8; 32],
+    scope: &str,
+    record_type: &str,
+    at: u64,
+) -> Result<Option<[u8; 32]>, DelegationError> {
+    follow(certs, pk, scope, record_type, at, true)
+}
+
+fn follow(
+    certs: &[Delegation],
+    pk: &[u8; 32],
+    scope: &str,
+    record_type: &str,
+    at: u64,
+    quorum: bool,
+) -> Result<Option<[u8; 32]>, DelegationError> {
+    let mut current = *pk;
+    let mut links = 0;
+    while let Some(cert) = certs.iter().find(|c| c.subject_pk == current) {
+        links += 1;
+        if links > certs.len() {
+            return Err(DelegationError::new(
+                error::E_DELEGATION_INVALID,
+                "delegation chain loops".to_string(),
+            ));
+        }
+        let subject = to_base64(&cert.subject_pk);
+        if !cert.verify() {
+            return Err(DelegationError::new(
+                error::E_DELEGATION_INVALID,
+                format!("delegation to {} has a bad signature", subject),
+            ));
+        }
+        if cert.scope != scope || !cert.allows(record_type) {
+            return Err(DelegationError::new(
+                error::E_DELEGATION_INVALID,
+                format!(
+                    "delegation to {} does not cover {} in {:?}",
+                    subject, record_type, scope
+                ),
+            ));
+        }
+        if quorum && !cert.quorum {
+            return Err(DelegationError::new(
+                error::E_DELEGATION_INVALID,
+                format!("delegation to {} does not cover quorum signing", subject),
+            ));
+        }
+        if at > cert.exp {
+            return Err(DelegationError::new(
+                error::E_DELEGATION_EXPIRED,
+                format!("delegation to {} expired at {}", subject, cert.exp),
+            ));
+        }
+        current = cert.issuer_pk;
+    }
+    Ok((links > 0).then_some(current))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chain_resolves_to_the_granted_key() {
+        let (root, middle, device) = (Key::generated(), Key::generated(), Key::generated());
+        let types = vec!["record:*".to_string()];
+        let mut first = Delegation::new(middle.pk.unwrap(), "farm", types, 500);
+        first.sign(&root).unwrap();
+        let mut second = Delegation::new(
+            device.pk.unwrap(),
+            "farm",
+            vec!["record:data".to_string()],
+            400,
+        );
+        second.sign(&middle).unwrap();
+        let certs = vec![second.clone(), first];
+        let device_pk = device.pk.unwrap();
+
+        let resolved = resolve(&certs, &device_pk, "farm", "record:data", 100).unwrap();
+        assert_eq!(resolved, root.pk);
+        assert_eq!(
+            resolve(&certs, &root.pk.unwrap(), "farm", "record:data", 1).unwrap(),
+            None
+        );
+        assert!(resolve(&certs, &device_pk, "farm", "record:note", 100).is_err());
+        assert!(resolve(&certs, &device_pk, "barn", "record:data", 100).is_err());
+        let expired = resolve(&certs, &device_pk, "farm", "record:data", 450);
+        assert_eq!(expired.unwrap_err().code, error::E_DELEGATION_EXPIRED);
+
+        // Authoring only, until every link also grants quorum
+        let unquorate = resolve_quorum(&certs, &device_pk, "farm", "record:data", 100);
+        assert_eq!(unquorate.unwrap_err().code, error::E_DELEGATION_INVALID);
+
+        second.scope = "barn".to_string();
+        assert!(!second.verify());
+        let cbor = certs[0].to_cbor().unwrap();
+        assert_eq!(Delegation::from_cbor(&cbor).unwrap(), certs[0]);
+    }
+
+    #[test]
+    fn quorum_needs_every_link_to_grant_it() {
+        let (root, middle, device) = (Key::generated(), Key::generated(), Key::generated());
+        let types = vec!["record:*".to_string()];
+        let mut first = Delegation::new(middle.pk.unwrap(), "farm", types.clone(), 500);
+        first.quorum = true;
+        first.sign(&root).unwrap();
+        let mut second = Delegation::new(device.pk.unwrap(), "farm", types, 500);
+        second.sign(&middle).unwrap();
+        let device_pk = device.pk.unwrap();
+
+        let certs = vec![first.clone(), second.clone()];
+        assert!(resolve_quorum(&certs, &device_pk, "farm", "record:data", 1).is_err());
+        second.quorum = true;
+        assert!(!second.verify());
+        second.sign(&middle).unwrap();
+        let certs = vec![first, second];
+        let resolved = resolve_quorum(&certs, &device_pk, "farm", "record:data", 1).unwrap();
+        assert_eq!(resolved, root.pk);
+    }
+}
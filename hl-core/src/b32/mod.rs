@@ -1 +1,2 @@
 pub mod b32;
+pub mod paper;
@@ -0,0 +1,79 @@
+use crate::b32::b32::{from_base32_crockford, to_base32_crockford};
+
+/// Paper payload kinds.
+pub const PAPER_KEY: u8 = 1;
+pub const PAPER_SHARE: u8 = 2;
+
+const CHECKSUM_LEN: usize = 4;
+const GROUP: usize = 4;
+const GROUPS_PER_LINE: usize = 6;
+
+/// Encode `payload` for writing down by hand: Crockford base32 of
+/// `kind || payload || blake3 checksum`, in dash-separated groups of
+/// four, six groups to a line.
+pub fn to_paper(kind: u8, payload: &[u8]) -> String {
+    let mut bytes = Vec::with_capacity(1 + payload.len() + CHECKSUM_LEN);
+    bytes.push(kind);
+    bytes.extend_from_slice(payload);
+    let checksum = blake3::hash(&bytes);
+    bytes.extend_from_slice(&checksum.as_bytes()[..CHECKSUM_LEN]);
+    let text = to_base32_crockford(&bytes);
+    let groups: Vec<&str> = text
+        .as_bytes()
+        .chunks(GROUP)
+        .map(|g| std::str::from_utf8(g).unwrap_or_default())
+        .collect();
+    groups
+        .chunks(GROUPS_PER_LINE)
+        .map(|line| line.join("-"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Decode a transcription back to `(kind, payload)`. Case, dashes,
+/// whitespace and the look-alikes O, I and L are forgiven; a wrong
+/// character fails the checksum.
+pub fn from_paper(text: &str) -> anyhow::Result<(u8, Vec<u8>)> {
+    let cleaned: String = text
+        .chars()
+        .map(|c| match c.to_ascii_uppercase() {
+            'O' => '0',
+            'I' | 'L' => '1',
+            c => c,
+        })
+        .collect();
+    let bytes = from_base32_crockford(&cleaned)?;
+    if bytes.len() <= CHECKSUM_LEN {
+        anyhow::bail!("paper backup too short");
+    }
+    let (body, checksum) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+    if &blake3::hash(body).as_bytes()[..CHECKSUM_LEN] != checksum {
+        anyhow::bail!("paper backup checksum mismatch; check the transcription");
+    }
+    Ok((body[0], body[1..].to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_and_catches_typos() {
+        let payload = [42u8; 32];
+        let paper = to_paper(PAPER_KEY, &payload);
+        assert!(paper.lines().all(|l| l.len() < 6 * 5));
+
+        let sloppy = paper.to_lowercase().replace('0', "o").replace('\n', " ");
+        assert_eq!(from_paper(&sloppy).unwrap(), (PAPER_KEY, payload.to_vec()));
+
+        let pos = paper.find(|c: char| c.is_ascii_alphanumeric()).unwrap();
+        let mut typo = paper.clone();
+        let wrong = if &paper[pos..pos + 1] == "7" {
+            "8"
+        } else {
+            "7"
+        };
+        typo.replace_range(pos..pos + 1, wrong);
+        assert!(from_paper(&typo).is_err());
+    }
+}
@@ -1 +1,2 @@
 pub mod b64;
+pub mod serde;
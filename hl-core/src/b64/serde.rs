@@ -0,0 +1,53 @@
+//! `#[serde(with = ...)]` helpers for keys, hashes and signatures, written as
+//! unpadded base64url strings.
+
+pub mod b64_32 {
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    use crate::b64::b64::{from_base64_to_32, to_base64};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8; 32], s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&to_base64(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<[u8; 32], D::Error> {
+        let s = String::deserialize(d)?;
+        from_base64_to_32(&s).map_err(D::Error::custom)
+    }
+}
+
+pub mod b64_64 {
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    use crate::b64::b64::{from_base64_to_64, to_base64};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8; 64], s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&to_base64(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<[u8; 64], D::Error> {
+        let s = String::deserialize(d)?;
+        from_base64_to_64(&s).map_err(D::Error::custom)
+    }
+}
+
+pub mod b64_32_vec {
+    use serde::{Deserialize, Deserializer, Serializer, de::Error, ser::SerializeSeq};
+
+    use crate::b64::b64::{from_base64_to_32, to_base64};
+
+    pub fn serialize<S: Serializer>(items: &[[u8; 32]], s: S) -> Result<S::Ok, S::Error> {
+        let mut seq = s.serialize_seq(Some(items.len()))?;
+        for item in items {
+            seq.serialize_element(&to_base64(item))?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<[u8; 32]>, D::Error> {
+        Vec::<String>::deserialize(d)?
+            .iter()
+            .map(|s| from_base64_to_32(s).map_err(D::Error::custom))
+            .collect()
+    }
+}
@@ -0,0 +1,162 @@
+//! RFC 8949 §4.2 deterministic CBOR.
+//!
+//! Everything we hash or sign goes through here so that any
+//! implementation, Rust or not, lands on the same bytes:
+//!
+//! * integers, lengths and floats use their shortest form
+//! * maps and arrays use definite lengths
+//! * map keys are sorted bytewise by their own canonical encoding
+//! * duplicate map keys are refused
+
+use anyhow::bail;
+use ciborium::value::Value;
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::error;
+
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+const MAJOR_TAG: u8 = 6;
+
+/// Encode any serializable value as deterministic CBOR.
+pub fn to_canonical_cbor<T: Serialize + ?Sized>(value: &T) -> anyhow::Result<Vec<u8>> {
+    let value = Value::serialized(value)?;
+    let mut buf = Vec::new();
+    write_value(&value, &mut buf)?;
+    Ok(buf)
+}
+
+/// Feed the canonical encoding of `value` straight into a hasher.
+pub fn hash_canonical<T: Serialize + ?Sized>(
+    hasher: &mut blake3::Hasher,
+    value: &T,
+) -> anyhow::Result<()> {
+    hasher.update(&to_canonical_cbor(value)?);
+    Ok(())
+}
+
+/// True if `bytes` is exactly one CBOR item already in canonical form.
+pub fn is_canonical(bytes: &[u8]) -> anyhow::Result<bool> {
+    Ok(decode_checked(bytes)?.is_some())
+}
+
+/// Decode `bytes`, refusing anything that isn't canonical CBOR.
+pub fn from_canonical_cbor<T: DeserializeOwned>(bytes: &[u8]) -> anyhow::Result<T> {
+    match decode_checked(bytes)? {
+        Some(value) => Ok(value.deserialized()?),
+        None => bail!(error::E_CBOR_CANON_MISMATCH),
+    }
+}
+
+/// Decode one item and re-encode it; `None` if the bytes differ.
+fn decode_checked(bytes: &[u8]) -> anyhow::Result<Option<Value>> {
+    let mut slice = bytes;
+    let value: Value = ciborium::de::from_reader(&mut slice)?;
+    if !slice.is_empty() {
+        // trailing garbage is never canonical
+        return Ok(None);
+    }
+    let mut buf = Vec::with_capacity(bytes.len());
+    if write_value(&value, &mut buf).is_err() || buf != bytes {
+        return Ok(None);
+    }
+    Ok(Some(value))
+}
+
+fn write_value(value: &Value, out: &mut Vec<u8>) -> anyhow::Result<()> {
+    match value {
+        Value::Array(items) => {
+            write_head(out, MAJOR_ARRAY, items.len() as u64);
+            for item in items {
+                write_value(item, out)?;
+            }
+        }
+        Value::Map(entries) => {
+            let mut encoded = Vec::with_capacity(entries.len());
+            for (k, v) in entries {
+                let mut key = Vec::new();
+                write_value(k, &mut key)?;
+                encoded.push((key, v));
+            }
+            encoded.sort_by(|a, b| a.0.cmp(&b.0));
+            if encoded.windows(2).any(|w| w[0].0 == w[1].0) {
+                bail!("{}: duplicate map key", error::E_CBOR_CANON_MISMATCH);
+            }
+            write_head(out, MAJOR_MAP, encoded.len() as u64);
+            for (key, v) in encoded {
+                out.extend_from_slice(&key);
+                write_value(v, out)?;
+            }
+        }
+        Value::Tag(tag, inner) => {
+            write_head(out, MAJOR_TAG, *tag);
+            write_value(inner, out)?;
+        }
+        // Scalars: ciborium already emits the shortest head and the
+        // shortest lossless float, so hand them over as-is.
+        scalar => ciborium::ser::into_writer(scalar, &mut *out)?,
+    }
+    Ok(())
+}
+
+/// Write a major type head with its argument in the shortest form.
+fn write_head(out: &mut Vec<u8>, major: u8, arg: u64) {
+    let major = major << 5;
+    match arg {
+        0..=23 => out.push(major | arg as u8),
+        24..=0xff => {
+            out.push(major | 24);
+            out.push(arg as u8);
+        }
+        0x100..=0xffff => {
+            out.push(major | 25);
+            out.extend_from_slice(&(arg as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(major | 26);
+            out.extend_from_slice(&(arg as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(major | 27);
+            out.extend_from_slice(&arg.to_be_bytes());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn map_keys_sorted_by_encoding() {
+        // shorter keys sort first, then bytewise
+        let a = to_canonical_cbor(&json!({ "bb": 1, "a": 2, "c": 3 })).unwrap();
+        let b = to_canonical_cbor(&json!({ "c": 3, "bb": 1, "a": 2 })).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(
+            a,
+            vec![
+                0xa3, 0x61, b'a', 0x02, 0x61, b'c', 0x03, 0x62, b'b', b'b', 0x01
+            ]
+        );
+    }
+
+    #[test]
+    fn shortest_forms() {
+        assert_eq!(to_canonical_cbor(&23u64).unwrap(), vec![0x17]);
+        assert_eq!(to_canonical_cbor(&24u64).unwrap(), vec![0x18, 0x18]);
+        assert_eq!(to_canonical_cbor(&1.5f64).unwrap(), vec![0xf9, 0x3e, 0x00]);
+    }
+
+    #[test]
+    fn rejects_non_canonical() {
+        // 1 encoded with a one-byte argument
+        assert!(!is_canonical(&[0x18, 0x01]).unwrap());
+        // indefinite-length array
+        assert!(!is_canonical(&[0x9f, 0x01, 0xff]).unwrap());
+        // unsorted map {"b": 1, "a": 2}
+        assert!(!is_canonical(&[0xa2, 0x61, b'b', 0x01, 0x61, b'a', 0x02]).unwrap());
+        assert!(is_canonical(&[0xa2, 0x61, b'a', 0x02, 0x61, b'b', 0x01]).unwrap());
+    }
+}
@@ -1,5 +1,13 @@
+use std::sync::Arc;
+
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    Key,
+    keymaster::signer::SignerSpec,
+    time::clock::{Clock, SystemClock},
+};
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
     pub host: String,
@@ -8,8 +16,23 @@ pub struct Config {
     pub fs_dir: String,
     pub data_dir: String,
     pub cache_db: String,
-    pub hot_keys: Vec<[u8; 32]>,
+    /// Where records are kept: `dir` (under `fs_dir`), `sqlite` (in
+    /// `store_db`) or `memory`
+    pub store: String,
+    pub store_db: String,
+    /// Appends in flight; apart from `fs_dir` so no scope can collide
+    pub journal_dir: String,
+    /// Signing keys from every configured backend. Never serialized.
+    #[serde(skip)]
+    pub hot_keys: Vec<Key>,
     pub verbose: bool,
+    /// Wall time for stamping and checking `context.at`
+    #[serde(skip, default = "system_clock")]
+    pub clock: Arc<dyn Clock>,
+}
+
+fn system_clock() -> Arc<dyn Clock> {
+    Arc::new(SystemClock)
 }
 
 impl Config {
@@ -21,8 +44,12 @@ impl Config {
             fs_dir: "".to_string(),
             data_dir: "".to_string(),
             cache_db: "".to_string(),
+            store: "dir".to_string(),
+            store_db: "".to_string(),
+            journal_dir: "".to_string(),
             hot_keys: Vec::new(),
             verbose: false,
+            clock: system_clock(),
         }
     }
 }
@@ -35,7 +62,11 @@ pub struct ConfigFile {
     pub fs_dir: Option<String>,
     pub data_dir: Option<String>,
     pub cache_db: Option<String>,
+    pub store: Option<String>,
+    pub store_db: Option<String>,
+    pub journal_dir: Option<String>,
     pub hot_keys: Option<Vec<String>>,
     pub cold_keys: Option<Vec<String>>,
+    pub signers: Option<Vec<SignerSpec>>,
     pub verbose: Option<bool>,
 }
@@ -46,6 +46,8 @@ pub const E_USHER_MISMATCH: &str = "E_USHER_MISMATCH";
 pub const E_QUORUM_KEY_DECODE: &str = "E_QUORUM_KEY_DECODE";
 pub const E_KEY_FORMAT_INVALID: &str = "E_KEY_FORMAT_INVALID";
 pub const E_KEY_REVOKED: &str = "E_KEY_REVOKED";
+pub const E_DELEGATION_INVALID: &str = "E_DELEGATION_INVALID";
+pub const E_DELEGATION_EXPIRED: &str = "E_DELEGATION_EXPIRED";
 pub const E_ROLE_NOT_PERMITTED: &str = "E_ROLE_NOT_PERMITTED";
 pub const E_APPEND_ROLES_EMPTY: &str = "E_APPEND_ROLES_EMPTY";
 pub const E_APPEND_DENIED: &str = "E_APPEND_DENIED";
@@ -81,6 +83,8 @@ pub const E_TIME_SKEW_PAST: &str = "E_TIME_SKEW_PAST";
 pub const E_TIME_SKEW_FUTURE: &str = "E_TIME_SKEW_FUTURE";
 pub const E_TIME_WINDOW_EXPIRED: &str = "E_TIME_WINDOW_EXPIRED";
 pub const E_TIME_WINDOW_NOT_YET_VALID: &str = "E_TIME_WINDOW_NOT_YET_VALID";
+pub const E_TIME_PARSE: &str = "E_TIME_PARSE"; // GT notation not turn.micromarks
+pub const E_EPOCH_UNKNOWN: &str = "E_EPOCH_UNKNOWN"; // no genesis epoch for scope
 pub const E_HEARTBEAT_DRIFT_EXCEEDED: &str = "E_HEARTBEAT_DRIFT_EXCEEDED";
 pub const E_HEARTBEAT_QUORUM_MISSED: &str = "E_HEARTBEAT_QUORUM_MISSED";
 
@@ -99,6 +103,7 @@ pub const E_NONCE_REUSED: &str = "E_NONCE_REUSED";
 pub const E_NONCE_UNAVALIBLE: &str = "E_NONCE_UNAVALIBLE";
 pub const E_DUPLICATE_SIGNATURE: &str = "E_DUPLICATE_SIGNATURE";
 pub const E_SPACIAL_MISSING: &str = "E_SPACIAL_MISSING";
+pub const E_SPACIAL_OUT_OF_BOUNDS: &str = "E_SPACIAL_OUT_OF_BOUNDS";
 
 //// ───────────────────────── Chain / Ledger Semantics ───────────────────
 
@@ -118,6 +123,9 @@ pub const E_FORK_DETECTED: &str = "E_FORK_DETECTED";
 pub const E_FORK_BLOCKED_BY_POLICY: &str = "E_FORK_BLOCKED_BY_POLICY";
 pub const E_NOT_LEAF_APPEND: &str = "E_NOT_LEAF_APPEND"; // trying to append when previous has a child
 pub const E_ALREADY_APPLIED: &str = "E_ALREADY_APPLIED";
+pub const E_CHECKPOINT_NOT_FOUND: &str = "E_CHECKPOINT_NOT_FOUND";
+pub const E_CHECKPOINT_INVALID: &str = "E_CHECKPOINT_INVALID";
+pub const E_MERKLE_PROOF_INVALID: &str = "E_MERKLE_PROOF_INVALID";
 
 //// ───────────────────────── Storage / Index / DB ───────────────────────
 
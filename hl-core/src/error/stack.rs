@@ -1,4 +1,8 @@
-use crate::{Key, Rhex, Signature, rhex::signature::SigType};
+use crate::{
+    Key, Rhex, Signature,
+    rhex::signature::SigType,
+    time::clock::{Clock, GTClock},
+};
 use serde_json::json;
 
 pub struct ErrorStack {
@@ -19,6 +23,8 @@ impl ErrorStack {
         scope: &str,
         key: &Key,
         target_key: &[u8; 32],
+        gt: &GTClock,
+        clock: &dyn Clock,
     ) -> Result<Rhex, anyhow::Error> {
         let mut rhex = Rhex::new();
         rhex.intent.previous_hash = Some([255u8; 32]);
@@ -30,7 +36,7 @@ impl ErrorStack {
             "codes": self.codes,
             "messages": self.messages,
         });
-        rhex.add_context(&None)?;
+        rhex.add_context(gt, clock, &None)?;
         let author_sig = Signature {
             sig_type: SigType::Author,
             public_key: key.pk.unwrap_or([0u8; 32]),
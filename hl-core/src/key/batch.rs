@@ -0,0 +1,63 @@
+use ed25519_dalek::{Signature, VerifyingKey};
+
+/// One Ed25519 signature over a 32-byte preimage.
+#[derive(Debug, Clone, Copy)]
+pub struct BatchItem {
+    pub public_key: [u8; 32],
+    pub message: [u8; 32],
+    pub sig: [u8; 64],
+}
+
+/// Check every item at once with `ed25519_dalek::verify_batch`. False
+/// says at least one fails, not which.
+///
+/// The batch equation is looser than `verify_strict` about small-order
+/// components, so weak keys fail the batch outright and the caller's
+/// per-signature fallback gets the final say.
+pub fn verify_batch(items: &[BatchItem]) -> bool {
+    let mut keys = Vec::with_capacity(items.len());
+    for item in items {
+        match VerifyingKey::from_bytes(&item.public_key) {
+            Ok(key) if !key.is_weak() => keys.push(key),
+            _ => return false,
+        }
+    }
+    let messages: Vec<&[u8]> = items.iter().map(|item| &item.message[..]).collect();
+    let signatures: Vec<Signature> = items
+        .iter()
+        .map(|item| Signature::from_bytes(&item.sig))
+        .collect();
+    ed25519_dalek::verify_batch(&messages, &signatures, &keys).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::secret::SecretKey;
+
+    #[test]
+    fn batch_agrees_with_single_checks() {
+        let mut items: Vec<BatchItem> = (0..20u8)
+            .map(|i| {
+                let sk = SecretKey::generate();
+                let message = [i; 32];
+                BatchItem {
+                    public_key: sk.public_key(),
+                    message,
+                    sig: sk.sign(&message),
+                }
+            })
+            .collect();
+        assert!(verify_batch(&items));
+        assert!(verify_batch(&[]));
+
+        items[7].message[0] ^= 1;
+        assert!(!verify_batch(&items));
+
+        // The identity is a small-order key
+        let mut weak = items[0];
+        weak.public_key = [0u8; 32];
+        weak.public_key[0] = 1;
+        assert!(!verify_batch(&[weak]));
+    }
+}
@@ -0,0 +1,109 @@
+use crate::b32::b32::{from_base32_crockford, to_base32_crockford};
+
+/// Prefix of the current fingerprint format, bumped if the digest changes.
+pub const FINGERPRINT_PREFIX: &str = "HK1";
+
+const CONTEXT: &str = "HodeauxLedger key fingerprint v1";
+const FINGERPRINT_BYTES: usize = 10;
+
+const ART_WIDTH: usize = 17;
+const ART_HEIGHT: usize = 9;
+const ART_SYMBOLS: &[u8] = b" .o+=*BOX@%&#/^";
+
+fn digest(pk: &[u8; 32]) -> [u8; 32] {
+    blake3::derive_key(CONTEXT, pk)
+}
+
+/// Short, versioned name for a public key, e.g. `HK1-7Q2M-C4ZD-0W8N-VR3K`.
+pub fn fingerprint(pk: &[u8; 32]) -> String {
+    let encoded = to_base32_crockford(&digest(pk)[..FINGERPRINT_BYTES]);
+    let groups: Vec<&str> = encoded
+        .as_bytes()
+        .chunks(4)
+        .map(|g| std::str::from_utf8(g).unwrap_or_default())
+        .collect();
+    format!("{}-{}", FINGERPRINT_PREFIX, groups.join("-"))
+}
+
+/// Whether `fp` (any case, dashes optional) is `pk`'s fingerprint.
+pub fn matches(fp: &str, pk: &[u8; 32]) -> bool {
+    parse(fp).is_ok_and(|bytes| bytes == digest(pk)[..FINGERPRINT_BYTES])
+}
+
+fn parse(fp: &str) -> anyhow::Result<Vec<u8>> {
+    let cleaned: String = fp
+        .chars()
+        .filter(|&c| c != '-' && !c.is_whitespace())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    let Some(body) = cleaned.strip_prefix(FINGERPRINT_PREFIX) else {
+        anyhow::bail!("fingerprint must start with {}", FINGERPRINT_PREFIX);
+    };
+    let bytes = from_base32_crockford(body)?;
+    if bytes.len() != FINGERPRINT_BYTES {
+        anyhow::bail!("fingerprint has the wrong length");
+    }
+    Ok(bytes)
+}
+
+/// OpenSSH-style "drunken bishop" art of the key's digest: a 17x9
+/// field walked two bits at a time, framed, one line per row.
+pub fn visual_hash(pk: &[u8; 32]) -> String {
+    let mut field = [[0usize; ART_WIDTH]; ART_HEIGHT];
+    let (mut x, mut y) = (ART_WIDTH / 2, ART_HEIGHT / 2);
+    let start = (x, y);
+    for byte in digest(pk)[..16].iter() {
+        for step in 0..4 {
+            let bits = byte >> (step * 2);
+            x = if bits & 1 == 1 {
+                (x + 1).min(ART_WIDTH - 1)
+            } else {
+                x.saturating_sub(1)
+            };
+            y = if bits & 2 == 2 {
+                (y + 1).min(ART_HEIGHT - 1)
+            } else {
+                y.saturating_sub(1)
+            };
+            field[y][x] += 1;
+        }
+    }
+
+    let title = format!("[{}]", FINGERPRINT_PREFIX);
+    let mut lines = vec![format!("+{:-^width$}+", title, width = ART_WIDTH)];
+    for (row, cells) in field.iter().enumerate() {
+        let line: String = cells
+            .iter()
+            .enumerate()
+            .map(|(col, &hits)| match (col, row) {
+                pos if pos == (x, y) => 'E',
+                pos if pos == start => 'S',
+                _ => ART_SYMBOLS[hits.min(ART_SYMBOLS.len() - 1)] as char,
+            })
+            .collect();
+        lines.push(format!("|{}|", line));
+    }
+    lines.push(format!("+{:-^width$}+", "[BLAKE3]", width = ART_WIDTH));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint_round_trips_and_art_is_stable() {
+        let pk = [9u8; 32];
+        let fp = fingerprint(&pk);
+        assert!(fp.starts_with("HK1-"));
+        assert_eq!(fp.len(), 3 + 4 * 5);
+        assert!(matches(&fp.to_lowercase().replace('-', ""), &pk));
+        assert!(!matches(&fp, &[8u8; 32]));
+
+        let art = visual_hash(&pk);
+        assert_eq!(art, visual_hash(&pk));
+        assert_ne!(art, visual_hash(&[8u8; 32]));
+        assert_eq!(art.lines().count(), ART_HEIGHT + 2);
+        assert!(art.lines().all(|l| l.chars().count() == ART_WIDTH + 2));
+    }
+}
@@ -1,10 +1,15 @@
+use std::sync::Arc;
+
 use anyhow::bail;
-use ed25519_dalek::Signer;
-use zeroize::Zeroize;
 
-#[derive(Clone, serde::Serialize, serde::Deserialize)]
+use crate::{key::secret::SecretKey, keymaster::signer::Signer};
+
+/// A public key, and a way to sign for it when we have one. Clones share
+/// the one signer; an in-memory secret is wiped when the last drops.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Key {
-    pub sk: Option<[u8; 32]>,
+    #[serde(skip)]
+    pub sk: Option<Arc<dyn Signer>>,
     pub pk: Option<[u8; 32]>,
 }
 
@@ -20,47 +25,39 @@ impl Key {
         }
     }
 
-    pub fn from_bytes(sk: [u8; 32]) -> Self {
-        let sk = ed25519_dalek::SigningKey::from_bytes(&sk);
-        let pk = sk.verifying_key();
-        let sk_bytes = sk.to_bytes();
-        let pk_bytes = pk.to_bytes();
+    pub fn from_secret(sk: SecretKey) -> Self {
+        Self::from_signer(Arc::new(sk))
+    }
+
+    pub fn from_signer(signer: Arc<dyn Signer>) -> Self {
         Self {
-            sk: Some(sk_bytes),
-            pk: Some(pk_bytes),
+            pk: Some(signer.public_key()),
+            sk: Some(signer),
         }
     }
 
+    /// A fresh random key.
+    pub fn generated() -> Self {
+        Self::from_secret(SecretKey::generate())
+    }
+
     pub fn generate(&mut self) -> Result<(), anyhow::Error> {
-        let mut buf = [0u8; 32];
-        getrandom::fill(&mut buf).expect("failed to fill buffer");
-        let sk = ed25519_dalek::SigningKey::from_bytes(&buf);
-        let pk = sk.verifying_key();
-        let sk_bytes = sk.to_bytes();
-        let pk_bytes = pk.to_bytes();
-        self.sk = Some(sk_bytes);
-        self.pk = Some(pk_bytes);
+        *self = Self::generated();
         Ok(())
     }
 
     pub fn sign(&self, hash: &[u8; 32]) -> Result<[u8; 64], anyhow::Error> {
-        if self.sk.is_none() {
-            bail!("Key has no secret key for signing");
+        match &self.sk {
+            Some(sk) => sk.sign(hash),
+            None => bail!("Key has no secret key for signing"),
         }
-        let sk = ed25519_dalek::SigningKey::from_bytes(&self.sk.unwrap());
-        let signature: ed25519_dalek::Signature = sk.sign(hash);
-        let mut sig_bytes = [0u8; 64];
-        sig_bytes.copy_from_slice(&signature.to_bytes());
-        Ok(sig_bytes)
     }
 
-    pub fn zero(&self) -> Result<(), anyhow::Error> {
-        if self.sk.is_none() {
+    /// Let go of the signer. A secret is wiped once no clone still holds it.
+    pub fn zero(&mut self) -> Result<(), anyhow::Error> {
+        if self.sk.take().is_none() {
             bail!("Key has no secret key to zero");
         }
-        // Use zeroize crate to zero out the secret key
-        let mut sk_bytes = self.sk.unwrap();
-        sk_bytes.zeroize();
         Ok(())
     }
 
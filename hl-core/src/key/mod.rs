@@ -1 +1,5 @@
+pub mod batch;
+pub mod fingerprint;
 pub mod key;
+pub mod secret;
+pub mod shamir;
@@ -0,0 +1,62 @@
+use std::fmt;
+
+use ed25519_dalek::{Signer, SigningKey};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// An Ed25519 secret key. Not `Copy` or `Clone`, wiped on drop, and
+/// redacted in `Debug`; share one through [`crate::Key`].
+pub struct SecretKey(SigningKey);
+
+// The wipe on drop is SigningKey's own; fail the build if that goes away.
+const _: fn() = || {
+    fn wipes_on_drop<T: ZeroizeOnDrop>() {}
+    wipes_on_drop::<SigningKey>();
+};
+
+impl SecretKey {
+    /// Takes the bytes and wipes the caller's copy.
+    pub fn from_bytes(bytes: &mut [u8; 32]) -> Self {
+        let sk = SigningKey::from_bytes(bytes);
+        bytes.zeroize();
+        Self(sk)
+    }
+
+    pub fn generate() -> Self {
+        let mut buf = [0u8; 32];
+        getrandom::fill(&mut buf).expect("failed to fill buffer");
+        Self::from_bytes(&mut buf)
+    }
+
+    pub fn public_key(&self) -> [u8; 32] {
+        self.0.verifying_key().to_bytes()
+    }
+
+    pub fn sign(&self, hash: &[u8; 32]) -> [u8; 64] {
+        self.0.sign(hash).to_bytes()
+    }
+
+    /// The raw secret, for writing it to a keystore. Don't hold on to it.
+    pub fn expose_secret(&self) -> &[u8; 32] {
+        self.0.as_bytes()
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretKey(<redacted>)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wipes_source_and_redacts() {
+        let mut bytes = [7u8; 32];
+        let sk = SecretKey::from_bytes(&mut bytes);
+        assert_eq!(bytes, [0u8; 32]);
+        assert_eq!(sk.expose_secret(), &[7u8; 32]);
+        assert_eq!(format!("{:?}", sk), "SecretKey(<redacted>)");
+    }
+}
@@ -0,0 +1,192 @@
+use std::fmt;
+
+use zeroize::Zeroize;
+
+use crate::key::secret::SecretKey;
+
+/// One M-of-N Shamir share of an Ed25519 secret, split bytewise over
+/// GF(2^8). `group` ties shares to the key they came from; wiped on drop.
+pub struct Share {
+    pub group: [u8; 4],
+    pub threshold: u8,
+    pub index: u8,
+    data: [u8; 32],
+}
+
+/// Serialized share length: group, threshold, index, data.
+pub const SHARE_LEN: usize = 4 + 1 + 1 + 32;
+
+/// First four bytes of the public key's blake3, shared by every share
+/// of that key.
+pub fn group_of(pk: &[u8; 32]) -> [u8; 4] {
+    let hash = blake3::hash(pk);
+    let mut group = [0u8; 4];
+    group.copy_from_slice(&hash.as_bytes()[..4]);
+    group
+}
+
+/// Split `sk` into `n` shares, any `m` of which rebuild it.
+pub fn split(sk: &SecretKey, m: u8, n: u8) -> anyhow::Result<Vec<Share>> {
+    if m == 0 || m > n {
+        anyhow::bail!("threshold must be between 1 and {}, got {}", n, m);
+    }
+    let group = group_of(&sk.public_key());
+    let secret = sk.expose_secret();
+    let mut shares: Vec<Share> = (1..=n)
+        .map(|index| Share {
+            group,
+            threshold: m,
+            index,
+            data: [0u8; 32],
+        })
+        .collect();
+    // One random polynomial of degree m-1 per byte, constant term the secret
+    let mut coeffs = vec![0u8; m as usize];
+    for (i, byte) in secret.iter().enumerate() {
+        coeffs[0] = *byte;
+        getrandom::fill(&mut coeffs[1..])
+            .map_err(|e| anyhow::anyhow!("failed to gather randomness: {}", e))?;
+        for share in shares.iter_mut() {
+            share.data[i] = eval(&coeffs, share.index);
+        }
+    }
+    coeffs.zeroize();
+    Ok(shares)
+}
+
+/// Rebuild the secret from at least `threshold` shares of one key.
+pub fn combine(shares: &[Share]) -> anyhow::Result<SecretKey> {
+    let Some(first) = shares.first() else {
+        anyhow::bail!("no shares given");
+    };
+    for (i, share) in shares.iter().enumerate() {
+        if share.group != first.group || share.threshold != first.threshold {
+            anyhow::bail!("share {} belongs to a different key", share.index);
+        }
+        if share.index == 0 || shares[..i].iter().any(|s| s.index == share.index) {
+            anyhow::bail!("share index {} is invalid or repeated", share.index);
+        }
+    }
+    let used = &shares[..(first.threshold as usize).min(shares.len())];
+    if used.len() < first.threshold as usize {
+        anyhow::bail!(
+            "need {} shares, only {} given",
+            first.threshold,
+            shares.len()
+        );
+    }
+    // Lagrange interpolation at x = 0
+    let mut secret = [0u8; 32];
+    for share in used {
+        let mut basis = 1u8;
+        for other in used {
+            if other.index != share.index {
+                basis = mul(basis, mul(other.index, inv(other.index ^ share.index)));
+            }
+        }
+        for (byte, y) in secret.iter_mut().zip(share.data.iter()) {
+            *byte ^= mul(*y, basis);
+        }
+    }
+    let sk = SecretKey::from_bytes(&mut secret);
+    if group_of(&sk.public_key()) != first.group {
+        anyhow::bail!("shares did not rebuild the key they were split from");
+    }
+    Ok(sk)
+}
+
+impl Share {
+    pub fn to_bytes(&self) -> [u8; SHARE_LEN] {
+        let mut out = [0u8; SHARE_LEN];
+        out[..4].copy_from_slice(&self.group);
+        out[4] = self.threshold;
+        out[5] = self.index;
+        out[6..].copy_from_slice(&self.data);
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        if bytes.len() != SHARE_LEN {
+            anyhow::bail!("share must be {} bytes, got {}", SHARE_LEN, bytes.len());
+        }
+        let mut share = Share {
+            group: [0u8; 4],
+            threshold: bytes[4],
+            index: bytes[5],
+            data: [0u8; 32],
+        };
+        share.group.copy_from_slice(&bytes[..4]);
+        share.data.copy_from_slice(&bytes[6..]);
+        Ok(share)
+    }
+}
+
+impl Drop for Share {
+    fn drop(&mut self) {
+        self.data.zeroize();
+    }
+}
+
+impl fmt::Debug for Share {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Share({}-of-n #{}, <redacted>)",
+            self.threshold, self.index
+        )
+    }
+}
+
+/// Horner's rule over GF(2^8).
+fn eval(coeffs: &[u8], x: u8) -> u8 {
+    coeffs.iter().rev().fold(0u8, |acc, c| mul(acc, x) ^ c)
+}
+
+/// GF(2^8) multiply, AES polynomial, no data-dependent branches.
+fn mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    for _ in 0..8 {
+        product ^= a & 0u8.wrapping_sub(b & 1);
+        let carry = 0u8.wrapping_sub(a >> 7);
+        a = (a << 1) ^ (0x1b & carry);
+        b >>= 1;
+    }
+    product
+}
+
+/// a^254 = a^-1 in GF(2^8).
+fn inv(a: u8) -> u8 {
+    let mut result = 1u8;
+    let mut base = a;
+    let mut exp = 254u8;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul(result, base);
+        }
+        base = mul(base, base);
+        exp >>= 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn any_m_shares_rebuild_the_key() {
+        let sk = SecretKey::generate();
+        let shares = split(&sk, 3, 5).unwrap();
+        let pick = |ix: &[usize]| -> Vec<Share> {
+            ix.iter()
+                .map(|&i| Share::from_bytes(&shares[i].to_bytes()).unwrap())
+                .collect()
+        };
+        for ix in [[0, 1, 2], [4, 2, 0], [1, 3, 4]] {
+            let rebuilt = combine(&pick(&ix)).unwrap();
+            assert_eq!(rebuilt.expose_secret(), sk.expose_secret());
+        }
+        assert!(combine(&pick(&[0, 1])).is_err());
+        assert!(combine(&pick(&[0, 0, 1])).is_err());
+    }
+}
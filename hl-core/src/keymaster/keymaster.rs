@@ -13,20 +13,18 @@ impl Keymaster {
         }
     }
 
-    pub fn load_keys(&mut self, list: &Vec<[u8; 32]>) -> Result<(), anyhow::Error> {
+    pub fn load_keys(&mut self, list: &[Key]) -> Result<(), anyhow::Error> {
         for key in list {
-            let working_key = Key::from_bytes(*key);
-            self.hot_keys.push(working_key);
+            self.hot_keys.push(key.clone());
         }
         Ok(())
     }
 
-    pub fn get_matching(&self, pk: &[u8; 32]) -> Result<[u8; 32], anyhow::Error> {
+    /// The hot key for `pk`, secret included.
+    pub fn get_matching(&self, pk: &[u8; 32]) -> Result<Key, anyhow::Error> {
         for key in self.hot_keys.iter() {
-            if let Some(key_pk) = key.pk {
-                if &key_pk == pk {
-                    return Ok(key.sk.unwrap_or([0u8; 32]));
-                }
+            if key.pk.as_ref() == Some(pk) && key.sk.is_some() {
+                return Ok(key.clone());
             }
         }
         Err(anyhow::anyhow!("No matching key found"))
@@ -53,8 +51,8 @@ impl Keymaster {
         Err(anyhow::anyhow!("No primary key set"))
     }
 
-    pub fn zero(&self, pk: &[u8; 32]) -> Result<(), anyhow::Error> {
-        for key in self.hot_keys.iter() {
+    pub fn zero(&mut self, pk: &[u8; 32]) -> Result<(), anyhow::Error> {
+        for key in self.hot_keys.iter_mut() {
             if let Some(key_pk) = key.pk {
                 if &key_pk == pk {
                     key.zero()?;
@@ -66,9 +64,6 @@ impl Keymaster {
     }
 
     pub fn zero_all(&mut self) -> Result<(), anyhow::Error> {
-        for key in self.hot_keys.iter() {
-            key.zero()?;
-        }
         self.hot_keys = Vec::new();
         Ok(())
     }
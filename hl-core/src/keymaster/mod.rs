@@ -1 +1,2 @@
 pub mod keymaster;
+pub mod signer;
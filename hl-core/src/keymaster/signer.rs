@@ -0,0 +1,35 @@
+use std::fmt::Debug;
+
+use serde::{Deserialize, Serialize};
+
+use crate::key::secret::SecretKey;
+
+/// Something that can sign for one public key. The secret may live in
+/// this process or somewhere else entirely.
+pub trait Signer: Debug + Send + Sync {
+    fn public_key(&self) -> [u8; 32];
+    fn sign(&self, hash: &[u8; 32]) -> anyhow::Result<[u8; 64]>;
+}
+
+impl Signer for SecretKey {
+    fn public_key(&self) -> [u8; 32] {
+        SecretKey::public_key(self)
+    }
+
+    fn sign(&self, hash: &[u8; 32]) -> anyhow::Result<[u8; 64]> {
+        Ok(SecretKey::sign(self, hash))
+    }
+}
+
+/// Where a config's signing keys come from.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SignerSpec {
+    /// Raw 32-byte secret key file
+    Hot { path: String },
+    /// Password-encrypted HKYV file, unlocked at startup with the password
+    /// in environment variable `password_env`
+    Hkyv { path: String, password_env: String },
+    /// Every key held by the `hl-agent` listening on `socket`
+    Agent { socket: String },
+}
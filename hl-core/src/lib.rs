@@ -1,14 +1,17 @@
 pub mod authority;
 pub mod b32;
 pub mod b64;
+pub mod cbor;
 pub mod config;
 pub mod error;
 pub mod key;
 pub mod keymaster;
+pub mod merkle;
 pub mod policy;
 pub mod rhex;
 pub mod schema;
 pub mod scope;
+pub mod spatial;
 pub mod time;
 pub mod usher;
 
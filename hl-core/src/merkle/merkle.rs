@@ -0,0 +1,243 @@
+//! Merkle trees over a scope's `current_hash` sequence.
+//!
+//! Shaped like RFC 9162 (Certificate Transparency v2): leaves and inner
+//! nodes are hashed with distinct prefixes, and a tree of `n` leaves
+//! splits at the largest power of two below `n`. Hashing is blake3.
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Rhex,
+    b64::serde::{b64_32, b64_32_vec},
+    error,
+    rhex::{
+        payload::Checkpoint,
+        signature::{SigCheck, SigType},
+    },
+};
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+pub fn leaf_hash(record_hash: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[LEAF_PREFIX]);
+    hasher.update(record_hash);
+    *hasher.finalize().as_bytes()
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    *hasher.finalize().as_bytes()
+}
+
+/// Largest power of two strictly below `n` (n > 1).
+fn split(n: usize) -> usize {
+    let mut k = 1;
+    while k << 1 < n {
+        k <<= 1;
+    }
+    k
+}
+
+/// Root over `record_hashes`, in chain order.
+pub fn merkle_root(record_hashes: &[[u8; 32]]) -> [u8; 32] {
+    match record_hashes.len() {
+        0 => *blake3::hash(&[]).as_bytes(),
+        1 => leaf_hash(&record_hashes[0]),
+        n => {
+            let k = split(n);
+            node_hash(
+                &merkle_root(&record_hashes[..k]),
+                &merkle_root(&record_hashes[k..]),
+            )
+        }
+    }
+}
+
+/// Proof that `leaf` is record `index` of a tree of `size` records.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InclusionProof {
+    pub index: u64,
+    pub size: u64,
+    /// `current_hash` of the record being proven
+    #[serde(with = "b64_32")]
+    pub leaf: [u8; 32],
+    /// Sibling hashes, leaf to root
+    #[serde(with = "b64_32_vec")]
+    pub path: Vec<[u8; 32]>,
+}
+
+impl InclusionProof {
+    /// Build a proof for record `index` of `record_hashes`.
+    pub fn new(record_hashes: &[[u8; 32]], index: usize) -> anyhow::Result<Self> {
+        if index >= record_hashes.len() {
+            bail!(
+                "{}: record {} is not among the {} covered",
+                error::E_MERKLE_PROOF_INVALID,
+                index,
+                record_hashes.len()
+            );
+        }
+        Ok(Self {
+            index: index as u64,
+            size: record_hashes.len() as u64,
+            leaf: record_hashes[index],
+            path: audit_path(index, record_hashes),
+        })
+    }
+
+    /// Does this proof lead to `root`?
+    pub fn verify(&self, root: &[u8; 32]) -> bool {
+        if self.index >= self.size {
+            return false;
+        }
+        let mut fnode = self.index;
+        let mut snode = self.size - 1;
+        let mut r = leaf_hash(&self.leaf);
+        for p in self.path.iter() {
+            if snode == 0 {
+                return false;
+            }
+            if fnode & 1 == 1 || fnode == snode {
+                r = node_hash(p, &r);
+                while fnode & 1 == 0 && fnode != 0 {
+                    fnode >>= 1;
+                    snode >>= 1;
+                }
+            } else {
+                r = node_hash(&r, p);
+            }
+            fnode >>= 1;
+            snode >>= 1;
+        }
+        snode == 0 && &r == root
+    }
+}
+
+fn audit_path(index: usize, record_hashes: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    let n = record_hashes.len();
+    if n <= 1 {
+        return Vec::new();
+    }
+    let k = split(n);
+    let mut path;
+    if index < k {
+        path = audit_path(index, &record_hashes[..k]);
+        path.push(merkle_root(&record_hashes[k..]));
+    } else {
+        path = audit_path(index - k, &record_hashes[k..]);
+        path.push(merkle_root(&record_hashes[..k]));
+    }
+    path
+}
+
+/// Offline check for light clients: `checkpoint` is an intact,
+/// finalized `scope:checkpoint` carrying at least `quorum_k` valid
+/// quorum signatures from `quorum_keys`, and `proof` leads to its root.
+pub fn verify_inclusion(
+    proof: &InclusionProof,
+    checkpoint: &Rhex,
+    quorum_keys: &[[u8; 32]],
+    quorum_k: usize,
+) -> anyhow::Result<()> {
+    if checkpoint.intent.record_type != "scope:checkpoint" {
+        bail!(
+            "{}: {} is not a checkpoint",
+            error::E_CHECKPOINT_INVALID,
+            checkpoint.intent.record_type
+        );
+    }
+    if checkpoint.current_hash != Some(checkpoint.generate_current_hash()?) {
+        bail!(
+            "{}: checkpoint current_hash does not match its contents",
+            error::E_CHECKPOINT_INVALID
+        );
+    }
+    let mut signers: Vec<[u8; 32]> = Vec::new();
+    for sig in checkpoint.signatures.iter() {
+        if sig.sig_type == SigType::Quorum
+            && quorum_keys.contains(&sig.public_key)
+            && !signers.contains(&sig.public_key)
+            && checkpoint.verify_signature(sig)? == SigCheck::Valid
+        {
+            signers.push(sig.public_key);
+        }
+    }
+    if signers.len() < quorum_k {
+        bail!(
+            "{}: checkpoint has {} of {} trusted quorum signatures",
+            error::E_QUORUM_INSUFFICIENT,
+            signers.len(),
+            quorum_k
+        );
+    }
+    let covered: Checkpoint = checkpoint.payload()?;
+    if proof.size != covered.size || !proof.verify(&covered.root) {
+        bail!(
+            "{}: record is not under this checkpoint's root",
+            error::E_MERKLE_PROOF_INVALID
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Key;
+
+    fn hashes(n: u8) -> Vec<[u8; 32]> {
+        (0..n).map(|i| [i; 32]).collect()
+    }
+
+    #[test]
+    fn every_leaf_proves() {
+        for n in 1..=9u8 {
+            let leaves = hashes(n);
+            let root = merkle_root(&leaves);
+            for i in 0..n as usize {
+                let proof = InclusionProof::new(&leaves, i).unwrap();
+                assert!(proof.verify(&root), "n={} i={}", n, i);
+                let mut wrong = proof.clone();
+                wrong.leaf = [99; 32];
+                assert!(!wrong.verify(&root));
+            }
+        }
+        assert!(InclusionProof::new(&hashes(3), 3).is_err());
+    }
+
+    #[test]
+    fn verify_against_signed_checkpoint() {
+        let usher = Key::generated();
+        let leaves = hashes(5);
+        let mut checkpoint = Rhex::new();
+        checkpoint.intent.author_pk = usher.pk.unwrap();
+        checkpoint.intent.usher_pk = usher.pk.unwrap();
+        checkpoint.intent.record_type = "scope:checkpoint".to_string();
+        checkpoint.intent.data = serde_json::to_value(Checkpoint {
+            root: merkle_root(&leaves),
+            size: 5,
+            last: leaves[4],
+        })
+        .unwrap();
+        checkpoint.sign_author(&usher).unwrap();
+        checkpoint.sign_usher(&usher).unwrap();
+        checkpoint.sign_quorum(&usher).unwrap();
+        checkpoint.finalize().unwrap();
+
+        let proof = InclusionProof::new(&leaves, 2).unwrap();
+        let trusted = [usher.pk.unwrap()];
+        verify_inclusion(&proof, &checkpoint, &trusted, 1).unwrap();
+        assert!(verify_inclusion(&proof, &checkpoint, &trusted, 2).is_err());
+        assert!(verify_inclusion(&proof, &checkpoint, &[[1; 32]], 1).is_err());
+
+        let mut tampered = checkpoint.clone();
+        tampered.intent.data["size"] = 6.into();
+        assert!(verify_inclusion(&proof, &tampered, &trusted, 1).is_err());
+    }
+}
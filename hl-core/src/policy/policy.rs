@@ -21,4 +21,39 @@ impl Policy {
             rules: vec![],
         }
     }
+
+    /// What governs a scope before its genesis lands: the genesis alone,
+    /// quorum signed once.
+    pub fn pre_genesis(scope: &str) -> Self {
+        Self {
+            scope: scope.to_string(),
+            quorum_ttl: 1_000_000_000,
+            rules: vec![authority_rule(scope, "scope:genesis", 1)],
+            ..Self::new()
+        }
+    }
+
+    /// What `scope:genesis` puts in place: its authority may set policy
+    /// and checkpoint the chain, one quorum signature each.
+    pub fn genesis(scope: &str) -> Self {
+        Self {
+            scope: scope.to_string(),
+            quorum_ttl: 1_000_000_000,
+            rules: vec![
+                authority_rule(scope, "policy:set", 90),
+                authority_rule(scope, "scope:checkpoint", 90),
+            ],
+            ..Self::new()
+        }
+    }
+}
+
+fn authority_rule(scope: &str, record_type: &str, rate_per_mark: u32) -> Rule {
+    let mut rule = Rule::new(scope);
+    rule.record_types = vec![record_type.to_string()];
+    rule.append_roles = vec!["authority".to_string()];
+    rule.quorum_k = 1;
+    rule.quorum_roles = vec!["authority".to_string()];
+    rule.rate_per_mark = rate_per_mark;
+    rule
 }
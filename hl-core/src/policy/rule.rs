@@ -21,29 +21,9 @@ impl Rule {
     }
 
     pub fn applies_to(&self, record_type: &str) -> bool {
-        self.record_types.iter().any(|rt| {
-            if rt == "*" {
-                return true; // global wildcard
-            }
-
-            // Split both strings on ':'
-            let mut rec_parts = record_type.splitn(2, ':');
-            let mut rt_parts = rt.splitn(2, ':');
-
-            let rec_main = rec_parts.next().unwrap_or("");
-            let rec_sub = rec_parts.next().unwrap_or("");
-
-            let rt_main = rt_parts.next().unwrap_or("");
-            let rt_sub = rt_parts.next().unwrap_or("");
-
-            // Main type must match
-            if rec_main != rt_main {
-                return false;
-            }
-
-            // Subtype: allow wildcard
-            rt_sub == "*" || rt_sub == rec_sub
-        })
+        self.record_types
+            .iter()
+            .any(|rt| type_matches(rt, record_type))
     }
 
     pub fn can_append(&self, roles: &[&str]) -> bool {
@@ -58,3 +38,28 @@ impl Rule {
             .any(|role| self.append_roles.iter().any(|r| r == role))
     }
 }
+
+/// Whether `pattern` (`*`, `main:*` or `main:sub`) covers `record_type`.
+pub fn type_matches(pattern: &str, record_type: &str) -> bool {
+    if pattern == "*" {
+        return true; // global wildcard
+    }
+
+    // Split both strings on ':'
+    let mut rec_parts = record_type.splitn(2, ':');
+    let mut rt_parts = pattern.splitn(2, ':');
+
+    let rec_main = rec_parts.next().unwrap_or("");
+    let rec_sub = rec_parts.next().unwrap_or("");
+
+    let rt_main = rt_parts.next().unwrap_or("");
+    let rt_sub = rt_parts.next().unwrap_or("");
+
+    // Main type must match
+    if rec_main != rt_main {
+        return false;
+    }
+
+    // Subtype: allow wildcard
+    rt_sub == "*" || rt_sub == rec_sub
+}
@@ -0,0 +1,129 @@
+//! Signature checks for a whole run of records, e.g. a chain replay.
+
+use crate::{
+    Rhex,
+    key::batch::{BatchItem, verify_batch},
+    rhex::signature::SigCheck,
+};
+
+/// Signatures checked per batch. A bad one costs a fallback over this many.
+pub const BATCH_SIZE: usize = 256;
+
+/// A signature that didn't verify: where it is and why.
+#[derive(Debug, Clone)]
+pub struct SigFailure {
+    pub record: usize,
+    pub signature: usize,
+    /// The check's outcome, or why there was nothing to check against.
+    pub outcome: Result<SigCheck, String>,
+}
+
+/// Every signature failure across a run of records.
+#[derive(Debug, Clone, Default)]
+pub struct BatchReport {
+    pub failures: Vec<SigFailure>,
+}
+
+impl BatchReport {
+    pub fn is_clean(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Outcome for one signature; anything not listed verified.
+    pub fn check(&self, record: usize, signature: usize) -> Result<SigCheck, String> {
+        self.failures
+            .iter()
+            .find(|f| f.record == record && f.signature == signature)
+            .map(|f| f.outcome.clone())
+            .unwrap_or(Ok(SigCheck::Valid))
+    }
+
+    /// Whether every signature on `record` verified.
+    pub fn record_ok(&self, record: usize) -> bool {
+        !self.failures.iter().any(|f| f.record == record)
+    }
+}
+
+/// Verify every author, usher and quorum signature on `records`,
+/// [`BATCH_SIZE`] at a time. A batch that fails is re-checked one
+/// signature at a time so the offending records can be named.
+pub fn verify_records(records: &[Rhex]) -> BatchReport {
+    let mut report = BatchReport::default();
+    // (record, signature, item)
+    let mut queued: Vec<(usize, usize, BatchItem)> = Vec::new();
+    for (r, rhex) in records.iter().enumerate() {
+        for (s, sig) in rhex.signatures.iter().enumerate() {
+            let preimage = rhex
+                .version()
+                .and_then(|version| rhex.preimage_for(version, sig.sig_type));
+            match preimage {
+                Ok(message) => queued.push((
+                    r,
+                    s,
+                    BatchItem {
+                        public_key: sig.public_key,
+                        message,
+                        sig: sig.sig,
+                    },
+                )),
+                Err(e) => report.failures.push(SigFailure {
+                    record: r,
+                    signature: s,
+                    outcome: Err(e.to_string()),
+                }),
+            }
+        }
+    }
+
+    for chunk in queued.chunks(BATCH_SIZE) {
+        let items: Vec<BatchItem> = chunk.iter().map(|(_, _, item)| *item).collect();
+        if verify_batch(&items) {
+            continue;
+        }
+        for &(r, s, _) in chunk {
+            let rhex = &records[r];
+            let outcome = rhex
+                .verify_signature(&rhex.signatures[s])
+                .map_err(|e| e.to_string());
+            if !matches!(outcome, Ok(SigCheck::Valid)) {
+                report.failures.push(SigFailure {
+                    record: r,
+                    signature: s,
+                    outcome,
+                });
+            }
+        }
+    }
+    report.failures.sort_by_key(|f| (f.record, f.signature));
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Key;
+
+    fn signed(n: u8, author: &Key, usher: &Key) -> Rhex {
+        let mut rhex = Rhex::new();
+        rhex.intent.author_pk = author.pk.unwrap();
+        rhex.intent.usher_pk = usher.pk.unwrap();
+        rhex.intent.record_type = format!("record:{}", n);
+        rhex.sign_author(author).unwrap();
+        rhex.sign_usher(usher).unwrap();
+        rhex.sign_quorum(usher).unwrap();
+        rhex
+    }
+
+    #[test]
+    fn names_the_bad_record() {
+        let (author, usher) = (Key::generated(), Key::generated());
+        let mut records: Vec<Rhex> = (0..5).map(|n| signed(n, &author, &usher)).collect();
+        assert!(verify_records(&records).is_clean());
+
+        records[3].intent.record_type = "record:tampered".to_string();
+        let report = verify_records(&records);
+        assert!(report.failures.iter().all(|f| f.record == 3));
+        assert!(!report.record_ok(3) && report.record_ok(2));
+        assert_eq!(report.check(3, 0), Ok(SigCheck::Invalid));
+    }
+}
@@ -1,6 +1,8 @@
 use rand::{Rng, distr::Alphanumeric};
 use serde::{Deserialize, Serialize};
 
+use crate::authority::delegation::Delegation;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Intent {
     #[serde(with = "serde_bytes")]
@@ -13,6 +15,10 @@ pub struct Intent {
     pub usher_pk: [u8; 32],
     pub record_type: String,
     pub data: serde_json::Value,
+    /// Certificates linking `author_pk` (or a quorum signer) to a granted
+    /// authority. Left out of the encoding when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delegation: Option<Vec<Delegation>>,
 }
 
 impl Intent {
@@ -25,6 +31,7 @@ impl Intent {
             usher_pk: [0u8; 32],
             record_type: String::new(),
             data: serde_json::Value::Null,
+            delegation: None,
         }
     }
 
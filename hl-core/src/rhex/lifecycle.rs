@@ -0,0 +1,258 @@
+//! Signing lifecycle of a R⬢.
+//!
+//! Signatures go on in a fixed order and each one commits to the ones
+//! before it:
+//!
+//! Draft → AuthorSigned → UsherSigned → Quorum(n) → Finalized
+//!
+//! Every signature addition goes through [`Rhex::add_signature`] (or the
+//! `sign_*` helpers on top of it), which refuses anything out of order.
+
+use std::fmt;
+
+use crate::{
+    Key, Rhex, error,
+    rhex::signature::{SigType, Signature},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Draft,
+    AuthorSigned,
+    UsherSigned,
+    /// Usher signed plus this many quorum signatures.
+    Quorum(u16),
+    Finalized,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignError {
+    /// Existing signatures aren't Author, Usher, Quorum... in that order.
+    OutOfOrder,
+    AlreadySigned(SigType),
+    NotYetSigned(SigType),
+    Finalized,
+    SignerMismatch {
+        role: SigType,
+        expected: [u8; 32],
+        got: [u8; 32],
+    },
+    DuplicateQuorumSigner([u8; 32]),
+    NoSecretKey,
+    UnknownVersion,
+    Preimage(String),
+}
+
+impl SignError {
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            SignError::OutOfOrder => error::E_SIG_INVALID,
+            SignError::AlreadySigned(_) => error::E_DUPLICATE_SIGNATURE,
+            SignError::NotYetSigned(_) => error::E_SIG_MISSING,
+            SignError::Finalized => error::E_ALREADY_APPLIED,
+            SignError::SignerMismatch { role, .. } => match role {
+                SigType::Author => error::E_AUTHOR_MISMATCH,
+                SigType::Usher => error::E_USHER_MISMATCH,
+                SigType::Quorum => error::E_QUORUM_INVALID_MEMBER,
+            },
+            SignError::DuplicateQuorumSigner(_) => error::E_ROLE_QUORUM_DUP_KEYS,
+            SignError::NoSecretKey => error::E_INVALID_ARGUMENT,
+            SignError::UnknownVersion => error::E_MAGIC_BAD,
+            SignError::Preimage(_) => error::E_INTERNAL,
+        }
+    }
+}
+
+impl fmt::Display for SignError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignError::OutOfOrder => write!(f, "signatures are out of order"),
+            SignError::AlreadySigned(role) => write!(f, "{} already signed", role),
+            SignError::NotYetSigned(role) => write!(f, "{} not signed", role),
+            SignError::Finalized => write!(f, "R⬢ is already finalized"),
+            SignError::SignerMismatch { role, .. } => {
+                write!(
+                    f,
+                    "{} signature is not from the key named in the intent",
+                    role
+                )
+            }
+            SignError::DuplicateQuorumSigner(_) => write!(f, "key already signed quorum"),
+            SignError::NoSecretKey => write!(f, "key has no secret key for signing"),
+            SignError::UnknownVersion => write!(f, "unknown R⬢ format version"),
+            SignError::Preimage(e) => write!(f, "could not build preimage: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SignError {}
+
+impl Stage {
+    /// Can a signature of `role` be added at this stage?
+    pub fn accepts(&self, role: SigType) -> Result<(), SignError> {
+        match (self, role) {
+            (Stage::Finalized, _) => Err(SignError::Finalized),
+            (Stage::Draft, SigType::Author) => Ok(()),
+            (Stage::Draft, _) => Err(SignError::NotYetSigned(SigType::Author)),
+            (_, SigType::Author) => Err(SignError::AlreadySigned(SigType::Author)),
+            (Stage::AuthorSigned, SigType::Usher) => Ok(()),
+            (Stage::AuthorSigned, SigType::Quorum) => Err(SignError::NotYetSigned(SigType::Usher)),
+            (_, SigType::Usher) => Err(SignError::AlreadySigned(SigType::Usher)),
+            (_, SigType::Quorum) => Ok(()),
+        }
+    }
+
+    /// Enough signatures to compute `current_hash`.
+    pub fn can_finalize(&self) -> bool {
+        matches!(
+            self,
+            Stage::UsherSigned | Stage::Quorum(_) | Stage::Finalized
+        )
+    }
+}
+
+impl Rhex {
+    /// Where this R⬢ is in its signing lifecycle.
+    pub fn stage(&self) -> Result<Stage, SignError> {
+        let mut sigs = self.signatures.iter();
+        let stage = match (sigs.next(), sigs.next()) {
+            (None, _) => Stage::Draft,
+            (Some(a), None) if a.sig_type == SigType::Author => Stage::AuthorSigned,
+            (Some(a), Some(u)) if a.sig_type == SigType::Author && u.sig_type == SigType::Usher => {
+                let mut seen: Vec<&[u8; 32]> = Vec::new();
+                for q in sigs {
+                    if q.sig_type != SigType::Quorum {
+                        return Err(SignError::OutOfOrder);
+                    }
+                    if seen.contains(&&q.public_key) {
+                        return Err(SignError::DuplicateQuorumSigner(q.public_key));
+                    }
+                    seen.push(&q.public_key);
+                }
+                match seen.len() {
+                    0 => Stage::UsherSigned,
+                    n => Stage::Quorum(n as u16),
+                }
+            }
+            _ => return Err(SignError::OutOfOrder),
+        };
+        if self.current_hash.is_some() {
+            if !stage.can_finalize() {
+                return Err(SignError::OutOfOrder);
+            }
+            return Ok(Stage::Finalized);
+        }
+        Ok(stage)
+    }
+
+    /// Attach an already-made signature, checking it is the next legal
+    /// one and comes from the key the intent names for that role. Does
+    /// not verify the signature bytes; see [`Rhex::verify_signature`].
+    pub fn add_signature(&mut self, sig: Signature) -> Result<Stage, SignError> {
+        let stage = self.stage()?;
+        stage.accepts(sig.sig_type)?;
+        let expected = match sig.sig_type {
+            SigType::Author => Some(self.intent.author_pk),
+            SigType::Usher => Some(self.intent.usher_pk),
+            SigType::Quorum => None,
+        };
+        if let Some(expected) = expected
+            && expected != sig.public_key
+        {
+            return Err(SignError::SignerMismatch {
+                role: sig.sig_type,
+                expected,
+                got: sig.public_key,
+            });
+        }
+        if sig.sig_type == SigType::Quorum
+            && self
+                .signatures
+                .iter()
+                .any(|s| s.sig_type == SigType::Quorum && s.public_key == sig.public_key)
+        {
+            return Err(SignError::DuplicateQuorumSigner(sig.public_key));
+        }
+        self.signatures.push(sig);
+        self.stage()
+    }
+
+    /// Draft → AuthorSigned
+    pub fn sign_author(&mut self, key: &Key) -> Result<Stage, SignError> {
+        self.sign_as(SigType::Author, key)
+    }
+
+    /// AuthorSigned → UsherSigned. Set `context` before calling; it is
+    /// part of what the usher signs.
+    pub fn sign_usher(&mut self, key: &Key) -> Result<Stage, SignError> {
+        self.sign_as(SigType::Usher, key)
+    }
+
+    /// UsherSigned/Quorum(n) → Quorum(n + 1)
+    pub fn sign_quorum(&mut self, key: &Key) -> Result<Stage, SignError> {
+        self.sign_as(SigType::Quorum, key)
+    }
+
+    fn sign_as(&mut self, role: SigType, key: &Key) -> Result<Stage, SignError> {
+        self.stage()?.accepts(role)?;
+        if self.version().is_err() {
+            return Err(SignError::UnknownVersion);
+        }
+        let public_key = key.pk.ok_or(SignError::NoSecretKey)?;
+        let preimage = match role {
+            SigType::Author => self.author_hash(),
+            SigType::Usher => self.usher_hash(&self.signatures[0]),
+            SigType::Quorum => self.quorum_hash(&self.signatures[0], &self.signatures[1]),
+        }
+        .map_err(|e| SignError::Preimage(e.to_string()))?;
+        let sig = key.sign(&preimage).map_err(|_| SignError::NoSecretKey)?;
+        self.add_signature(Signature {
+            sig_type: role,
+            public_key,
+            sig,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walks_the_lifecycle() {
+        let (author, usher, other) = (Key::generated(), Key::generated(), Key::generated());
+        let mut rhex = Rhex::new();
+        rhex.intent.author_pk = author.pk.unwrap();
+        rhex.intent.usher_pk = usher.pk.unwrap();
+        rhex.intent.record_type = "record:data".to_string();
+
+        assert_eq!(rhex.stage(), Ok(Stage::Draft));
+        assert_eq!(
+            rhex.sign_usher(&usher),
+            Err(SignError::NotYetSigned(SigType::Author))
+        );
+        assert!(matches!(
+            rhex.sign_author(&other),
+            Err(SignError::SignerMismatch { .. })
+        ));
+        assert_eq!(rhex.sign_author(&author), Ok(Stage::AuthorSigned));
+        assert_eq!(
+            rhex.sign_author(&author),
+            Err(SignError::AlreadySigned(SigType::Author))
+        );
+        assert!(rhex.finalize().is_err());
+        assert_eq!(rhex.sign_usher(&usher), Ok(Stage::UsherSigned));
+        assert_eq!(rhex.sign_quorum(&usher), Ok(Stage::Quorum(1)));
+        assert_eq!(
+            rhex.sign_quorum(&usher),
+            Err(SignError::DuplicateQuorumSigner(usher.pk.unwrap()))
+        );
+        assert_eq!(rhex.sign_quorum(&other), Ok(Stage::Quorum(2)));
+        rhex.finalize().unwrap();
+        assert_eq!(rhex.stage(), Ok(Stage::Finalized));
+        assert_eq!(rhex.sign_quorum(&author), Err(SignError::Finalized));
+        for sig in rhex.signatures.clone().iter() {
+            assert!(rhex.verify_signature(sig).unwrap().error_code().is_none());
+        }
+    }
+}
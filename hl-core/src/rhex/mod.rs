@@ -1,5 +1,8 @@
+pub mod batch;
 pub mod context;
 pub mod intent;
+pub mod lifecycle;
+pub mod payload;
 pub mod record_types;
 pub mod rhex;
 pub mod signature;
@@ -0,0 +1,404 @@
+//! Typed `intent.data` for the built-in record types.
+//!
+//! Each field takes its long name when written, and accepts the short and
+//! emoji spellings when read. Freeform types (`record:*`) have no payload
+//! struct; their data is whatever the author put there.
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{
+    Rhex,
+    b64::serde::b64_32,
+    error,
+    policy::rule::Rule,
+    rhex::record_types::RecordTypeDef,
+    schema::Schema,
+    spatial::{frame::Frame, query::SpatialQuery},
+};
+
+pub trait Payload: Serialize + DeserializeOwned {
+    /// Record types whose `data` is this payload.
+    const RECORD_TYPES: &'static [&'static str];
+}
+
+/// `scope:genesis`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScopeGenesis {
+    #[serde(default, alias = "n", alias = "🗒️")]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub unix_ms: Option<u64>,
+    #[serde(alias = "pk", alias = "🔓", with = "b64_32")]
+    pub public_key: [u8; 32],
+}
+
+impl Payload for ScopeGenesis {
+    const RECORD_TYPES: &'static [&'static str] = &["scope:genesis"];
+}
+
+/// `scope:request`. The usher's reply carries only `new_scope`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScopeRequest {
+    #[serde(alias = "ns")]
+    pub new_scope: String,
+    /// Base64 CBOR of the child scope's genesis R⬢
+    #[serde(default, alias = "g", skip_serializing_if = "Option::is_none")]
+    pub genesis: Option<String>,
+}
+
+impl Payload for ScopeRequest {
+    const RECORD_TYPES: &'static [&'static str] = &["scope:request"];
+}
+
+/// `scope:create`: the usher's answer to a granted `scope:request`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScopeCreate {
+    #[serde(alias = "ns")]
+    pub new_scope: String,
+}
+
+impl Payload for ScopeCreate {
+    const RECORD_TYPES: &'static [&'static str] = &["scope:create"];
+}
+
+/// `scope:seal`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ScopeSeal {
+    #[serde(default, alias = "n", alias = "🗒️")]
+    pub note: Option<String>,
+}
+
+impl Payload for ScopeSeal {
+    const RECORD_TYPES: &'static [&'static str] = &["scope:seal"];
+}
+
+/// `scope:checkpoint`: Merkle root over the first `size` record hashes
+/// of the scope, in chain order from genesis.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Checkpoint {
+    #[serde(with = "b64_32")]
+    pub root: [u8; 32],
+    pub size: u64,
+    /// `current_hash` of the last record covered
+    #[serde(with = "b64_32")]
+    pub last: [u8; 32],
+}
+
+impl Payload for Checkpoint {
+    const RECORD_TYPES: &'static [&'static str] = &["scope:checkpoint"];
+}
+
+/// `request:proof`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProofRequest {
+    /// `current_hash` of the record to prove
+    #[serde(with = "b64_32")]
+    pub hash: [u8; 32],
+}
+
+impl Payload for ProofRequest {
+    const RECORD_TYPES: &'static [&'static str] = &["request:proof"];
+}
+
+/// `policy:set`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PolicySet {
+    #[serde(default, alias = "n", alias = "🗒️")]
+    pub note: Option<String>,
+    #[serde(default, alias = "qt", alias = "🤝⏳")]
+    pub quorum_ttl: Option<u64>,
+    #[serde(default, rename = "effective_micromark", alias = "eff", alias = "🟢🕑")]
+    pub eff: Option<u64>,
+    #[serde(default, rename = "expires_micromark", alias = "exp", alias = "🔴🕑")]
+    pub exp: Option<u64>,
+    #[serde(default, alias = "r", alias = "⛓️")]
+    pub rules: Vec<Rule>,
+}
+
+impl Payload for PolicySet {
+    const RECORD_TYPES: &'static [&'static str] = &["policy:set"];
+}
+
+/// `usher:appoint`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UsherAppoint {
+    #[serde(alias = "n", alias = "🗒️")]
+    pub note: String,
+    #[serde(alias = "h", alias = "🏠")]
+    pub host: String,
+    #[serde(alias = "p", alias = "🚪")]
+    pub port: u16,
+    #[serde(alias = "pk", alias = "🔓", with = "b64_32")]
+    pub public_key: [u8; 32],
+}
+
+impl Payload for UsherAppoint {
+    const RECORD_TYPES: &'static [&'static str] = &["usher:appoint"];
+}
+
+/// `usher:demote`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UsherDemote {
+    #[serde(alias = "pk", alias = "🔓", with = "b64_32")]
+    pub public_key: [u8; 32],
+    #[serde(default, alias = "n", alias = "🗒️")]
+    pub note: Option<String>,
+}
+
+impl Payload for UsherDemote {
+    const RECORD_TYPES: &'static [&'static str] = &["usher:demote"];
+}
+
+/// `key:grant`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KeyGrant {
+    #[serde(alias = "pk", alias = "🔓", with = "b64_32")]
+    pub public_key: [u8; 32],
+    #[serde(alias = "n", alias = "🗒️")]
+    pub note: String,
+    #[serde(default, alias = "r", alias = "🥐")]
+    pub roles: Vec<String>,
+    #[serde(rename = "effective_micromark", alias = "eff", alias = "🟢🕑")]
+    pub eff: u64,
+    #[serde(rename = "expiration_micromark", alias = "exp", alias = "🔴🕑")]
+    pub exp: u64,
+}
+
+impl Payload for KeyGrant {
+    const RECORD_TYPES: &'static [&'static str] = &["key:grant"];
+}
+
+/// `key:revoke`: `public_key` may sign nothing in the scope after this
+/// record's `at`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KeyRevoke {
+    #[serde(alias = "pk", alias = "🔓", with = "b64_32")]
+    pub public_key: [u8; 32],
+    #[serde(default, alias = "n", alias = "🗒️")]
+    pub note: Option<String>,
+}
+
+impl Payload for KeyRevoke {
+    const RECORD_TYPES: &'static [&'static str] = &["key:revoke"];
+}
+
+/// `key:rotate`: the author hands its roles over to `public_key` and is
+/// revoked as of this record's `at`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KeyRotate {
+    #[serde(alias = "pk", alias = "🔓", with = "b64_32")]
+    pub public_key: [u8; 32],
+    #[serde(default, alias = "n", alias = "🗒️")]
+    pub note: Option<String>,
+}
+
+impl Payload for KeyRotate {
+    const RECORD_TYPES: &'static [&'static str] = &["key:rotate"];
+}
+
+/// `alias:set`: a human name for `public_key` within the scope.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AliasSet {
+    #[serde(alias = "a")]
+    pub alias: String,
+    #[serde(alias = "pk", alias = "🔓", with = "b64_32")]
+    pub public_key: [u8; 32],
+    #[serde(default, alias = "n", alias = "🗒️")]
+    pub note: Option<String>,
+}
+
+impl Payload for AliasSet {
+    const RECORD_TYPES: &'static [&'static str] = &["alias:set"];
+}
+
+/// `alias:unset`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AliasUnset {
+    #[serde(alias = "a")]
+    pub alias: String,
+    #[serde(default, alias = "n", alias = "🗒️")]
+    pub note: Option<String>,
+}
+
+impl Payload for AliasUnset {
+    const RECORD_TYPES: &'static [&'static str] = &["alias:unset"];
+}
+
+/// `steward:info`, `steward:warning` and `steward:error`; the level is
+/// in the record type.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StewardNotice {
+    #[serde(alias = "m")]
+    pub message: String,
+    /// An `E_*` code from `hl_core::error`, if the notice is about one
+    #[serde(default, alias = "c", skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+impl Payload for StewardNotice {
+    const RECORD_TYPES: &'static [&'static str] =
+        &["steward:info", "steward:warning", "steward:error"];
+}
+
+/// `type:define`
+impl Payload for RecordTypeDef {
+    const RECORD_TYPES: &'static [&'static str] = &["type:define"];
+}
+
+/// `frame:define`
+impl Payload for Frame {
+    const RECORD_TYPES: &'static [&'static str] = &["frame:define"];
+}
+
+/// `schema:define`. Prefer `Schema::from_define`, which also checks the
+/// definition's own schema URI and name.
+impl Payload for Schema {
+    const RECORD_TYPES: &'static [&'static str] = &["schema:define"];
+}
+
+/// `request:rhex`. With no `spatial` filter the whole scope comes back.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct RhexRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spatial: Option<SpatialQuery>,
+}
+
+impl Payload for RhexRequest {
+    const RECORD_TYPES: &'static [&'static str] = &["request:rhex"];
+}
+
+/// Requests that name everything they need in `intent.scope`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ScopeQuery {}
+
+impl Payload for ScopeQuery {
+    const RECORD_TYPES: &'static [&'static str] = &[
+        "request:head",
+        "request:policy",
+        "request:aliases",
+        "request:scope",
+    ];
+}
+
+impl Rhex {
+    /// Decode `intent.data` as the payload of this record's type. Errors
+    /// name the offending field, e.g. `policy:set data.rules[0].quorum_k`.
+    pub fn payload<T: Payload>(&self) -> anyhow::Result<T> {
+        let record_type = self.intent.record_type.as_str();
+        if !T::RECORD_TYPES.contains(&record_type) {
+            bail!(
+                "{}: {} does not carry a {} payload",
+                error::E_RECORD_TYPE_UNKNOWN,
+                record_type,
+                std::any::type_name::<T>().rsplit("::").next().unwrap_or("")
+            );
+        }
+        serde_path_to_error::deserialize(&self.intent.data).map_err(|e| {
+            let path = e.path().to_string();
+            let field = if path == "." {
+                String::new()
+            } else {
+                format!(".{}", path)
+            };
+            anyhow::anyhow!(
+                "{}: {} data{}: {}",
+                error::E_DATA_SCHEMA_INVALID,
+                record_type,
+                field,
+                e.inner()
+            )
+        })
+    }
+
+    /// As `payload`, but null data decodes as the default payload.
+    pub fn payload_or_default<T: Payload + Default>(&self) -> anyhow::Result<T> {
+        if self.intent.data.is_null() {
+            return Ok(T::default());
+        }
+        self.payload()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rhex(record_type: &str, data: serde_json::Value) -> Rhex {
+        let mut rhex = Rhex::new();
+        rhex.intent.record_type = record_type.to_string();
+        rhex.intent.data = data;
+        rhex
+    }
+
+    #[test]
+    fn aliases_decode() {
+        let pk = crate::to_base64(&[7u8; 32]);
+        let long = rhex(
+            "usher:appoint",
+            json!({"note": "a", "host": "h", "port": 1984, "public_key": pk}),
+        );
+        let emoji = rhex(
+            "usher:appoint",
+            json!({"🗒️": "a", "🏠": "h", "🚪": 1984, "🔓": pk}),
+        );
+        let a: UsherAppoint = long.payload().unwrap();
+        assert_eq!(a, emoji.payload().unwrap());
+        assert_eq!(a.public_key, [7u8; 32]);
+    }
+
+    #[test]
+    fn errors_name_the_field() {
+        let r = rhex(
+            "policy:set",
+            json!({"r": [{"record_types": [], "append_roles": [], "quorum_k": "two",
+                          "quorum_roles": [], "rate_per_mark": 1}]}),
+        );
+        let e = r.payload::<PolicySet>().unwrap_err().to_string();
+        assert!(e.contains("policy:set data.r[0].quorum_k"), "{}", e);
+
+        let r = rhex("key:grant", json!({"pk": "not a key"}));
+        let e = r.payload::<KeyGrant>().unwrap_err().to_string();
+        assert!(e.contains("data.pk"), "{}", e);
+
+        assert!(r.payload::<PolicySet>().is_err());
+    }
+
+    #[test]
+    fn every_builtin_has_a_payload() {
+        let typed: Vec<&str> = [
+            ScopeGenesis::RECORD_TYPES,
+            ScopeRequest::RECORD_TYPES,
+            ScopeCreate::RECORD_TYPES,
+            ScopeSeal::RECORD_TYPES,
+            Checkpoint::RECORD_TYPES,
+            ProofRequest::RECORD_TYPES,
+            PolicySet::RECORD_TYPES,
+            UsherAppoint::RECORD_TYPES,
+            UsherDemote::RECORD_TYPES,
+            KeyGrant::RECORD_TYPES,
+            KeyRevoke::RECORD_TYPES,
+            KeyRotate::RECORD_TYPES,
+            AliasSet::RECORD_TYPES,
+            AliasUnset::RECORD_TYPES,
+            StewardNotice::RECORD_TYPES,
+            RecordTypeDef::RECORD_TYPES,
+            Frame::RECORD_TYPES,
+            Schema::RECORD_TYPES,
+            RhexRequest::RECORD_TYPES,
+            ScopeQuery::RECORD_TYPES,
+        ]
+        .concat();
+        for rt in crate::rhex::record_types::RECORD_TYPES {
+            if !rt.starts_with("record:") {
+                assert!(typed.contains(&rt), "{} has no payload", rt);
+            }
+        }
+
+        let r = rhex("request:head", serde_json::Value::Null);
+        assert_eq!(r.payload_or_default::<ScopeQuery>().unwrap(), ScopeQuery {});
+        let r = rhex("steward:warning", json!({"m": "disk low", "c": "E_IO"}));
+        assert_eq!(r.payload::<StewardNotice>().unwrap().message, "disk low");
+    }
+}
@@ -1,9 +1,17 @@
-pub const RECORD_TYPES: [&str; 29] = [
+use serde::{Deserialize, Serialize};
+
+use crate::scope::scope::scope_lineage;
+
+pub const RECORD_TYPES: [&str; 35] = [
     "scope:genesis",
     "scope:request",
     "scope:create",
     "scope:seal",
+    "scope:checkpoint",
     "policy:set",
+    "schema:define",
+    "type:define",
+    "frame:define",
     "usher:appoint",
     "usher:demote",
     "alias:set",
@@ -23,11 +31,19 @@ pub const RECORD_TYPES: [&str; 29] = [
     "request:policy",
     "request:aliases",
     "request:scope",
+    "request:proof",
     "steward:info",
     "steward:warning",
     "steward:error",
     "key:grant",
     "key:revoke",
+    "key:rotate",
+];
+
+/// Prefixes with a built-in processor behind them. Custom types may only
+/// extend `record:` or introduce a prefix of their own.
+const RESERVED_PREFIXES: [&str; 10] = [
+    "scope", "policy", "schema", "type", "frame", "usher", "alias", "request", "steward", "key",
 ];
 
 pub fn is_valid_record_type(record_type: &str) -> bool {
@@ -41,3 +57,106 @@ pub fn is_valid_record_type(record_type: &str) -> bool {
     }
     false
 }
+
+/// A custom record type, added to a scope by a `type:define` record.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecordTypeDef {
+    pub name: String,
+    /// `rhex://` uri of the schema its data must follow
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    /// Keep records of this type in the cache, not only on disk
+    #[serde(default)]
+    pub stateful: bool,
+}
+
+impl RecordTypeDef {
+    /// Can this be defined at all? Must be `prefix:name`, lowercase, and
+    /// clear of the built-ins.
+    pub fn check_name(&self) -> Result<(), anyhow::Error> {
+        let Some((prefix, sub)) = self.name.split_once(':') else {
+            anyhow::bail!("record type {} is not prefix:name", self.name);
+        };
+        let label = |s: &str| {
+            !s.is_empty()
+                && s.chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+        };
+        if !label(prefix) || !label(sub) {
+            anyhow::bail!("record type {} has invalid characters", self.name);
+        }
+        if is_valid_record_type(&self.name) || RESERVED_PREFIXES.contains(&prefix) {
+            anyhow::bail!("record type {} is reserved", self.name);
+        }
+        Ok(())
+    }
+}
+
+/// Record types known to one scope: the built-ins plus whatever it and
+/// its ancestors have defined.
+#[derive(Debug, Clone, Default)]
+pub struct RecordTypeRegistry {
+    /// (defining scope, definition)
+    custom: Vec<(String, RecordTypeDef)>,
+}
+
+impl RecordTypeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Later definitions for the same scope and name replace earlier ones.
+    pub fn define(&mut self, scope: &str, def: RecordTypeDef) {
+        self.custom
+            .retain(|(s, d)| !(s == scope && d.name == def.name));
+        self.custom.push((scope.to_string(), def));
+    }
+
+    /// The custom definition `record_type` has in `scope`, taking the
+    /// nearest scope in its lineage that defines it.
+    pub fn custom(&self, scope: &str, record_type: &str) -> Option<&RecordTypeDef> {
+        scope_lineage(scope).into_iter().find_map(|s| {
+            self.custom
+                .iter()
+                .find(|(ds, d)| ds == s && d.name == record_type)
+                .map(|(_, d)| d)
+        })
+    }
+
+    pub fn is_known(&self, scope: &str, record_type: &str) -> bool {
+        is_valid_record_type(record_type) || self.custom(scope, record_type).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn def(name: &str) -> RecordTypeDef {
+        RecordTypeDef {
+            name: name.to_string(),
+            schema: None,
+            stateful: false,
+        }
+    }
+
+    #[test]
+    fn custom_types_follow_lineage() {
+        let mut registry = RecordTypeRegistry::new();
+        registry.define("acme", def("record:invoice"));
+        assert!(registry.is_known("acme", "record:invoice"));
+        assert!(registry.is_known("acme.billing", "record:invoice"));
+        assert!(!registry.is_known("other", "record:invoice"));
+        assert!(!registry.is_known("", "record:invoice"));
+        assert!(registry.is_known("other", "record:data"));
+    }
+
+    #[test]
+    fn builtins_are_reserved() {
+        assert!(def("record:invoice").check_name().is_ok());
+        assert!(def("ledger:entry").check_name().is_ok());
+        assert!(def("record:data").check_name().is_err());
+        assert!(def("key:steal").check_name().is_err());
+        assert!(def("Invoice").check_name().is_err());
+    }
+}
@@ -1,14 +1,27 @@
 use crate::{
+    cbor::canonical::{from_canonical_cbor, hash_canonical, to_canonical_cbor},
+    error,
     rhex::{
         context::Context,
         intent::Intent,
-        record_types,
-        signature::{SigType, Signature},
+        lifecycle::SignError,
+        record_types::RecordTypeRegistry,
+        signature::{SigCheck, SigType, Signature},
     },
-    time::clock::GTClock,
+    time::clock::{Clock, GTClock},
 };
+use anyhow::bail;
 use serde::{Deserialize, Serialize};
 
+/// Original format: preimages are plain blake3 over canonical CBOR.
+pub const RHEX_MAGIC_V0: [u8; 6] = *b"RHEX\x00\x00";
+/// v1: each signature preimage is keyed by its role's domain string.
+pub const RHEX_MAGIC_V1: [u8; 6] = *b"RHEX\x00\x01";
+/// Format we write for new records.
+pub const RHEX_MAGIC: [u8; 6] = RHEX_MAGIC_V1;
+/// Highest format version we know how to verify.
+pub const RHEX_VERSION_MAX: u16 = 1;
+
 // Enable serde_with macros in Cargo.toml:
 // serde_with = { version = "3", features = ["macros"] }
 
@@ -29,9 +42,11 @@ pub struct Rhex {
     pub current_hash: Option<[u8; 32]>,
 }
 
+/// Structural problems that make a R⬢ unusable whatever its signing
+/// stage. For the lifecycle itself see [`Rhex::stage`].
 #[derive(Debug, PartialEq)]
 pub enum RhexStatus {
-    Finalized,
+    Valid,
     InvalidPreviousHash,
     InvalidScope,
     InvalidAuthorPK,
@@ -41,15 +56,12 @@ pub enum RhexStatus {
     InvalidAt,
     InvalidSpacial,
     InvalidSignature,
-    AuthorSigned,
-    UsherSigned,
-    QuorumSigned(u16),
 }
 
 impl Rhex {
     pub fn new() -> Self {
         Self {
-            magic: *b"RHEX\x00\x00",
+            magic: RHEX_MAGIC,
             intent: Intent {
                 previous_hash: None,
                 scope: String::new(),
@@ -58,6 +70,7 @@ impl Rhex {
                 usher_pk: [0u8; 32],
                 record_type: String::new(),
                 data: serde_json::Value::Null,
+                delegation: None,
             },
             context: Context {
                 at: 0,
@@ -71,30 +84,41 @@ impl Rhex {
         }
     }
 
+    /// Decode a R⬢. Anything not in canonical CBOR is refused with
+    /// `E_CBOR_CANON_MISMATCH`.
     pub fn from_cbor(cbor: &[u8]) -> anyhow::Result<Self> {
-        // &[u8] doesn’t impl Read; &mut &[u8] does
-        let mut slice = cbor;
-        Ok(ciborium::de::from_reader(&mut slice)?)
-        // or: Ok(ciborium::de::from_reader(&mut std::io::Cursor::new(cbor))?)
+        from_canonical_cbor(cbor)
     }
 
     pub fn into_cbor(&self) -> anyhow::Result<Vec<u8>> {
-        let mut buf = Vec::new();
-        ciborium::ser::into_writer(self, &mut buf)?;
-        Ok(buf)
+        to_canonical_cbor(self)
+    }
+
+    /// Format version carried in the last two bytes of `magic`.
+    pub fn version(&self) -> anyhow::Result<u16> {
+        if &self.magic[..4] != b"RHEX" {
+            bail!(error::E_MAGIC_BAD);
+        }
+        let version = u16::from_be_bytes([self.magic[4], self.magic[5]]);
+        if version > RHEX_VERSION_MAX {
+            bail!(error::E_MAGIC_BAD);
+        }
+        Ok(version)
     }
 
     /// Preimage for the author (intent only)
     pub fn author_hash(&self) -> anyhow::Result<[u8; 32]> {
-        let mut hasher = blake3::Hasher::new();
-        ciborium::ser::into_writer(&self.intent, &mut hasher)?; // Hasher: Write
-        Ok(*hasher.finalize().as_bytes())
+        self.author_preimage(self.version()?)
     }
 
-    /// Add context to the current Rhex
-    pub fn add_context(&mut self, spacial: &Option<(f64, f64, f64, String)>) -> anyhow::Result<()> {
-        let clock = GTClock::new(0);
-        self.context.at = clock.now_micromarks_u64();
+    /// Add context to the current Rhex, stamped on the ledger's GT clock
+    pub fn add_context(
+        &mut self,
+        gt: &GTClock,
+        clock: &dyn Clock,
+        spacial: &Option<(f64, f64, f64, String)>,
+    ) -> anyhow::Result<()> {
+        self.context.at = gt.micromarks_on(clock)?;
         if let Some((x, y, z, refer)) = spacial {
             self.context.x = Some(*x);
             self.context.y = Some(*y);
@@ -106,11 +130,7 @@ impl Rhex {
 
     /// Preimage for usher (author sig + context)
     pub fn usher_hash(&self, author_sig: &Signature) -> anyhow::Result<[u8; 32]> {
-        let mut hasher = blake3::Hasher::new();
-        // Encode signature as CBOR bytes, not array-of-u8
-        ciborium::ser::into_writer(serde_bytes::Bytes::new(&author_sig.sig), &mut hasher)?;
-        ciborium::ser::into_writer(&self.context, &mut hasher)?;
-        Ok(*hasher.finalize().as_bytes())
+        self.usher_preimage(self.version()?, author_sig)
     }
 
     /// Preimage for quorum (author sig + usher sig)
@@ -119,14 +139,90 @@ impl Rhex {
         author_sig: &Signature,
         usher_sig: &Signature,
     ) -> anyhow::Result<[u8; 32]> {
-        let mut hasher = blake3::Hasher::new();
-        ciborium::ser::into_writer(serde_bytes::Bytes::new(&author_sig.sig), &mut hasher)?;
-        ciborium::ser::into_writer(serde_bytes::Bytes::new(&usher_sig.sig), &mut hasher)?;
+        self.quorum_preimage(self.version()?, author_sig, usher_sig)
+    }
+
+    fn author_preimage(&self, version: u16) -> anyhow::Result<[u8; 32]> {
+        let mut hasher = role_hasher(version, SigType::Author);
+        hash_canonical(&mut hasher, &self.intent)?;
         Ok(*hasher.finalize().as_bytes())
     }
 
+    fn usher_preimage(&self, version: u16, author_sig: &Signature) -> anyhow::Result<[u8; 32]> {
+        let mut hasher = role_hasher(version, SigType::Usher);
+        // Encode signature as CBOR bytes, not array-of-u8
+        hash_canonical(&mut hasher, serde_bytes::Bytes::new(&author_sig.sig))?;
+        hash_canonical(&mut hasher, &self.context)?;
+        Ok(*hasher.finalize().as_bytes())
+    }
+
+    fn quorum_preimage(
+        &self,
+        version: u16,
+        author_sig: &Signature,
+        usher_sig: &Signature,
+    ) -> anyhow::Result<[u8; 32]> {
+        let mut hasher = role_hasher(version, SigType::Quorum);
+        hash_canonical(&mut hasher, serde_bytes::Bytes::new(&author_sig.sig))?;
+        hash_canonical(&mut hasher, serde_bytes::Bytes::new(&usher_sig.sig))?;
+        Ok(*hasher.finalize().as_bytes())
+    }
+
+    /// Preimage a signer in `role` signs under format `version`, using
+    /// the author/usher signatures already on the record.
+    pub(crate) fn preimage_for(&self, version: u16, role: SigType) -> anyhow::Result<[u8; 32]> {
+        let find = |t: SigType| {
+            self.signatures
+                .iter()
+                .find(|s| s.sig_type == t)
+                .ok_or_else(|| anyhow::anyhow!(error::E_SIG_MISSING))
+        };
+        match role {
+            SigType::Author => self.author_preimage(version),
+            SigType::Usher => self.usher_preimage(version, find(SigType::Author)?),
+            SigType::Quorum => {
+                self.quorum_preimage(version, find(SigType::Author)?, find(SigType::Usher)?)
+            }
+        }
+    }
+
+    /// Verify `sig` against the preimage for its role in this record's
+    /// format. A signature that only checks out under a different role
+    /// or version comes back as [`SigCheck::DomainMismatch`].
+    pub fn verify_signature(&self, sig: &Signature) -> anyhow::Result<SigCheck> {
+        let version = self.version()?;
+        let vk = match ed25519_dalek::VerifyingKey::from_bytes(&sig.public_key) {
+            Ok(vk) => vk,
+            Err(_) => return Ok(SigCheck::Invalid),
+        };
+        let ed_sig = ed25519_dalek::Signature::from_bytes(&sig.sig);
+
+        let expected = self.preimage_for(version, sig.sig_type)?;
+        if vk.verify_strict(&expected, &ed_sig).is_ok() {
+            return Ok(SigCheck::Valid);
+        }
+
+        for other_version in 0..=RHEX_VERSION_MAX {
+            for role in [SigType::Author, SigType::Usher, SigType::Quorum] {
+                if other_version == version && role == sig.sig_type {
+                    continue;
+                }
+                let Ok(other) = self.preimage_for(other_version, role) else {
+                    continue;
+                };
+                if vk.verify_strict(&other, &ed_sig).is_ok() {
+                    return Ok(SigCheck::DomainMismatch);
+                }
+            }
+        }
+        Ok(SigCheck::Invalid)
+    }
+
     /// Finalize: intent + context + signatures (in canonical order)
     pub fn finalize(&mut self) -> anyhow::Result<()> {
+        if !self.stage()?.can_finalize() {
+            return Err(SignError::NotYetSigned(SigType::Usher).into());
+        }
         self.sort_signatures()?;
         let current_hash = self.generate_current_hash()?;
         self.current_hash = Some(current_hash);
@@ -135,9 +231,9 @@ impl Rhex {
 
     pub fn generate_current_hash(&self) -> Result<[u8; 32], anyhow::Error> {
         let mut hasher = blake3::Hasher::new();
-        ciborium::ser::into_writer(&self.intent, &mut hasher)?;
-        ciborium::ser::into_writer(&self.context, &mut hasher)?;
-        ciborium::ser::into_writer(&self.signatures, &mut hasher)?;
+        hash_canonical(&mut hasher, &self.intent)?;
+        hash_canonical(&mut hasher, &self.context)?;
+        hash_canonical(&mut hasher, &self.signatures)?;
         Ok(*hasher.finalize().as_bytes())
     }
 
@@ -152,7 +248,14 @@ impl Rhex {
         Ok(())
     }
 
+    /// Status against the built-in record types only.
     pub fn status(&self) -> RhexStatus {
+        self.status_in(&RecordTypeRegistry::new())
+    }
+
+    /// Status with custom record types from `registry`, as seen from
+    /// this record's scope.
+    pub fn status_in(&self, registry: &RecordTypeRegistry) -> RhexStatus {
         // TODO: check status of previous_hash, see if it matches the
         // previous record or not
 
@@ -166,7 +269,7 @@ impl Rhex {
             return RhexStatus::InvalidUsherPK;
         }
 
-        let valid = record_types::is_valid_record_type(&self.intent.record_type);
+        let valid = registry.is_known(&self.intent.scope, &self.intent.record_type);
         if !valid {
             return RhexStatus::InvalidRecordType;
         }
@@ -182,38 +285,80 @@ impl Rhex {
         // If not, just .is_some().
         let have_refer = self.context.refer.is_some();
 
-        let valid = match (have_x, have_y, have_z, have_refer) {
-            (true, true, true, true) => RhexStatus::Finalized,
-            (false, false, false, false) => RhexStatus::Finalized,
-            _ => {
-                return RhexStatus::InvalidSpacial;
-            }
+        match (have_x, have_y, have_z, have_refer) {
+            (true, true, true, true) | (false, false, false, false) => {}
+            _ => return RhexStatus::InvalidSpacial,
         };
-        if valid != RhexStatus::Finalized {
-            return valid;
-        }
 
-        if self.signatures.len() == 0 {
+        // Signatures out of order (or finalized too early) can't be
+        // repaired by signing further.
+        if self.stage().is_err() {
             return RhexStatus::InvalidSignature;
         }
+        RhexStatus::Valid
+    }
+}
 
-        if self.signatures.len() == 1 {
-            if self.signatures[0].sig_type == SigType::Author {
-                return RhexStatus::AuthorSigned;
-            }
-        }
+/// v0 preimages are plain blake3; v1+ key the hasher by the signer's role.
+fn role_hasher(version: u16, role: SigType) -> blake3::Hasher {
+    if version == 0 {
+        blake3::Hasher::new()
+    } else {
+        blake3::Hasher::new_derive_key(role.domain())
+    }
+}
 
-        if self.signatures.len() == 2 {
-            if self.signatures[0].sig_type == SigType::Author
-                && self.signatures[1].sig_type == SigType::Usher
-            {
-                return RhexStatus::UsherSigned;
-            }
-        }
-        if self.current_hash.is_some() {
-            return RhexStatus::Finalized;
-        } else {
-            RhexStatus::QuorumSigned((self.signatures.len() - 2) as u16)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Key;
+
+    fn signed(magic: [u8; 6], key: &Key) -> Rhex {
+        let mut rhex = Rhex::new();
+        rhex.magic = magic;
+        rhex.intent.author_pk = key.pk.unwrap();
+        rhex.intent.usher_pk = key.pk.unwrap();
+        rhex.intent.record_type = "record:data".to_string();
+        let sig = key.sign(&rhex.author_hash().unwrap()).unwrap();
+        rhex.signatures.push(Signature {
+            sig_type: SigType::Author,
+            public_key: key.pk.unwrap(),
+            sig,
+        });
+        rhex
+    }
+
+    #[test]
+    fn both_versions_verify() {
+        let key = Key::generated();
+        for magic in [RHEX_MAGIC_V0, RHEX_MAGIC_V1] {
+            let rhex = signed(magic, &key);
+            assert_eq!(
+                rhex.verify_signature(&rhex.signatures[0]).unwrap(),
+                SigCheck::Valid
+            );
         }
     }
+
+    #[test]
+    fn cross_domain_signature_rejected() {
+        let key = Key::generated();
+        // A v0 author signature presented on a v1 record
+        let v0 = signed(RHEX_MAGIC_V0, &key);
+        let mut v1 = v0.clone();
+        v1.magic = RHEX_MAGIC_V1;
+        assert_eq!(
+            v1.verify_signature(&v1.signatures[0]).unwrap(),
+            SigCheck::DomainMismatch
+        );
+        assert_ne!(v0.author_hash().unwrap(), v1.author_hash().unwrap());
+    }
+
+    #[test]
+    fn unknown_version_refused() {
+        let mut rhex = Rhex::new();
+        rhex.magic = *b"RHEX\x00\x07";
+        assert!(rhex.version().is_err());
+        assert!(rhex.author_hash().is_err());
+    }
 }
@@ -24,6 +24,18 @@ impl TryFrom<u8> for SigType {
     }
 }
 
+impl SigType {
+    /// blake3 derive-key context that binds a v1+ preimage to this role,
+    /// so a signature made for one role can't be replayed as another.
+    pub fn domain(&self) -> &'static str {
+        match self {
+            SigType::Author => "HodeauxLedger R⬢ v1 author preimage",
+            SigType::Usher => "HodeauxLedger R⬢ v1 usher preimage",
+            SigType::Quorum => "HodeauxLedger R⬢ v1 quorum preimage",
+        }
+    }
+}
+
 impl fmt::Display for SigType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let s = match self {
@@ -44,3 +56,22 @@ pub struct Signature {
     #[serde_as(as = "Bytes")]
     pub sig: [u8; 64],
 }
+
+/// Outcome of checking one signature against its role's preimage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SigCheck {
+    Valid,
+    Invalid,
+    /// Signature is good, just not for this role/version.
+    DomainMismatch,
+}
+
+impl SigCheck {
+    pub fn error_code(&self) -> Option<&'static str> {
+        match self {
+            SigCheck::Valid => None,
+            SigCheck::Invalid => Some(crate::error::E_SIG_INVALID),
+            SigCheck::DomainMismatch => Some(crate::error::E_SIG_DOMAIN_MISMATCH),
+        }
+    }
+}
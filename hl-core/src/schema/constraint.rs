@@ -1,8 +1,52 @@
-#[derive(Clone)]
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{b64::b64::from_base64_to_32, error, schema::Violation};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+    /// No type check, presence only
+    #[default]
+    Any,
+    String,
+    U64,
+    /// Base64url (no padding) of exactly 32 bytes, i.e. a key or hash
+    #[serde(rename = "b64-32")]
+    B64_32,
+    Array,
+    Object,
+    Enum,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Constraint {
+    #[serde(rename = "id", default)]
     pub index: u16,
+    #[serde(default)]
     pub name: String,
+    #[serde(rename = "req", default)]
     pub required: u8, // 0 = false, 1 = true
+    #[serde(rename = "type", default)]
+    pub kind: Kind,
+    /// String length in chars, or array length in items
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_len: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_len: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<u64>,
+    /// `array`: constraint every item must meet (name and req unused)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub items: Option<Box<Constraint>>,
+    /// `object`: nested fields
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<Constraint>,
+    /// `enum`: allowed string values
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<String>,
 }
 
 impl Constraint {
@@ -11,6 +55,111 @@ impl Constraint {
             index: *index,
             name: name.to_string(),
             required: *required,
+            kind: Kind::Any,
+            min_len: None,
+            max_len: None,
+            min: None,
+            max: None,
+            items: None,
+            fields: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    /// Check `value`, found at `path`, against this constraint.
+    pub fn check(&self, value: &Value, path: &str, out: &mut Vec<Violation>) {
+        let type_err = |out: &mut Vec<Violation>, want: &str| {
+            out.push(Violation::new(
+                error::E_DATA_SCHEMA_INVALID,
+                path,
+                format!("expected {}", want),
+            ));
+        };
+        match self.kind {
+            Kind::Any => {}
+            Kind::String => match value.as_str() {
+                Some(s) => self.check_len(s.chars().count(), path, out),
+                None => type_err(out, "string"),
+            },
+            Kind::U64 => match value.as_u64() {
+                Some(n) => {
+                    if self.min.is_some_and(|min| n < min) || self.max.is_some_and(|max| n > max) {
+                        out.push(Violation::new(
+                            error::E_SCHEMA_CONSTRAINT_VIOLATION,
+                            path,
+                            format!("{} outside {:?}..={:?}", n, self.min, self.max),
+                        ));
+                    }
+                }
+                None => type_err(out, "u64"),
+            },
+            Kind::B64_32 => {
+                if value
+                    .as_str()
+                    .and_then(|s| from_base64_to_32(s).ok())
+                    .is_none()
+                {
+                    type_err(out, "base64 of 32 bytes");
+                }
+            }
+            Kind::Array => match value.as_array() {
+                Some(items) => {
+                    self.check_len(items.len(), path, out);
+                    if let Some(item) = &self.items {
+                        for (i, v) in items.iter().enumerate() {
+                            item.check(v, &format!("{}[{}]", path, i), out);
+                        }
+                    }
+                }
+                None => type_err(out, "array"),
+            },
+            Kind::Object => match value.as_object() {
+                Some(_) => check_fields(&self.fields, value, path, out),
+                None => type_err(out, "object"),
+            },
+            Kind::Enum => match value.as_str() {
+                Some(s) if self.values.iter().any(|v| v == s) => {}
+                Some(s) => out.push(Violation::new(
+                    error::E_SCHEMA_CONSTRAINT_VIOLATION,
+                    path,
+                    format!("{:?} not one of {:?}", s, self.values),
+                )),
+                None => type_err(out, "string"),
+            },
+        }
+    }
+
+    fn check_len(&self, len: usize, path: &str, out: &mut Vec<Violation>) {
+        if self.min_len.is_some_and(|min| len < min) || self.max_len.is_some_and(|max| len > max) {
+            out.push(Violation::new(
+                error::E_SCHEMA_CONSTRAINT_VIOLATION,
+                path,
+                format!(
+                    "length {} outside {:?}..={:?}",
+                    len, self.min_len, self.max_len
+                ),
+            ));
+        }
+    }
+}
+
+/// Check each named field of the object at `path`.
+pub(crate) fn check_fields(
+    fields: &[Constraint],
+    value: &Value,
+    path: &str,
+    out: &mut Vec<Violation>,
+) {
+    for field in fields {
+        let field_path = format!("{}.{}", path, field.name);
+        match value.get(&field.name) {
+            Some(v) => field.check(v, &field_path, out),
+            None if field.required == 1 => out.push(Violation::new(
+                error::E_SCHEMA_CONSTRAINT_VIOLATION,
+                &field_path,
+                "is required",
+            )),
+            None => {}
         }
     }
 }
@@ -1,13 +1,52 @@
-use crate::schema::constraint::Constraint;
+//! Schemas for `intent.data`.
+//!
+//! A schema is published as a `schema:define` record whose data follows
+//! `schema-define@0` (see docs/schema). Records opt in by naming it under
+//! `schema`, e.g. `"schema": "rhex://schema/policy-set@0"`.
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub use crate::schema::constraint::{Constraint, Kind};
+use crate::{error, schema::constraint::check_fields};
 
 mod constraint;
 
-#[derive(Clone)]
+/// What a `schema:define` record's own data conforms to.
+pub const SCHEMA_DEFINE: &str = "rhex://schema/schema-define@0";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Schema {
     pub name: String,
+    #[serde(rename = "fields")]
     pub constraints: Vec<Constraint>,
 }
 
+/// One failed check, with the JSON path (`$.rules[0].quorum_k`) it was found at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub code: &'static str,
+    pub path: String,
+    pub message: String,
+}
+
+impl Violation {
+    pub fn new(code: &'static str, path: &str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            path: path.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
 impl Schema {
     pub fn new(name: String, constraints: &Vec<Constraint>) -> anyhow::Result<Self> {
         Ok(Self {
@@ -15,4 +54,155 @@ impl Schema {
             constraints: constraints.clone(),
         })
     }
+
+    /// Read a schema out of `schema:define` data.
+    pub fn from_define(data: &Value) -> anyhow::Result<Self> {
+        match data.get("schema").and_then(Value::as_str) {
+            Some(SCHEMA_DEFINE) | None => {}
+            Some(other) => bail!(
+                "{}: schema definition follows {}, not {}",
+                error::E_DATA_SCHEMA_INVALID,
+                other,
+                SCHEMA_DEFINE
+            ),
+        }
+        let schema: Schema = serde_json::from_value(data.clone())
+            .map_err(|e| anyhow::anyhow!("{}: {}", error::E_DATA_SCHEMA_INVALID, e))?;
+        if schema.name.is_empty() {
+            bail!("{}: schema has no name", error::E_DATA_SCHEMA_INVALID);
+        }
+        Ok(schema)
+    }
+
+    /// Everything wrong with `data`; empty if it conforms.
+    pub fn validate(&self, data: &Value) -> Vec<Violation> {
+        let mut out = Vec::new();
+        if !data.is_object() {
+            out.push(Violation::new(
+                error::E_DATA_SCHEMA_INVALID,
+                "$",
+                "expected object",
+            ));
+            return out;
+        }
+        check_fields(&self.constraints, data, "$", &mut out);
+        out
+    }
+}
+
+/// Split `rhex://<scope>/<name>` into scope and name. The scope may
+/// itself contain `/`; the name is the last segment.
+pub fn parse_schema_uri(uri: &str) -> anyhow::Result<(&str, &str)> {
+    let Some(rest) = uri.strip_prefix("rhex://") else {
+        bail!(
+            "{}: schema {} is not a rhex:// uri",
+            error::E_DATA_SCHEMA_INVALID,
+            uri
+        );
+    };
+    match rest.rsplit_once('/') {
+        Some((scope, name)) if !name.is_empty() => Ok((scope, name)),
+        _ => bail!(
+            "{}: schema {} has no name",
+            error::E_DATA_SCHEMA_INVALID,
+            uri
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn violations_carry_paths() {
+        let schema = Schema::from_define(&json!({
+            "schema": SCHEMA_DEFINE,
+            "name": "policy-set@0",
+            "fields": [
+                { "id": 0, "name": "note", "req": 0, "type": "string", "max_len": 4 },
+                { "id": 1, "name": "quorum_ttl", "req": 1, "type": "u64", "min": 1 },
+                { "id": 2, "name": "rules", "req": 1, "type": "array", "items": {
+                    "type": "object", "fields": [
+                        { "id": 0, "name": "quorum_k", "req": 1, "type": "u64" },
+                        { "id": 1, "name": "mode", "type": "enum", "values": ["a", "b"] }
+                    ]
+                }},
+                { "id": 3, "name": "pk", "req": 0, "type": "b64-32" }
+            ]
+        }))
+        .unwrap();
+
+        let ok = json!({"quorum_ttl": 5, "rules": [{"quorum_k": 1, "mode": "a"}]});
+        assert!(schema.validate(&ok).is_empty());
+
+        let bad = json!({
+            "note": "too long",
+            "rules": [{"quorum_k": 1}, {"quorum_k": "x", "mode": "c"}],
+            "pk": "short"
+        });
+        let paths: Vec<String> = schema.validate(&bad).into_iter().map(|v| v.path).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "$.note",
+                "$.quorum_ttl",
+                "$.rules[1].quorum_k",
+                "$.rules[1].mode",
+                "$.pk"
+            ]
+        );
+    }
+
+    #[test]
+    fn docs_schemas_parse() {
+        for doc in [
+            include_str!("../../../docs/schema/policy-set@0.json"),
+            include_str!("../../../docs/schema/schema-define@0.json"),
+            include_str!("../../../docs/schema/scope-genesis@0.json"),
+            include_str!("../../../docs/schema/scope-request@0.json"),
+        ] {
+            let data: Value = serde_json::from_str(doc).unwrap();
+            Schema::from_define(&data).unwrap();
+        }
+        // The meta-schema accepts its own definition
+        let meta: Value =
+            serde_json::from_str(include_str!("../../../docs/schema/schema-define@0.json"))
+                .unwrap();
+        assert!(
+            Schema::from_define(&meta)
+                .unwrap()
+                .validate(&meta)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn schema_uri() {
+        assert_eq!(
+            parse_schema_uri("rhex://schema/policy-set@0").unwrap(),
+            ("schema", "policy-set@0")
+        );
+        assert!(parse_schema_uri("schema/policy-set@0").is_err());
+    }
+
+    #[test]
+    fn records_follow_their_schemas() {
+        let pairs = [
+            (
+                include_str!("testdata/scope-request@0.json"),
+                include_str!("testdata/scope-request.json"),
+            ),
+            (
+                include_str!("testdata/scope-genesis@0.json"),
+                include_str!("testdata/scope-genesis.json"),
+            ),
+        ];
+        for (define, fixture) in pairs {
+            let schema = Schema::from_define(&serde_json::from_str(define).unwrap()).unwrap();
+            let fixture: Value = serde_json::from_str(fixture).unwrap();
+            assert_eq!(schema.validate(&fixture), [], "{}", schema.name);
+        }
+    }
 }
@@ -54,3 +54,51 @@ impl Scope {
         }
     }
 }
+
+/// `scope` followed by each ancestor up to the root `""`. This holds
+/// because `scope:request` only creates names that pass `is_child_scope`.
+pub fn scope_lineage(scope: &str) -> Vec<&str> {
+    let mut lineage = vec![scope];
+    let mut current = scope;
+    while !current.is_empty() {
+        current = current
+            .rsplit_once('.')
+            .map(|(parent, _)| parent)
+            .unwrap_or("");
+        lineage.push(current);
+    }
+    lineage
+}
+
+/// Whether `child` is `parent` extended by exactly one non-empty label:
+/// `acme.billing` under `acme`, or `acme` under the root `""`.
+pub fn is_child_scope(parent: &str, child: &str) -> bool {
+    let label = if parent.is_empty() {
+        Some(child)
+    } else {
+        child
+            .strip_prefix(parent)
+            .and_then(|rest| rest.strip_prefix('.'))
+    };
+    label.is_some_and(|label| !label.is_empty() && !label.contains('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn children_add_one_label() {
+        assert!(is_child_scope("", "acme"));
+        assert!(is_child_scope("acme", "acme.billing"));
+        assert!(!is_child_scope("", "acme.billing"));
+        assert!(!is_child_scope("acme", "acmebilling"));
+        assert!(!is_child_scope("acme", "acme."));
+        assert!(!is_child_scope("acme", "acme.billing.eu"));
+        assert!(!is_child_scope("", ""));
+        assert_eq!(
+            scope_lineage("acme.billing"),
+            vec!["acme.billing", "acme", ""]
+        );
+    }
+}
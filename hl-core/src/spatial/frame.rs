@@ -0,0 +1,139 @@
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+use crate::{Context, error, scope::scope::scope_lineage};
+
+/// Mean earth radius in metres, for distances in geodetic frames.
+const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+/// A reference frame, added to a scope by a `frame:define` record.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub name: String,
+    /// Units of x/y/z, e.g. `m`, or `deg` for lon/lat
+    pub units: String,
+    /// x/y are longitude/latitude in degrees and z is metres; distances
+    /// are great-circle metres rather than euclidean
+    #[serde(default)]
+    pub geodetic: bool,
+    pub min: [f64; 3],
+    pub max: [f64; 3],
+}
+
+impl Frame {
+    /// WGS 84 longitude, latitude, altitude. Known to every scope.
+    pub fn wgs84() -> Self {
+        Self {
+            name: "wgs84".to_string(),
+            units: "deg".to_string(),
+            geodetic: true,
+            min: [-180.0, -90.0, -11_000.0],
+            max: [180.0, 90.0, 1_000_000.0],
+        }
+    }
+
+    pub fn check(&self) -> Result<(), anyhow::Error> {
+        if self.name.is_empty() || self.name.chars().any(char::is_whitespace) {
+            bail!("frame name {:?} is invalid", self.name);
+        }
+        if self.name == "wgs84" {
+            bail!("frame wgs84 is built in");
+        }
+        if (0..3).any(|i| self.min[i].is_nan() || self.max[i].is_nan()) {
+            bail!("frame {} has NaN bounds", self.name);
+        }
+        if (0..3).any(|i| self.min[i] > self.max[i]) {
+            bail!("frame {} has min above max", self.name);
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, point: [f64; 3]) -> bool {
+        (0..3).all(|i| point[i] >= self.min[i] && point[i] <= self.max[i])
+    }
+
+    /// Distance between two points in this frame: great-circle metres over
+    /// x/y for geodetic frames, euclidean frame units otherwise.
+    pub fn distance(&self, a: [f64; 3], b: [f64; 3]) -> f64 {
+        if self.geodetic {
+            let (lat1, lat2) = (a[1].to_radians(), b[1].to_radians());
+            let dlat = lat2 - lat1;
+            let dlon = (b[0] - a[0]).to_radians();
+            let h =
+                (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+            2.0 * EARTH_RADIUS_M * h.sqrt().min(1.0).asin()
+        } else {
+            (0..3).map(|i| (a[i] - b[i]).powi(2)).sum::<f64>().sqrt()
+        }
+    }
+}
+
+/// `x`, `y`, `z` of a context, if it is spatial at all.
+pub fn context_point(context: &Context) -> Option<[f64; 3]> {
+    Some([context.x?, context.y?, context.z?])
+}
+
+/// Frames known to one scope: the built-ins plus whatever it and its
+/// ancestors have defined.
+#[derive(Debug, Clone, Default)]
+pub struct FrameRegistry {
+    /// (defining scope, frame)
+    frames: Vec<(String, Frame)>,
+}
+
+impl FrameRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Later definitions for the same scope and name replace earlier ones.
+    pub fn define(&mut self, scope: &str, frame: Frame) {
+        self.frames
+            .retain(|(s, f)| !(s == scope && f.name == frame.name));
+        self.frames.push((scope.to_string(), frame));
+    }
+
+    /// `name` as seen from `scope`, taking the nearest definition.
+    pub fn frame(&self, scope: &str, name: &str) -> Option<Frame> {
+        if name == "wgs84" {
+            return Some(Frame::wgs84());
+        }
+        scope_lineage(scope).into_iter().find_map(|s| {
+            self.frames
+                .iter()
+                .find(|(fs, f)| fs == s && f.name == name)
+                .map(|(_, f)| f.clone())
+        })
+    }
+
+    /// Check a spatial context from `scope` names a known frame and sits
+    /// inside its bounds. Returns the error code with the message.
+    pub fn check_context(
+        &self,
+        scope: &str,
+        context: &Context,
+    ) -> Result<(), (&'static str, String)> {
+        let (Some(point), Some(refer)) = (context_point(context), context.refer.as_ref()) else {
+            return Ok(());
+        };
+        let Some(frame) = self.frame(scope, refer) else {
+            return Err((
+                error::E_CONTEXT_REFER_INVALID,
+                format!("rhex.context.refer {:?} is not a known frame", refer),
+            ));
+        };
+        if point.iter().any(|v| !v.is_finite()) {
+            return Err((
+                error::E_CONTEXT_SPACIAL_PARSE,
+                "rhex.context x/y/z must be finite".to_string(),
+            ));
+        }
+        if !frame.contains(point) {
+            return Err((
+                error::E_SPACIAL_OUT_OF_BOUNDS,
+                format!("rhex.context {:?} outside frame {}", point, frame.name),
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,5 @@
+//! Meaning for `Context`'s `x`, `y`, `z` and `refer`: `refer` names a
+//! reference frame, declared per scope, that fixes units and bounds.
+
+pub mod frame;
+pub mod query;
@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+
+use crate::spatial::frame::Frame;
+
+/// Axis-aligned box, inclusive on every side.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BBox {
+    pub min: [f64; 3],
+    pub max: [f64; 3],
+}
+
+/// Everything within `r` of `center`, measured by the frame.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Radius {
+    pub center: [f64; 3],
+    pub r: f64,
+}
+
+/// Spatial filter over records in one frame.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SpatialQuery {
+    pub frame: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bbox: Option<BBox>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub radius: Option<Radius>,
+}
+
+impl SpatialQuery {
+    pub fn matches(&self, frame: &Frame, point: [f64; 3]) -> bool {
+        if let Some(bbox) = &self.bbox
+            && !(0..3).all(|i| point[i] >= bbox.min[i] && point[i] <= bbox.max[i])
+        {
+            return false;
+        }
+        if let Some(radius) = &self.radius
+            && frame.distance(radius.center, point) > radius.r
+        {
+            return false;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bbox_and_radius() {
+        let mut local = Frame::wgs84();
+        local.geodetic = false;
+        let query = SpatialQuery {
+            frame: "local".to_string(),
+            bbox: Some(BBox {
+                min: [0.0, 0.0, 0.0],
+                max: [10.0, 10.0, 10.0],
+            }),
+            radius: Some(Radius {
+                center: [0.0, 0.0, 0.0],
+                r: 5.0,
+            }),
+        };
+        assert!(query.matches(&local, [3.0, 4.0, 0.0]));
+        assert!(!query.matches(&local, [4.0, 4.0, 0.0]));
+        assert!(!query.matches(&local, [-1.0, 0.0, 0.0]));
+
+        // Paris to London is roughly 344 km
+        let wgs84 = Frame::wgs84();
+        let d = wgs84.distance([2.3522, 48.8566, 0.0], [-0.1276, 51.5072, 0.0]);
+        assert!((d - 344_000.0).abs() < 2_000.0, "{}", d);
+    }
+}
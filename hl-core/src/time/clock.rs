@@ -1,12 +1,66 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::{
+    fmt::Debug,
+    sync::atomic::{AtomicI64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::bail;
+
+use crate::{error, rhex::payload::ScopeGenesis};
 
 /// 1 sidereal day in milliseconds.
 pub const SIDEREAL_MS: i128 = 86_164_090;
 /// Micromarks per sidereal day (1e9).
 pub const MICROMARKS_PER_TURN: i128 = 1_000_000_000;
 
+/// Epoch for tools with no ledger in hand, and for genesis records that
+/// predate `unix_ms`.
 pub const EPOCH_AT_UNIX_MS: i128 = 1757778666636;
 
+/// Source of wall time. Swap in a [`ManualClock`] to control time in tests.
+pub trait Clock: Debug + Send + Sync {
+    /// Milliseconds since the Unix epoch.
+    fn now_unix_ms(&self) -> i128;
+}
+
+/// The machine's clock.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_unix_ms(&self) -> i128 {
+        current_unix_ms()
+    }
+}
+
+/// A clock that only moves when told to.
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    unix_ms: AtomicI64,
+}
+
+impl ManualClock {
+    pub fn new(unix_ms: i64) -> Self {
+        Self {
+            unix_ms: AtomicI64::new(unix_ms),
+        }
+    }
+
+    pub fn set(&self, unix_ms: i64) {
+        self.unix_ms.store(unix_ms, Ordering::SeqCst);
+    }
+
+    pub fn advance(&self, ms: i64) {
+        self.unix_ms.fetch_add(ms, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_unix_ms(&self) -> i128 {
+        self.unix_ms.load(Ordering::SeqCst) as i128
+    }
+}
+
 /// Clock that converts wall time to GT relative to a ledger epoch (in ms).
 #[derive(Clone, Copy, Debug)]
 pub struct GTClock {
@@ -17,12 +71,14 @@ pub struct GTClock {
 impl GTClock {
     /// Create a clock with the epoch pulled from your genesis record (ms).
     pub fn new(epoch_unix_ms: i128) -> Self {
-        if epoch_unix_ms == 0 {
-            Self {
-                epoch_unix_ms: EPOCH_AT_UNIX_MS,
-            }
-        } else {
-            Self { epoch_unix_ms }
+        Self { epoch_unix_ms }
+    }
+
+    /// Clock for the ledger rooted at this genesis.
+    pub fn from_genesis(genesis: &ScopeGenesis) -> Self {
+        match genesis.unix_ms {
+            Some(unix_ms) => Self::new(unix_ms as i128),
+            None => Self::new(EPOCH_AT_UNIX_MS),
         }
     }
 
@@ -33,16 +89,34 @@ impl GTClock {
 
     /// Current GT as **total micromarks since epoch** (can be negative before epoch).
     pub fn now_micromarks(&self) -> i128 {
-        let now_ms = current_unix_ms();
-        let delta_ms = now_ms - self.epoch_unix_ms;
-        // Convert ms → micromarks: floor division with full precision in i128.
-        delta_ms.saturating_mul(MICROMARKS_PER_TURN) / SIDEREAL_MS
+        self.micromarks_at(current_unix_ms())
     }
 
     pub fn now_micromarks_u64(&self) -> u64 {
         self.now_micromarks() as u64
     }
 
+    /// GT at a given wall time (ms).
+    pub fn micromarks_at(&self, unix_ms: i128) -> i128 {
+        let delta_ms = unix_ms - self.epoch_unix_ms;
+        // Convert ms → micromarks: floor division with full precision in i128.
+        delta_ms.saturating_mul(MICROMARKS_PER_TURN) / SIDEREAL_MS
+    }
+
+    /// GT now according to `clock`, as a `context.at`. Errors before the
+    /// epoch, which no record can be stamped with.
+    pub fn micromarks_on(&self, clock: &dyn Clock) -> anyhow::Result<u64> {
+        let mm = self.micromarks_at(clock.now_unix_ms());
+        if mm < 0 {
+            bail!(
+                "{}: clock reads {} micromarks before the ledger epoch",
+                error::E_TIME_SKEW_PAST,
+                -mm
+            );
+        }
+        Ok(mm as u64)
+    }
+
     /// Split into (turn, micromarks_into_turn).
     pub fn now_turn_and_offset(&self) -> (i128, i128) {
         let mm_total = self.now_micromarks();
@@ -78,9 +152,23 @@ mod tests {
         assert!(b >= a);
     }
 
+    #[test]
+    fn manual_clock() {
+        let wall = ManualClock::new(1_000);
+        let clock = GTClock::new(1_000);
+        assert_eq!(clock.micromarks_on(&wall).unwrap(), 0);
+        wall.advance(SIDEREAL_MS as i64);
+        assert_eq!(
+            clock.micromarks_on(&wall).unwrap(),
+            MICROMARKS_PER_TURN as u64
+        );
+        wall.set(0);
+        assert!(clock.micromarks_on(&wall).is_err());
+    }
+
     #[test]
     fn split_roundtrip() {
-        let clock = GTClock::new(0);
+        let clock = GTClock::new(EPOCH_AT_UNIX_MS);
         let mm = clock.now_micromarks();
         let (turn, into) = clock.now_turn_and_offset();
         assert_eq!(turn * MICROMARKS_PER_TURN + into, mm);
@@ -0,0 +1,63 @@
+//! GT notation: `turn.micromarks`, the sidereal turn since the ledger epoch
+//! and nine digits into it. `42.000123456` is 123,456 micromarks into
+//! turn 42. Before the epoch the turn goes negative and the offset still
+//! counts up: one micromark before is `-1.999999999`.
+
+use anyhow::bail;
+
+use crate::{error, time::clock::MICROMARKS_PER_TURN};
+
+pub fn format(mm: i128) -> String {
+    let turn = mm.div_euclid(MICROMARKS_PER_TURN);
+    let into = mm.rem_euclid(MICROMARKS_PER_TURN);
+    format!("{}.{:09}", turn, into)
+}
+
+/// Inverse of [`format`]. A bare turn (`42`) is the start of that turn.
+pub fn parse(s: &str) -> anyhow::Result<i128> {
+    let (turn, into) = s.split_once('.').unwrap_or((s, "000000000"));
+    if into.len() != 9 || !into.bytes().all(|b| b.is_ascii_digit()) {
+        bail!(
+            "{}: {:?} needs nine digits after the turn",
+            error::E_TIME_PARSE,
+            s
+        );
+    }
+    let Ok(turn) = turn.parse::<i128>() else {
+        bail!("{}: {:?} has no valid turn", error::E_TIME_PARSE, s);
+    };
+    let into: i128 = into.parse()?;
+    match turn
+        .checked_mul(MICROMARKS_PER_TURN)
+        .and_then(|mm| mm.checked_add(into))
+    {
+        Some(mm) => Ok(mm),
+        None => bail!("{}: {:?} is out of range", error::E_TIME_PARSE, s),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        for mm in [
+            0,
+            1,
+            999_999_999,
+            1_000_000_000,
+            42_000_123_456,
+            -1,
+            -1_000_000_001,
+        ] {
+            assert_eq!(parse(&format(mm)).unwrap(), mm);
+        }
+        assert_eq!(format(42_000_123_456), "42.000123456");
+        assert_eq!(format(-1), "-1.999999999");
+        assert_eq!(parse("7").unwrap(), 7 * MICROMARKS_PER_TURN);
+        assert!(parse("7.5").is_err());
+        assert!(parse("x.000000000").is_err());
+        assert!(parse("1.-00000001").is_err());
+    }
+}
@@ -0,0 +1,67 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context, bail};
+use hl_core::{keymaster::signer::Signer, to_base64};
+
+use crate::agent::proto::{AgentRequest, AgentResponse};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Send one request to the agent at `socket` and read its answer.
+pub fn call(socket: &Path, request: &AgentRequest) -> anyhow::Result<AgentResponse> {
+    let mut stream = UnixStream::connect(socket)
+        .with_context(|| format!("failed to connect to agent at {:?}", socket))?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut line = serde_json::to_vec(request)?;
+    line.push(b'\n');
+    stream.write_all(&line)?;
+
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    match serde_json::from_str(&reply)? {
+        AgentResponse::Error { message } => bail!("agent: {}", message),
+        response => Ok(response),
+    }
+}
+
+/// Public keys the agent holds.
+pub fn list_keys(socket: &Path) -> anyhow::Result<Vec<[u8; 32]>> {
+    match call(socket, &AgentRequest::Keys)? {
+        AgentResponse::Keys { keys } => Ok(keys),
+        other => bail!("agent: unexpected reply {:?}", other),
+    }
+}
+
+/// Signs for one of the agent's keys, asking it each time.
+#[derive(Debug, Clone)]
+pub struct AgentSigner {
+    pub socket: PathBuf,
+    pub pk: [u8; 32],
+}
+
+impl Signer for AgentSigner {
+    fn public_key(&self) -> [u8; 32] {
+        self.pk
+    }
+
+    fn sign(&self, hash: &[u8; 32]) -> anyhow::Result<[u8; 64]> {
+        let request = AgentRequest::Sign {
+            pk: self.pk,
+            hash: *hash,
+        };
+        match call(&self.socket, &request)? {
+            AgentResponse::Signature { sig } => Ok(sig),
+            other => bail!(
+                "agent: unexpected reply signing for {}: {:?}",
+                to_base64(&self.pk),
+                other
+            ),
+        }
+    }
+}
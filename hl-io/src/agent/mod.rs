@@ -0,0 +1,6 @@
+//! Talking to `hl-agent`, which holds secret keys in its own process and
+//! signs on request over a Unix socket. One JSON request per line, one
+//! JSON response line back.
+
+pub mod client;
+pub mod proto;
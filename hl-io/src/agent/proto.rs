@@ -0,0 +1,58 @@
+use hl_core::b64::serde::{b64_32, b64_32_vec, b64_64};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum AgentRequest {
+    /// Public keys the agent can sign for
+    Keys,
+    Sign {
+        #[serde(with = "b64_32")]
+        pk: [u8; 32],
+        #[serde(with = "b64_32")]
+        hash: [u8; 32],
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "result", rename_all = "lowercase")]
+pub enum AgentResponse {
+    Keys {
+        #[serde(with = "b64_32_vec")]
+        keys: Vec<[u8; 32]>,
+    },
+    Signature {
+        #[serde(with = "b64_64")]
+        sig: [u8; 64],
+    },
+    Error {
+        message: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wire_shape() {
+        let request = AgentRequest::Sign {
+            pk: [0u8; 32],
+            hash: [0u8; 32],
+        };
+        let line = serde_json::to_string(&request).unwrap();
+        assert!(line.starts_with(r#"{"op":"sign","pk":"AAAA"#));
+        assert_eq!(
+            serde_json::from_str::<AgentRequest>(&line).unwrap(),
+            request
+        );
+        let reply: AgentResponse =
+            serde_json::from_str(r#"{"result":"error","message":"no"}"#).unwrap();
+        assert_eq!(
+            reply,
+            AgentResponse::Error {
+                message: "no".to_string()
+            }
+        );
+    }
+}
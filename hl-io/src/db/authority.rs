@@ -1,7 +1,8 @@
-use hl_core::{Authority, Key};
-use rusqlite::{Connection, params};
+use crate::db::Cache;
+use hl_core::{Authority, Key, key::fingerprint};
+use rusqlite::params;
 
-pub fn get_authorities(conn: &Connection, scope: &str) -> Result<Vec<Authority>, anyhow::Error> {
+pub fn get_authorities(conn: &Cache, scope: &str) -> Result<Vec<Authority>, anyhow::Error> {
     let mut stmt =
         conn.prepare("SELECT key, roles, eff, exp, note FROM authorities WHERE scope = ?1")?;
     let mut rows = stmt.query([scope])?;
@@ -25,8 +26,47 @@ pub fn get_authorities(conn: &Connection, scope: &str) -> Result<Vec<Authority>,
     Ok(authorities)
 }
 
+/// `pk`'s authority in `scope`, if it has one.
+pub fn get_authority(
+    conn: &Cache,
+    scope: &str,
+    pk: &[u8; 32],
+) -> Result<Option<Authority>, anyhow::Error> {
+    Ok(get_authorities(conn, scope)?
+        .into_iter()
+        .find(|auth| auth.key.pk.as_ref() == Some(pk)))
+}
+
+/// End `pk`'s authority in `scope` at micromark `at`. The row stays so
+/// earlier records still check out.
+pub fn expire_authority(
+    conn: &Cache,
+    scope: &str,
+    pk: &[u8; 32],
+    at: u64,
+) -> Result<(), anyhow::Error> {
+    conn.execute(
+        "UPDATE authorities SET exp = ?3 WHERE scope = ?1 AND key = ?2 AND (exp IS NULL OR exp > ?3)",
+        params![scope, pk, at],
+    )?;
+    Ok(())
+}
+
+/// The authority key, in any scope, whose fingerprint is `fp`.
+pub fn resolve_fingerprint(conn: &Cache, fp: &str) -> Result<Option<[u8; 32]>, anyhow::Error> {
+    let mut stmt = conn.prepare("SELECT DISTINCT key FROM authorities")?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let key: [u8; 32] = row.get("key")?;
+        if fingerprint::matches(fp, &key) {
+            return Ok(Some(key));
+        }
+    }
+    Ok(None)
+}
+
 pub fn store_authority(
-    conn: &Connection,
+    conn: &Cache,
     scope: &str,
     authority: &Authority,
 ) -> Result<(), anyhow::Error> {
@@ -45,12 +85,12 @@ pub fn store_authority(
     Ok(())
 }
 
-pub fn flush_authorities(cache: &Connection) -> Result<(), anyhow::Error> {
+pub fn flush_authorities(cache: &Cache) -> Result<(), anyhow::Error> {
     cache.execute("DELETE FROM authorities", params![])?;
     Ok(())
 }
 
-pub fn build_table(conn: &Connection) -> Result<(), anyhow::Error> {
+pub fn build_table(conn: &Cache) -> Result<(), anyhow::Error> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS authorities (
                 scope TEXT,
@@ -0,0 +1,126 @@
+use std::{ops::Deref, time::Duration};
+
+use hl_core::{Config, error};
+use rusqlite::Connection;
+
+/// How long a write waits on another connection's lock before failing.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The usher's handle on its SQLite cache. Every `db` function takes one;
+/// open it once from config and pass it down.
+pub struct Cache {
+    conn: Connection,
+}
+
+impl Cache {
+    pub fn open(path: &str) -> Result<Self, anyhow::Error> {
+        let conn = Connection::open(path)
+            .map_err(|e| anyhow::anyhow!("{}: {}: {}", error::E_DB_CONNECT, path, e))?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        Ok(Self { conn })
+    }
+
+    /// The cache at `config.cache_db`.
+    pub fn from_config(config: &Config) -> Result<Self, anyhow::Error> {
+        Self::open(&config.cache_db)
+    }
+
+    pub fn open_in_memory() -> Result<Self, anyhow::Error> {
+        Ok(Self {
+            conn: Connection::open_in_memory()?,
+        })
+    }
+
+    /// Start a transaction scope. Scopes nest as savepoints; one dropped
+    /// without `commit` rolls back everything written since it began.
+    pub fn transaction(&self) -> Result<CacheTx<'_>, anyhow::Error> {
+        self.conn
+            .execute_batch("SAVEPOINT hl_tx")
+            .map_err(|e| anyhow::anyhow!("{}: {}", error::E_DB_TX_BEGIN, e))?;
+        Ok(CacheTx {
+            cache: self,
+            done: false,
+        })
+    }
+}
+
+impl Deref for Cache {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        &self.conn
+    }
+}
+
+/// An open transaction scope on a `Cache`, from `Cache::transaction`.
+pub struct CacheTx<'a> {
+    cache: &'a Cache,
+    done: bool,
+}
+
+impl CacheTx<'_> {
+    /// Keep what was written in this scope. Fails with `E_DB_TX_COMMIT`
+    /// after rolling the scope back.
+    pub fn commit(mut self) -> Result<(), anyhow::Error> {
+        self.done = true;
+        if let Err(e) = self.cache.execute_batch("RELEASE hl_tx") {
+            self.rollback_quietly();
+            anyhow::bail!("{}: {}", error::E_DB_TX_COMMIT, e);
+        }
+        Ok(())
+    }
+
+    pub fn rollback(mut self) -> Result<(), anyhow::Error> {
+        self.done = true;
+        self.cache
+            .execute_batch("ROLLBACK TO hl_tx; RELEASE hl_tx")?;
+        Ok(())
+    }
+
+    fn rollback_quietly(&self) {
+        if let Err(e) = self.cache.execute_batch("ROLLBACK TO hl_tx; RELEASE hl_tx") {
+            eprintln!("Error rolling back cache transaction: {}", e);
+        }
+    }
+}
+
+impl Drop for CacheTx<'_> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.rollback_quietly();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_scopes_commit_or_roll_back_together() {
+        let cache = Cache::open_in_memory().unwrap();
+        cache.execute("CREATE TABLE t (n INTEGER)", []).unwrap();
+        let count = |cache: &Cache| -> i64 {
+            cache
+                .query_row("SELECT COUNT(*) FROM t", [], |row| row.get(0))
+                .unwrap()
+        };
+
+        let outer = cache.transaction().unwrap();
+        cache.execute("INSERT INTO t VALUES (1)", []).unwrap();
+        let inner = cache.transaction().unwrap();
+        cache.execute("INSERT INTO t VALUES (2)", []).unwrap();
+        drop(inner);
+        assert_eq!(count(&cache), 1);
+        outer.commit().unwrap();
+        assert_eq!(count(&cache), 1);
+
+        let outer = cache.transaction().unwrap();
+        let inner = cache.transaction().unwrap();
+        cache.execute("INSERT INTO t VALUES (3)", []).unwrap();
+        inner.commit().unwrap();
+        outer.rollback().unwrap();
+        assert_eq!(count(&cache), 1);
+        assert!(cache.is_autocommit());
+    }
+}
@@ -0,0 +1,58 @@
+use crate::db::Cache;
+use hl_core::{
+    error,
+    scope::scope::scope_lineage,
+    time::clock::{Clock, GTClock},
+};
+use rusqlite::{OptionalExtension, params};
+
+/// Record the GT epoch a scope's genesis declared.
+pub fn store_epoch(cache: &Cache, scope: &str, gt: &GTClock) -> Result<(), anyhow::Error> {
+    cache.execute(
+        "INSERT OR REPLACE INTO epochs (scope, unix_ms) VALUES (?1, ?2)",
+        params![scope, gt.epoch_unix_ms as i64],
+    )?;
+    Ok(())
+}
+
+/// The GT clock for a scope: its own genesis epoch, else the nearest
+/// ancestor's.
+pub fn gt_clock(cache: &Cache, scope: &str) -> Result<GTClock, anyhow::Error> {
+    let mut stmt = cache.prepare("SELECT unix_ms FROM epochs WHERE scope = ?1")?;
+    for ancestor in scope_lineage(scope) {
+        let unix_ms: Option<i64> = stmt
+            .query_row(params![ancestor], |row| row.get(0))
+            .optional()?;
+        if let Some(unix_ms) = unix_ms {
+            return Ok(GTClock::new(unix_ms as i128));
+        }
+    }
+    Err(anyhow::anyhow!(
+        "{}: no genesis epoch known for scope {:?}",
+        error::E_EPOCH_UNKNOWN,
+        scope
+    ))
+}
+
+/// `context.at` for right now in `scope`.
+pub fn now_at(cache: &Cache, scope: &str, clock: &dyn Clock) -> Result<u64, anyhow::Error> {
+    gt_clock(cache, scope)?.micromarks_on(clock)
+}
+
+pub fn flush_epochs(cache: &Cache) -> Result<(), anyhow::Error> {
+    cache.execute("DELETE FROM epochs", params![])?;
+    Ok(())
+}
+
+pub fn build_table(cache: &Cache) -> Result<(), anyhow::Error> {
+    cache.execute(
+        "CREATE TABLE IF NOT EXISTS epochs (
+                scope TEXT,
+                unix_ms INTEGER,
+                PRIMARY KEY (scope)
+            )
+        ",
+        params![],
+    )?;
+    Ok(())
+}
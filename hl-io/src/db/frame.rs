@@ -0,0 +1,55 @@
+use crate::db::Cache;
+use hl_core::{
+    scope::scope::scope_lineage,
+    spatial::frame::{Frame, FrameRegistry},
+};
+use rusqlite::params;
+
+pub fn store_frame(cache: &Cache, scope: &str, frame: &Frame) -> Result<(), anyhow::Error> {
+    cache.execute(
+        "INSERT OR REPLACE INTO frames (scope, name, definition) VALUES (?1, ?2, ?3)",
+        params![scope, frame.name, serde_json::to_string(frame)?],
+    )?;
+    Ok(())
+}
+
+pub fn get_frames(cache: &Cache, scope: &str) -> Result<Vec<Frame>, anyhow::Error> {
+    let mut stmt = cache.prepare("SELECT definition FROM frames WHERE scope = ?1")?;
+    let mut rows = stmt.query(params![scope])?;
+
+    let mut frames = vec![];
+    while let Some(row) = rows.next()? {
+        let definition: String = row.get("definition")?;
+        frames.push(serde_json::from_str(&definition)?);
+    }
+    Ok(frames)
+}
+
+/// Registry as seen from `scope`: its own frames and its ancestors'.
+pub fn load_registry(cache: &Cache, scope: &str) -> Result<FrameRegistry, anyhow::Error> {
+    let mut registry = FrameRegistry::new();
+    for s in scope_lineage(scope) {
+        for frame in get_frames(cache, s)? {
+            registry.define(s, frame);
+        }
+    }
+    Ok(registry)
+}
+
+pub fn flush_frames(cache: &Cache) -> Result<(), anyhow::Error> {
+    cache.execute("DELETE FROM frames", params![])?;
+    Ok(())
+}
+
+pub fn build_table(cache: &Cache) -> Result<(), anyhow::Error> {
+    cache.execute(
+        "CREATE TABLE IF NOT EXISTS frames (
+                scope TEXT,
+                name TEXT,
+                definition TEXT,
+                PRIMARY KEY (scope, name)
+            )",
+        params![],
+    )?;
+    Ok(())
+}
@@ -1,10 +1,8 @@
-use rusqlite::{Connection, params};
+use rusqlite::params;
 
-use crate::db::connect_db;
-
-pub fn get_head(scope: &str) -> Result<[u8; 32], anyhow::Error> {
-    let cache = connect_db("./ledger/cache/cache.db")?;
+use crate::db::Cache;
 
+pub fn get_head(cache: &Cache, scope: &str) -> Result<[u8; 32], anyhow::Error> {
     let mut stmt = cache.prepare("SELECT head FROM heads WHERE scope = ?1")?;
     let mut rows = stmt.query(rusqlite::params![scope])?;
 
@@ -17,7 +15,7 @@ pub fn get_head(scope: &str) -> Result<[u8; 32], anyhow::Error> {
     }
 }
 
-pub fn set_head(cache: &Connection, scope: &str, head: &[u8; 32]) -> Result<(), anyhow::Error> {
+pub fn set_head(cache: &Cache, scope: &str, head: &[u8; 32]) -> Result<(), anyhow::Error> {
     let head_b64 = hl_core::to_base64(head);
     cache.execute(
         "INSERT OR REPLACE INTO heads (scope, head) VALUES (?1, ?2)",
@@ -26,12 +24,12 @@ pub fn set_head(cache: &Connection, scope: &str, head: &[u8; 32]) -> Result<(),
     Ok(())
 }
 
-pub fn flush_heads(cache: &Connection) -> Result<(), anyhow::Error> {
+pub fn flush_heads(cache: &Cache) -> Result<(), anyhow::Error> {
     cache.execute("DELETE FROM heads", params![])?;
     Ok(())
 }
 
-pub fn build_table(cache: &Connection) -> Result<(), anyhow::Error> {
+pub fn build_table(cache: &Cache) -> Result<(), anyhow::Error> {
     cache.execute(
         "CREATE TABLE IF NOT EXISTS heads (
                 scope TEXT PRIMARY KEY,
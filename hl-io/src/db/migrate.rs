@@ -0,0 +1,133 @@
+use hl_core::error;
+
+use crate::db::{
+    Cache, authority, epoch, frame, head, policy, record_type, revocation, rhex, rule, schema,
+    scope, spatial, usher,
+};
+
+/// One step of the cache schema, applied when `user_version` is below
+/// `version`. Append new steps; never edit one that has shipped.
+struct Migration {
+    version: u32,
+    note: &'static str,
+    up: fn(&Cache) -> Result<(), anyhow::Error>,
+}
+
+const MIGRATIONS: &[Migration] = &[Migration {
+    version: 1,
+    note: "versioned baseline",
+    up: baseline,
+}];
+
+/// Schema version this build reads and writes.
+pub const SCHEMA_VERSION: u32 = MIGRATIONS[MIGRATIONS.len() - 1].version;
+
+/// The cache's `user_version`; 0 for one made before versioning.
+pub fn schema_version(cache: &Cache) -> Result<u32, anyhow::Error> {
+    Ok(cache.pragma_query_value(None, "user_version", |row| row.get(0))?)
+}
+
+/// Bring the cache up to `SCHEMA_VERSION`, one transaction per step.
+/// Refuses a cache written by a newer build. Returns the version found.
+pub fn migrate(cache: &Cache) -> Result<u32, anyhow::Error> {
+    let found = schema_version(cache)?;
+    if found > SCHEMA_VERSION {
+        anyhow::bail!(
+            "{}: cache schema v{} is newer than this build's v{}",
+            error::E_MIGRATION_REQUIRED,
+            found,
+            SCHEMA_VERSION
+        );
+    }
+    for migration in MIGRATIONS.iter().filter(|m| m.version > found) {
+        let step = || -> Result<(), anyhow::Error> {
+            let tx = cache.transaction()?;
+            (migration.up)(cache)?;
+            cache.pragma_update(None, "user_version", migration.version)?;
+            tx.commit()
+        };
+        step().map_err(|e| {
+            anyhow::anyhow!(
+                "{}: cache migration v{} ({}) failed: {}",
+                error::E_MIGRATION_FAILED,
+                migration.version,
+                migration.note,
+                e
+            )
+        })?;
+    }
+    Ok(found)
+}
+
+/// Every table as of versioning, plus the columns caches from before
+/// it may lack.
+fn baseline(cache: &Cache) -> Result<(), anyhow::Error> {
+    authority::build_table(cache)?;
+    epoch::build_table(cache)?;
+    frame::build_table(cache)?;
+    policy::build_table(cache)?;
+    record_type::build_table(cache)?;
+    revocation::build_table(cache)?;
+    rule::build_table(cache)?;
+    schema::build_table(cache)?;
+    scope::build_table(cache)?;
+    spatial::build_table(cache)?;
+    usher::build_table(cache)?;
+    rhex::build_table(cache)?;
+    head::build_table(cache)?;
+    add_column(cache, "rhex", "magic", "INTEGER")?;
+    add_column(cache, "rhex", "delegation", "TEXT")?;
+    Ok(())
+}
+
+/// `ALTER TABLE .. ADD COLUMN` unless the column is already there.
+fn add_column(cache: &Cache, table: &str, column: &str, decl: &str) -> Result<(), anyhow::Error> {
+    let mut stmt = cache.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, _>>()?
+        .iter()
+        .any(|name| name == column);
+    if !exists {
+        cache.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upgrades_unversioned_caches_and_refuses_newer_ones() {
+        let cache = Cache::open_in_memory().unwrap();
+        // The rhex table as caches built before versioning had it
+        cache
+            .execute(
+                "CREATE TABLE rhex (previous_hash TEXT, scope TEXT, nonce INTEGER,
+                    author_pk TEXT, usher_pk TEXT, record_type TEXT, data TEXT,
+                    at INTEGER, spacial TEXT, signatures TEXT, current_hash TEXT,
+                    PRIMARY KEY (previous_hash, scope))",
+                [],
+            )
+            .unwrap();
+        assert_eq!(migrate(&cache).unwrap(), 0);
+        assert_eq!(schema_version(&cache).unwrap(), SCHEMA_VERSION);
+        cache
+            .execute("UPDATE rhex SET magic = 1, delegation = NULL", [])
+            .unwrap();
+        cache
+            .prepare("SELECT scope, key, at FROM revocations")
+            .unwrap();
+        assert_eq!(migrate(&cache).unwrap(), SCHEMA_VERSION);
+
+        cache
+            .pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+        let err = migrate(&cache).unwrap_err().to_string();
+        assert!(err.starts_with(error::E_MIGRATION_REQUIRED));
+    }
+}
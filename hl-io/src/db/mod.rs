@@ -1,14 +1,23 @@
-use rusqlite::Connection;
 use std::fs;
 
 pub mod authority;
+mod cache;
+pub mod epoch;
+pub mod frame;
 pub mod head;
+pub mod migrate;
 pub mod policy;
+pub mod record_type;
+pub mod revocation;
 pub mod rhex;
 pub mod rule;
+pub mod schema;
 pub mod scope;
+pub mod spatial;
 pub mod usher;
 
+pub use cache::{Cache, CacheTx};
+
 pub fn delete_db(path: &str) -> Result<(), anyhow::Error> {
     let delete_status = fs::remove_file(path);
     if delete_status.is_err() {
@@ -18,32 +27,27 @@ pub fn delete_db(path: &str) -> Result<(), anyhow::Error> {
     Ok(())
 }
 
-pub fn connect_db(path: &str) -> Result<Connection, anyhow::Error> {
-    let conn = Connection::open(path)?;
-    Ok(conn)
-}
-
-pub fn create_db(path: &str) -> Result<Connection, anyhow::Error> {
-    delete_db(path)?;
-    let conn = Connection::open(path)?;
-    authority::build_table(&conn)?;
-    policy::build_table(&conn)?;
-    rule::build_table(&conn)?;
-    scope::build_table(&conn)?;
-    usher::build_table(&conn)?;
-    rhex::build_table(&conn)?;
-    head::build_table(&conn)?;
-    Ok(conn)
+/// Open the cache, creating it or migrating it to the current schema.
+pub fn create_db(path: &str) -> Result<Cache, anyhow::Error> {
+    let cache = Cache::open(path)?;
+    migrate::migrate(&cache)?;
+    Ok(cache)
 }
 
-pub fn flush_all(path: &str) -> Result<(), anyhow::Error> {
-    let cache = Connection::open(path)?;
-    authority::flush_authorities(&cache)?;
-    head::flush_heads(&cache)?;
-    policy::flush_policies(&cache)?;
-    rhex::flush_rhex(&cache)?;
-    rule::flush_rules(&cache)?;
-    scope::flush_scopes(&cache)?;
-    usher::flush_ushers(&cache)?;
-    Ok(())
+pub fn flush_all(cache: &Cache) -> Result<(), anyhow::Error> {
+    let tx = cache.transaction()?;
+    authority::flush_authorities(cache)?;
+    epoch::flush_epochs(cache)?;
+    frame::flush_frames(cache)?;
+    head::flush_heads(cache)?;
+    policy::flush_policies(cache)?;
+    record_type::flush_record_types(cache)?;
+    revocation::flush_revocations(cache)?;
+    rhex::flush_rhex(cache)?;
+    rule::flush_rules(cache)?;
+    schema::flush_schemas(cache)?;
+    scope::flush_scopes(cache)?;
+    spatial::flush_spatial(cache)?;
+    usher::flush_ushers(cache)?;
+    tx.commit()
 }
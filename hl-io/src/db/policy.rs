@@ -1,7 +1,8 @@
+use crate::db::Cache;
 use hl_core::Policy;
-use rusqlite::{Connection, params};
+use rusqlite::params;
 
-pub fn store_policy(cache: &Connection, scope: &str, policy: &Policy) -> Result<(), anyhow::Error> {
+pub fn store_policy(cache: &Cache, scope: &str, policy: &Policy) -> Result<(), anyhow::Error> {
     let status = cache.execute(
         "INSERT OR REPLACE INTO policies (scope, quorum_ttl, eff, exp, note) VALUES (?1, ?2, ?3, ?4, ?5)",
         params![scope, policy.quorum_ttl, policy.eff, policy.exp, policy.note],
@@ -16,11 +17,7 @@ pub fn store_policy(cache: &Connection, scope: &str, policy: &Policy) -> Result<
     Ok(())
 }
 
-pub fn store_policy_full(
-    cache: &Connection,
-    scope: &str,
-    policy: &Policy,
-) -> Result<(), anyhow::Error> {
+pub fn store_policy_full(cache: &Cache, scope: &str, policy: &Policy) -> Result<(), anyhow::Error> {
     store_policy(&cache, &scope, &policy)?;
     for rule in policy.rules.iter() {
         crate::db::rule::store_rule(cache, &scope, rule)?;
@@ -29,7 +26,7 @@ pub fn store_policy_full(
     Ok(())
 }
 
-pub fn retrieve_policy(cache: &Connection, scope: &str) -> Result<Policy, anyhow::Error> {
+pub fn retrieve_policy(cache: &Cache, scope: &str) -> Result<Policy, anyhow::Error> {
     let mut stmt =
         cache.prepare("SELECT quorum_ttl, eff, exp, note FROM policies WHERE scope = ?1")?;
     let mut rows = stmt.query(params![scope])?;
@@ -48,19 +45,19 @@ pub fn retrieve_policy(cache: &Connection, scope: &str) -> Result<Policy, anyhow
         Err(anyhow::anyhow!("Policy not found for scope: {}", scope))
     }
 }
-pub fn clear_scope_policy(cache: &Connection, scope: &str) -> Result<(), anyhow::Error> {
+pub fn clear_scope_policy(cache: &Cache, scope: &str) -> Result<(), anyhow::Error> {
     cache.execute("DELETE FROM policies WHERE scope = ?1", params![scope])?;
-    crate::db::rule::clear_scope_rules(scope)?;
+    crate::db::rule::clear_scope_rules(cache, scope)?;
 
     Ok(())
 }
 
-pub fn flush_policies(cache: &Connection) -> Result<(), anyhow::Error> {
+pub fn flush_policies(cache: &Cache) -> Result<(), anyhow::Error> {
     cache.execute("DELETE FROM policies", params![])?;
     Ok(())
 }
 
-pub fn build_table(cache: &Connection) -> Result<(), anyhow::Error> {
+pub fn build_table(cache: &Cache) -> Result<(), anyhow::Error> {
     cache.execute(
         "CREATE TABLE IF NOT EXISTS policies (
                 scope TEXT,
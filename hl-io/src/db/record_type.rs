@@ -0,0 +1,64 @@
+use crate::db::Cache;
+use hl_core::{
+    rhex::record_types::{RecordTypeDef, RecordTypeRegistry},
+    scope::scope::scope_lineage,
+};
+use rusqlite::params;
+
+pub fn store_record_type(
+    cache: &Cache,
+    scope: &str,
+    def: &RecordTypeDef,
+) -> Result<(), anyhow::Error> {
+    cache.execute(
+        "INSERT OR REPLACE INTO record_types (scope, name, schema, stateful) VALUES (?1, ?2, ?3, ?4)",
+        params![scope, def.name, def.schema, def.stateful],
+    )?;
+    Ok(())
+}
+
+pub fn get_record_types(cache: &Cache, scope: &str) -> Result<Vec<RecordTypeDef>, anyhow::Error> {
+    let mut stmt =
+        cache.prepare("SELECT name, schema, stateful FROM record_types WHERE scope = ?1")?;
+    let mut rows = stmt.query(params![scope])?;
+
+    let mut defs = vec![];
+    while let Some(row) = rows.next()? {
+        defs.push(RecordTypeDef {
+            name: row.get("name")?,
+            schema: row.get("schema")?,
+            stateful: row.get("stateful")?,
+        });
+    }
+    Ok(defs)
+}
+
+/// Registry as seen from `scope`: its own custom types and its ancestors'.
+pub fn load_registry(cache: &Cache, scope: &str) -> Result<RecordTypeRegistry, anyhow::Error> {
+    let mut registry = RecordTypeRegistry::new();
+    for s in scope_lineage(scope) {
+        for def in get_record_types(cache, s)? {
+            registry.define(s, def);
+        }
+    }
+    Ok(registry)
+}
+
+pub fn flush_record_types(cache: &Cache) -> Result<(), anyhow::Error> {
+    cache.execute("DELETE FROM record_types", params![])?;
+    Ok(())
+}
+
+pub fn build_table(cache: &Cache) -> Result<(), anyhow::Error> {
+    cache.execute(
+        "CREATE TABLE IF NOT EXISTS record_types (
+                scope TEXT,
+                name TEXT,
+                schema TEXT,
+                stateful INTEGER,
+                PRIMARY KEY (scope, name)
+            )",
+        params![],
+    )?;
+    Ok(())
+}
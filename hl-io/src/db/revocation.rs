@@ -0,0 +1,52 @@
+use crate::db::Cache;
+use rusqlite::{OptionalExtension, params};
+
+/// `pk` may sign nothing in `scope` after micromark `at`.
+pub fn store_revocation(
+    cache: &Cache,
+    scope: &str,
+    pk: &[u8; 32],
+    at: u64,
+) -> Result<(), anyhow::Error> {
+    // A key revoked twice stays revoked from the earlier time
+    cache.execute(
+        "INSERT INTO revocations (scope, key, at) VALUES (?1, ?2, ?3)
+            ON CONFLICT (scope, key) DO UPDATE SET at = MIN(at, excluded.at)",
+        params![scope, pk, at],
+    )?;
+    Ok(())
+}
+
+/// When `pk` was revoked in `scope`, if it was.
+pub fn get_revocation(
+    cache: &Cache,
+    scope: &str,
+    pk: &[u8; 32],
+) -> Result<Option<u64>, anyhow::Error> {
+    Ok(cache
+        .query_row(
+            "SELECT at FROM revocations WHERE scope = ?1 AND key = ?2",
+            params![scope, pk],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+pub fn flush_revocations(cache: &Cache) -> Result<(), anyhow::Error> {
+    cache.execute("DELETE FROM revocations", params![])?;
+    Ok(())
+}
+
+pub fn build_table(cache: &Cache) -> Result<(), anyhow::Error> {
+    cache.execute(
+        "CREATE TABLE IF NOT EXISTS revocations (
+                scope TEXT,
+                key BLOB,
+                at INTEGER,
+                PRIMARY KEY (scope, key)
+            )
+        ",
+        params![],
+    )?;
+    Ok(())
+}
@@ -1,25 +1,36 @@
+use crate::db::Cache;
 use crate::sink::RhexSink;
 use crate::source::RhexSource;
-use hl_core::{Context, Intent, Rhex, b64::b64::from_base64_to_32};
-use rusqlite::{Connection, params};
+use hl_core::{
+    Context, Intent, Rhex,
+    b64::b64::from_base64_to_32,
+    rhex::rhex::{RHEX_MAGIC_V0, RHEX_MAGIC_V1},
+};
+use rusqlite::params;
 
-pub struct CacheSource {
+pub struct CacheSource<'a> {
     scope: String,
-    conn: Connection,
+    cache: &'a Cache,
+    // rows already yielded, in append order
+    offset: u64,
 }
 
-impl CacheSource {
-    pub fn new(path: String, scope: String) -> Self {
-        let conn = Connection::open(&path).unwrap();
-        Self { conn, scope }
+impl<'a> CacheSource<'a> {
+    pub fn new(cache: &'a Cache, scope: String) -> Self {
+        Self {
+            cache,
+            scope,
+            offset: 0,
+        }
     }
 }
 
-impl RhexSource for CacheSource {
+impl RhexSource for CacheSource<'_> {
     fn next(&mut self) -> Result<Option<Rhex>, anyhow::Error> {
-        let mut stmt = self.conn.prepare(
+        let mut stmt = self.cache.prepare(
             r#"
             SELECT
+                magic,
                 previous_hash,
                 scope,
                 nonce,
@@ -29,13 +40,17 @@ impl RhexSource for CacheSource {
                 data,
                 at,
                 spacial,
-                signatures
+                signatures,
+                current_hash,
+                delegation
             FROM rhex
             WHERE scope = ?1
+            ORDER BY rowid
+            LIMIT 1 OFFSET ?2
             "#,
         )?;
 
-        let mut rows = stmt.query(rusqlite::params![&self.scope])?;
+        let mut rows = stmt.query(rusqlite::params![&self.scope, self.offset])?;
 
         fn parse_spacial(
             s: Option<String>,
@@ -56,10 +71,21 @@ impl RhexSource for CacheSource {
         if let Some(row) = rows.next()? {
             let data_json: String = row.get("data")?;
             let signatures_json: String = row.get("signatures")?;
+            let delegation_json: Option<String> = row.get("delegation")?;
             let (x, y, z, refer) = parse_spacial(row.get::<_, Option<String>>("spacial")?);
+            // Rows from before the magic column was tracked are v0.
+            let magic = match row.get::<_, Option<u16>>("magic")? {
+                None | Some(0) => RHEX_MAGIC_V0,
+                Some(1) => RHEX_MAGIC_V1,
+                Some(other) => anyhow::bail!(
+                    "{}: cached record has unknown format version {}",
+                    hl_core::error::E_MAGIC_BAD,
+                    other
+                ),
+            };
 
             let rhex = Rhex {
-                magic: *b"RHEX\x00\x00",
+                magic,
                 intent: Intent {
                     previous_hash: row.get("previous_hash")?,
                     scope: row.get("scope")?,
@@ -68,6 +94,9 @@ impl RhexSource for CacheSource {
                     usher_pk: row.get("usher_pk")?,
                     record_type: row.get("record_type")?,
                     data: serde_json::from_str(&data_json)?,
+                    delegation: delegation_json
+                        .map(|d| serde_json::from_str(&d))
+                        .transpose()?,
                 },
                 context: Context {
                     at: row.get("at")?,
@@ -77,9 +106,13 @@ impl RhexSource for CacheSource {
                     refer,
                 },
                 signatures: serde_json::from_str(&signatures_json)?,
-                current_hash: None,
+                current_hash: row
+                    .get::<_, Option<String>>("current_hash")?
+                    .map(|h| from_base64_to_32(&h))
+                    .transpose()?,
             };
 
+            self.offset += 1;
             return Ok(Some(rhex));
         }
 
@@ -87,21 +120,26 @@ impl RhexSource for CacheSource {
     }
 }
 
-pub struct CacheSink {
-    conn: Connection,
+pub struct CacheSink<'a> {
+    cache: &'a Cache,
 }
 
-impl CacheSink {
-    pub fn new(path: String) -> Self {
-        let conn = Connection::open(&path).unwrap();
-        Self { conn }
+impl<'a> CacheSink<'a> {
+    pub fn new(cache: &'a Cache) -> Self {
+        Self { cache }
     }
 }
 
-impl RhexSink for CacheSink {
+impl RhexSink for CacheSink<'_> {
     fn send(&mut self, r: &Rhex) -> Result<(), anyhow::Error> {
         let data_string = serde_json::to_string(&r.intent.data)?;
         let signatures = serde_json::to_string(&r.signatures)?;
+        let delegation = r
+            .intent
+            .delegation
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?;
         let spacial = if r.context.x.is_some() {
             // If we have one we should have them all.
 
@@ -120,8 +158,8 @@ impl RhexSink for CacheSink {
         } else {
             String::new()
         };
-        self.conn.execute(
-            "INSERT INTO rhex (previous_hash, scope, nonce, author_pk, usher_pk, record_type, data, at, spacial, signatures) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        self.cache.execute(
+            "INSERT INTO rhex (previous_hash, scope, nonce, author_pk, usher_pk, record_type, data, at, spacial, signatures, magic, current_hash, delegation) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
             params![
                 r.intent.previous_hash,
                 r.intent.scope,
@@ -132,7 +170,10 @@ impl RhexSink for CacheSink {
                 data_string,
                 r.context.at,
                 spacial,
-                signatures
+                signatures,
+                r.version()?,
+                r.current_hash.map(|h| hl_core::to_base64(&h)),
+                delegation
             ],
         )?;
         Ok(())
@@ -142,7 +183,7 @@ impl RhexSink for CacheSink {
     }
 }
 
-pub fn check_nonce(cache: &Connection, scope: &str, nonce: &str) -> Result<bool, anyhow::Error> {
+pub fn check_nonce(cache: &Cache, scope: &str, nonce: &str) -> Result<bool, anyhow::Error> {
     let mut stmt =
         cache.prepare("SELECT COUNT(*) as count FROM rhex WHERE scope = ?1 AND nonce = ?2")?;
     let mut rows = stmt.query(params![scope, nonce])?;
@@ -154,13 +195,13 @@ pub fn check_nonce(cache: &Connection, scope: &str, nonce: &str) -> Result<bool,
     }
 }
 
-pub fn flush_rhex(cache: &Connection) -> Result<(), anyhow::Error> {
+pub fn flush_rhex(cache: &Cache) -> Result<(), anyhow::Error> {
     cache.execute("DELETE FROM rhex", params![])?;
     Ok(())
 }
 
 pub fn get_last_append(
-    cache: &Connection,
+    cache: &Cache,
     scope: &str,
     author_pk: &[u8; 32],
     record_type: &str,
@@ -177,7 +218,7 @@ pub fn get_last_append(
     }
 }
 
-pub fn build_table(conn: &Connection) -> Result<(), anyhow::Error> {
+pub fn build_table(conn: &Cache) -> Result<(), anyhow::Error> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS rhex (
                 previous_hash TEXT,
@@ -191,9 +232,33 @@ pub fn build_table(conn: &Connection) -> Result<(), anyhow::Error> {
                 spacial TEXT,
                 signatures TEXT,
                 current_hash TEXT,
+                magic INTEGER,
+                delegation TEXT,
                 PRIMARY KEY (previous_hash, scope)
             )",
         [],
     )?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_cached_version_is_refused() {
+        let cache = Cache::open_in_memory().unwrap();
+        build_table(&cache).unwrap();
+        let mut rhex = Rhex::new();
+        rhex.intent.scope = "acme".to_string();
+        CacheSink::new(&cache).send(&rhex).unwrap();
+
+        let mut source = CacheSource::new(&cache, "acme".to_string());
+        assert_eq!(source.next().unwrap().unwrap().magic, rhex.magic);
+
+        cache.execute("UPDATE rhex SET magic = 9", []).unwrap();
+        let mut source = CacheSource::new(&cache, "acme".to_string());
+        let err = source.next().unwrap_err().to_string();
+        assert!(err.starts_with(hl_core::error::E_MAGIC_BAD), "{}", err);
+    }
+}
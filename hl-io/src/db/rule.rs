@@ -1,9 +1,9 @@
 use hl_core::policy::rule::Rule;
-use rusqlite::{Connection, params};
+use rusqlite::params;
 
-use crate::db::connect_db;
+use crate::db::Cache;
 
-pub fn store_rule(cache: &Connection, scope: &str, rule: &Rule) -> Result<(), anyhow::Error> {
+pub fn store_rule(cache: &Cache, scope: &str, rule: &Rule) -> Result<(), anyhow::Error> {
     let status = cache.execute(
         "INSERT OR REPLACE INTO 
         rules (scope, record_types, append_roles, quorum_k, quorum_roles, rate_per_mark) 
@@ -28,7 +28,7 @@ pub fn store_rule(cache: &Connection, scope: &str, rule: &Rule) -> Result<(), an
     Ok(())
 }
 
-pub fn get_rules(cache: &Connection, scope: &str) -> Result<Vec<Rule>, anyhow::Error> {
+pub fn get_rules(cache: &Cache, scope: &str) -> Result<Vec<Rule>, anyhow::Error> {
     let mut stmt = cache.prepare(
         "SELECT record_types, append_roles, quorum_k, quorum_roles, rate_per_mark 
         FROM rules WHERE scope = ?1",
@@ -66,18 +66,17 @@ pub fn get_rules(cache: &Connection, scope: &str) -> Result<Vec<Rule>, anyhow::E
     Ok(rules)
 }
 
-pub fn clear_scope_rules(scope: &str) -> Result<(), anyhow::Error> {
-    let cache = connect_db("./ledger/cache/cache.db")?;
+pub fn clear_scope_rules(cache: &Cache, scope: &str) -> Result<(), anyhow::Error> {
     cache.execute("DELETE FROM rules WHERE scope = ?1", params![scope])?;
     Ok(())
 }
 
-pub fn flush_rules(cache: &Connection) -> Result<(), anyhow::Error> {
+pub fn flush_rules(cache: &Cache) -> Result<(), anyhow::Error> {
     cache.execute("DELETE FROM rules", params![])?;
     Ok(())
 }
 
-pub fn build_table(cache: &Connection) -> Result<(), anyhow::Error> {
+pub fn build_table(cache: &Cache) -> Result<(), anyhow::Error> {
     cache.execute(
         "CREATE TABLE IF NOT EXISTS rules (
             scope TEXT NOT NULL,
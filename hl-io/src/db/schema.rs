@@ -0,0 +1,47 @@
+use crate::db::Cache;
+use hl_core::schema::Schema;
+use rusqlite::{OptionalExtension, params};
+
+pub fn store_schema(cache: &Cache, scope: &str, schema: &Schema) -> Result<(), anyhow::Error> {
+    cache.execute(
+        "INSERT OR REPLACE INTO schemas (scope, name, definition) VALUES (?1, ?2, ?3)",
+        params![scope, schema.name, serde_json::to_string(schema)?],
+    )?;
+    Ok(())
+}
+
+/// `None` until a `schema:define` for `name` has been cached.
+pub fn retrieve_schema(
+    cache: &Cache,
+    scope: &str,
+    name: &str,
+) -> Result<Option<Schema>, anyhow::Error> {
+    let definition: Option<String> = cache
+        .query_row(
+            "SELECT definition FROM schemas WHERE scope = ?1 AND name = ?2",
+            params![scope, name],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(definition
+        .map(|definition| serde_json::from_str(&definition))
+        .transpose()?)
+}
+
+pub fn flush_schemas(cache: &Cache) -> Result<(), anyhow::Error> {
+    cache.execute("DELETE FROM schemas", params![])?;
+    Ok(())
+}
+
+pub fn build_table(cache: &Cache) -> Result<(), anyhow::Error> {
+    cache.execute(
+        "CREATE TABLE IF NOT EXISTS schemas (
+                scope TEXT,
+                name TEXT,
+                definition TEXT,
+                PRIMARY KEY (scope, name)
+            )",
+        params![],
+    )?;
+    Ok(())
+}
@@ -1,10 +1,9 @@
 use hl_core::scope::scope::Scope;
-use rusqlite::{Connection, params};
+use rusqlite::params;
 
-use crate::db::connect_db;
+use crate::db::Cache;
 
-pub fn store_scope(scope: &Scope) -> Result<(), anyhow::Error> {
-    let cache = connect_db("./ledger/cache/cache.db")?;
+pub fn store_scope(cache: &Cache, scope: &Scope) -> Result<(), anyhow::Error> {
     let status = cache.execute(
         "INSERT OR REPLACE INTO scopes (scope, role, last_synced) VALUES (?1, ?2, ?3)",
         params![scope.name, scope.role.to_string(), scope.last_synced],
@@ -19,7 +18,7 @@ pub fn store_scope(scope: &Scope) -> Result<(), anyhow::Error> {
     Ok(())
 }
 
-pub fn store_scope_full(cache: &Connection, scope: &Scope) -> Result<(), anyhow::Error> {
+pub fn store_scope_full(cache: &Cache, scope: &Scope) -> Result<(), anyhow::Error> {
     let status = cache.execute(
         "INSERT OR REPLACE INTO scopes (scope, role, last_synced) VALUES (?1, ?2, ?3)",
         params![scope.name, scope.role.to_string(), scope.last_synced],
@@ -47,13 +46,13 @@ pub fn store_scope_full(cache: &Connection, scope: &Scope) -> Result<(), anyhow:
     Ok(())
 }
 
-pub fn scope_exists(cache: &Connection, scope_name: &str) -> Result<bool, anyhow::Error> {
+pub fn scope_exists(cache: &Cache, scope_name: &str) -> Result<bool, anyhow::Error> {
     let mut stmt = cache.prepare("SELECT 1 FROM scopes WHERE scope = ?1 LIMIT 1")?;
     let mut rows = stmt.query(params![scope_name])?;
     Ok(rows.next()?.is_some())
 }
 
-pub fn retrieve_scope(cache: &Connection, scope_name: &str) -> Result<Scope, anyhow::Error> {
+pub fn retrieve_scope(cache: &Cache, scope_name: &str) -> Result<Scope, anyhow::Error> {
     let mut stmt = cache.prepare("SELECT scope, role, last_synced FROM scopes WHERE scope = ?1")?;
     let mut rows = stmt.query(params![scope_name])?;
 
@@ -72,13 +71,12 @@ pub fn retrieve_scope(cache: &Connection, scope_name: &str) -> Result<Scope, any
     }
 }
 
-pub fn flush_scope(scope_name: &str) -> Result<(), anyhow::Error> {
-    let cache = connect_db("./ledger/cache/cache.db")?;
+pub fn flush_scope(cache: &Cache, scope_name: &str) -> Result<(), anyhow::Error> {
     cache.execute("DELETE FROM scopes WHERE scope = ?1", params![scope_name])?;
     Ok(())
 }
 
-pub fn flush_scope_full(cache: &Connection, scope_name: &str) -> Result<(), anyhow::Error> {
+pub fn flush_scope_full(cache: &Cache, scope_name: &str) -> Result<(), anyhow::Error> {
     cache.execute("DELETE FROM scopes WHERE scope = ?1", params![scope_name])?;
     cache.execute(
         "DELETE FROM authorities WHERE scope = ?1",
@@ -89,14 +87,14 @@ pub fn flush_scope_full(cache: &Connection, scope_name: &str) -> Result<(), anyh
     Ok(())
 }
 
-pub fn flush_scopes(cache: &Connection) -> Result<(), anyhow::Error> {
+pub fn flush_scopes(cache: &Cache) -> Result<(), anyhow::Error> {
     cache.execute("DELETE FROM scopes", params![])?;
     Ok(())
 }
 
-pub fn build_table(cache: &Connection) -> Result<(), anyhow::Error> {
+pub fn build_table(cache: &Cache) -> Result<(), anyhow::Error> {
     cache.execute(
-        "CREATE TABLE scopes (
+        "CREATE TABLE IF NOT EXISTS scopes (
                 scope TEXT,
                 role TEXT,
                 last_synced INTEGER,
@@ -0,0 +1,102 @@
+use crate::db::Cache;
+use hl_core::{
+    Rhex, from_base64,
+    spatial::{
+        frame::{Frame, context_point},
+        query::SpatialQuery,
+    },
+    to_base64,
+};
+use rusqlite::params;
+
+/// Index a finalized record by where it happened. Records without a
+/// spatial context are ignored.
+pub fn index_rhex(cache: &Cache, rhex: &Rhex) -> Result<(), anyhow::Error> {
+    let (Some(point), Some(refer), Some(hash)) = (
+        context_point(&rhex.context),
+        rhex.context.refer.as_ref(),
+        rhex.current_hash,
+    ) else {
+        return Ok(());
+    };
+    cache.execute(
+        "INSERT OR REPLACE INTO spatial (scope, current_hash, refer, x, y, z, at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            rhex.intent.scope,
+            to_base64(&hash),
+            refer,
+            point[0],
+            point[1],
+            point[2],
+            rhex.context.at
+        ],
+    )?;
+    Ok(())
+}
+
+/// `current_hash` of every record in `scope` matching `query`, oldest first.
+pub fn query(
+    cache: &Cache,
+    scope: &str,
+    frame: &Frame,
+    query: &SpatialQuery,
+) -> Result<Vec<[u8; 32]>, anyhow::Error> {
+    // The box narrows in SQL; the radius is checked exactly below.
+    let (min, max) = match &query.bbox {
+        Some(bbox) => (bbox.min, bbox.max),
+        None => (frame.min, frame.max),
+    };
+    let mut stmt = cache.prepare(
+        "SELECT current_hash, x, y, z FROM spatial
+            WHERE scope = ?1 AND refer = ?2
+              AND x BETWEEN ?3 AND ?4 AND y BETWEEN ?5 AND ?6 AND z BETWEEN ?7 AND ?8
+            ORDER BY at",
+    )?;
+    let mut rows = stmt.query(params![
+        scope,
+        query.frame,
+        min[0],
+        max[0],
+        min[1],
+        max[1],
+        min[2],
+        max[2]
+    ])?;
+
+    let mut hashes = vec![];
+    while let Some(row) = rows.next()? {
+        let point = [row.get("x")?, row.get("y")?, row.get("z")?];
+        if query.matches(frame, point) {
+            let hash: String = row.get("current_hash")?;
+            let hash = <[u8; 32]>::try_from(from_base64(&hash)?.as_slice())?;
+            hashes.push(hash);
+        }
+    }
+    Ok(hashes)
+}
+
+pub fn flush_spatial(cache: &Cache) -> Result<(), anyhow::Error> {
+    cache.execute("DELETE FROM spatial", params![])?;
+    Ok(())
+}
+
+pub fn build_table(cache: &Cache) -> Result<(), anyhow::Error> {
+    cache.execute(
+        "CREATE TABLE IF NOT EXISTS spatial (
+                scope TEXT,
+                current_hash TEXT,
+                refer TEXT,
+                x REAL,
+                y REAL,
+                z REAL,
+                at INTEGER,
+                PRIMARY KEY (scope, current_hash)
+            )",
+        params![],
+    )?;
+    cache.execute(
+        "CREATE INDEX IF NOT EXISTS spatial_xy ON spatial (scope, refer, x, y)",
+        params![],
+    )?;
+    Ok(())
+}
@@ -1,9 +1,9 @@
 use hl_core::Usher;
-use rusqlite::{Connection, params};
+use rusqlite::params;
 
-use crate::db::connect_db;
+use crate::db::Cache;
 
-pub fn get_ushers(cache: &Connection, scope: &str) -> Result<Vec<Usher>, anyhow::Error> {
+pub fn get_ushers(cache: &Cache, scope: &str) -> Result<Vec<Usher>, anyhow::Error> {
     let mut stmt = cache.prepare(
         "SELECT note, public_key, host, port, proto, priority FROM ushers WHERE scope = ?1",
     )?;
@@ -24,7 +24,7 @@ pub fn get_ushers(cache: &Connection, scope: &str) -> Result<Vec<Usher>, anyhow:
     Ok(ushers_out)
 }
 
-pub fn store_usher(cache: &Connection, scope: &str, usher: &Usher) -> Result<(), anyhow::Error> {
+pub fn store_usher(cache: &Cache, scope: &str, usher: &Usher) -> Result<(), anyhow::Error> {
     cache.execute(
         "INSERT OR REPLACE INTO ushers (scope, note, public_key, host, port, proto, priority) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
         params![
@@ -40,19 +40,17 @@ pub fn store_usher(cache: &Connection, scope: &str, usher: &Usher) -> Result<(),
     Ok(())
 }
 
-pub fn clear_by_scope(scope: &str) -> Result<(), anyhow::Error> {
-    let cache = connect_db("./ledger/cache/cache.db")?;
-
+pub fn clear_by_scope(cache: &Cache, scope: &str) -> Result<(), anyhow::Error> {
     cache.execute("DELETE FROM ushers WHERE scope = ?1", params![scope])?;
     Ok(())
 }
 
-pub fn flush_ushers(cache: &Connection) -> Result<(), anyhow::Error> {
+pub fn flush_ushers(cache: &Cache) -> Result<(), anyhow::Error> {
     cache.execute("DELETE FROM ushers", params![])?;
     Ok(())
 }
 
-pub fn build_table(cache: &Connection) -> Result<(), anyhow::Error> {
+pub fn build_table(cache: &Cache) -> Result<(), anyhow::Error> {
     cache.execute(
         "CREATE TABLE IF NOT EXISTS ushers (
                 scope TEXT,
@@ -1,121 +1,398 @@
-use aes_gcm::{Aes256Gcm, KeyInit, aead::Aead};
+use aes_gcm::{
+    Aes256Gcm, KeyInit,
+    aead::{Aead, Payload},
+};
 use anyhow::{Context, Result, bail};
 use argon2::{Algorithm, Argon2, Params, Version};
 use getrandom;
+use serde::{Deserialize, Serialize};
 //use rand::RngCore;
+use hl_core::key::secret::SecretKey;
 use std::{fs, path::Path};
+
+use crate::fs::write_atomic;
 use zeroize::Zeroize;
 
 const MAGIC: &[u8; 6] = b"HKYV1\0"; // Hodeaux Key, Version 1
+const MAGIC_V2: &[u8; 6] = b"HKYV2\0"; // Hodeaux Key, Version 2
 const SALT_LEN: usize = 16;
 const NONCE_LEN: usize = 12;
+const KDF_ARGON2ID: u8 = 1;
+// Upper bounds on what a key file may ask Argon2 for, so a crafted
+// header cannot stall or exhaust whoever opens it
+const MAX_M_COST: u32 = 1024 * 1024; // 1 GiB in KiB
+const MAX_T_COST: u32 = 16;
+const MAX_P_COST: u32 = 16;
 
-/// File layout:
+/// HKYV1 file layout:
 /// [0..6)  = MAGIC "HKYV1\0"
 /// [6..22) = SALT (16B)
 /// [22..34)= NONCE (12B)
 /// [34.. ) = CIPHERTEXT (AEAD: key 32B plaintext -> 32B + 16B tag)
+///
+/// HKYV2 file layout (integers big-endian):
+/// [0..6)   = MAGIC "HKYV2\0"
+/// [6]      = KDF id (1 = Argon2id v0x13)
+/// [7..11)  = m_cost (KiB)
+/// [11..15) = t_cost
+/// [15..19) = p_cost
+/// [19..35) = SALT (16B)
+/// [35..47) = NONCE (12B)
+/// [47..51) = META_LEN
+/// [51..51+META_LEN) = META (JSON [`KeyMeta`])
+/// [.. )    = CIPHERTEXT
+/// Everything before the ciphertext is AEAD associated data, so the KDF
+/// parameters and metadata can't be altered without failing decryption.
+
+/// Argon2id cost parameters, stored in the HKYV2 header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KdfParams {
+    /// Memory in KiB
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+impl Default for KdfParams {
+    /// What HKYV1 always used: 19 MiB, 2 iterations, 1 lane.
+    fn default() -> Self {
+        Self {
+            m_cost: 19 * 1024,
+            t_cost: 2,
+            p_cost: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    /// Refuse costs above what any key we write would use.
+    pub fn check(&self) -> Result<()> {
+        if self.m_cost > MAX_M_COST || self.t_cost > MAX_T_COST || self.p_cost > MAX_P_COST {
+            bail!(
+                "KDF cost {:?} above the limit (m_cost {}, t_cost {}, p_cost {})",
+                self,
+                MAX_M_COST,
+                MAX_T_COST,
+                MAX_P_COST
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Plaintext, authenticated facts about a stored key.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct KeyMeta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// GT (micromarks) the key was created
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    /// Scope the key is meant for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+}
+
+/// A decrypted key file.
+#[derive(Debug)]
+pub struct KeyFile {
+    pub sk: SecretKey,
+    /// 1 or 2
+    pub version: u8,
+    pub kdf: KdfParams,
+    pub meta: KeyMeta,
+}
 
 /// Derive a 32B AES key from password+salt using Argon2id (strong, portable).
-fn derive_key(password: &str, salt: &[u8]) -> Result<[u8; 32]> {
-    // Reasonable defaults; tune as you like (time/memory parallelism)
-    let params =
-        Params::new(19 * 1024, 2, 1, Some(32)).map_err(|_| anyhow::anyhow!("invalid params"))?; // 19 MiB, 2 iters, 1 lane → 32 bytes
+fn derive_key(password: &str, salt: &[u8], kdf: &KdfParams) -> Result<[u8; 32]> {
+    let params = Params::new(kdf.m_cost, kdf.t_cost, kdf.p_cost, Some(32))
+        .map_err(|e| anyhow::anyhow!("invalid KDF params: {}", e))?;
     let a2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
 
     // Argon2 crate works with PHC strings; we can hash into an output buffer:
     let mut out = [0u8; 32];
-    let _ = a2
-        .hash_password_into(password.as_bytes(), salt, &mut out)
-        .map_err(|_| anyhow::anyhow!("password hash failed"));
+    a2.hash_password_into(password.as_bytes(), salt, &mut out)
+        .map_err(|e| anyhow::anyhow!("password hash failed: {}", e))?;
     Ok(out)
 }
 
 /// Save an Ed25519 SigningKey encrypted with a password.
 pub fn save_key(path: &Path, password: &str, signing_key: &[u8; 32]) -> Result<()> {
+    save_key_with(
+        path,
+        password,
+        signing_key,
+        &KdfParams::default(),
+        &KeyMeta::default(),
+    )
+}
+
+/// Save an Ed25519 SigningKey as HKYV2 with the given KDF cost and metadata.
+pub fn save_key_with(
+    path: &Path,
+    password: &str,
+    signing_key: &[u8; 32],
+    kdf: &KdfParams,
+    meta: &KeyMeta,
+) -> Result<()> {
     if password.is_empty() {
         anyhow::bail!("empty password");
     }
+    kdf.check()?;
     let mut salt = [0u8; SALT_LEN];
     let mut nonce = [0u8; NONCE_LEN];
     getrandom::fill(&mut salt).expect("failed to fill salt buffer");
     getrandom::fill(&mut nonce).expect("failed to fill nonce buffer");
+    let meta = serde_json::to_vec(meta)?;
 
-    let mut aes_key = derive_key(password, &salt)?;
-    let cipher = Aes256Gcm::new_from_slice(&aes_key).context("bad AES key")?;
-
-    let key_bytes = signing_key; // [u8;32] already
-    let ciphertext = cipher
-        .encrypt(&nonce.into(), key_bytes.as_ref())
-        .map_err(|_| anyhow::anyhow!("encryption failed"))?;
-
-    // Assemble file
-    let mut out = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
-    out.extend_from_slice(MAGIC);
+    // Header, which the AEAD authenticates
+    let mut out = Vec::with_capacity(MAGIC_V2.len() + 45 + meta.len() + 48);
+    out.extend_from_slice(MAGIC_V2);
+    out.push(KDF_ARGON2ID);
+    out.extend_from_slice(&kdf.m_cost.to_be_bytes());
+    out.extend_from_slice(&kdf.t_cost.to_be_bytes());
+    out.extend_from_slice(&kdf.p_cost.to_be_bytes());
     out.extend_from_slice(&salt);
     out.extend_from_slice(&nonce);
-    out.extend_from_slice(&ciphertext);
+    out.extend_from_slice(&(meta.len() as u32).to_be_bytes());
+    out.extend_from_slice(&meta);
 
+    let mut aes_key = derive_key(password, &salt, kdf)?;
+    let cipher = Aes256Gcm::new_from_slice(&aes_key).context("bad AES key")?;
     // Best-effort zero secrets from memory
     aes_key.zeroize();
+    let ciphertext = cipher
+        .encrypt(
+            &nonce.into(),
+            Payload {
+                msg: signing_key,
+                aad: &out,
+            },
+        )
+        .map_err(|_| anyhow::anyhow!("encryption failed"))?;
+    out.extend_from_slice(&ciphertext);
 
-    // Atomic-ish write: write to temp then rename
-    let tmp = path.with_extension("tmp");
-    fs::write(&tmp, &out).with_context(|| format!("write temp key file {:?}", tmp))?;
-    fs::rename(&tmp, path).with_context(|| format!("rename {:?} -> {:?}", tmp, path))?;
-    Ok(())
+    write_atomic(path, &out)
 }
 
 /// Saves a key as just a raw [u8; 32], needed for the usher's hot
 /// key and other on the fly signing.
 pub fn save_key_hot(path: &Path, signing_key: &[u8; 32]) -> Result<()> {
-    let tmp = path.with_extension("tmp");
-    fs::write(&tmp, signing_key).with_context(|| format!("write temp key file {:?}", tmp))?;
-    fs::rename(&tmp, path)?;
-    Ok(())
+    write_atomic(path, signing_key)
 }
 
 /// Load an Ed25519 SigningKey by decrypting with password.
-pub fn load_key(path: &Path, password: &str) -> Result<[u8; 32], anyhow::Error> {
+pub fn load_key(path: &Path, password: &str) -> Result<SecretKey, anyhow::Error> {
+    Ok(open_key(path, password)?.sk)
+}
+
+/// Decrypt an HKYV1 or HKYV2 key file, along with what its header says.
+pub fn open_key(path: &Path, password: &str) -> Result<KeyFile> {
     let data = fs::read(path).with_context(|| format!("read key file {:?}", path.to_str()))?;
-    if data.len() < MAGIC.len() + SALT_LEN + NONCE_LEN {
+    if data.len() < MAGIC.len() {
         anyhow::bail!("key file too short");
     }
     let (magic, rest) = data.split_at(MAGIC.len());
-    if magic != MAGIC {
+    if magic == MAGIC {
+        open_v1(rest, password)
+    } else if magic == MAGIC_V2 {
+        open_v2(&data, password)
+    } else {
         anyhow::bail!("bad key magic/version");
     }
+}
+
+fn open_v1(rest: &[u8], password: &str) -> Result<KeyFile> {
+    if rest.len() < SALT_LEN + NONCE_LEN {
+        anyhow::bail!("key file too short");
+    }
     let (salt, rest) = rest.split_at(SALT_LEN);
     let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let kdf = KdfParams::default();
+    Ok(KeyFile {
+        sk: decrypt(password, salt, &kdf, nonce, ciphertext, &[])?,
+        version: 1,
+        kdf,
+        meta: KeyMeta::default(),
+    })
+}
+
+fn open_v2(data: &[u8], password: &str) -> Result<KeyFile> {
+    let fixed = MAGIC_V2.len() + 13 + SALT_LEN + NONCE_LEN + 4;
+    if data.len() < fixed {
+        anyhow::bail!("key file too short");
+    }
+    let u32_at = |i: usize| u32::from_be_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+    if data[6] != KDF_ARGON2ID {
+        bail!("unsupported KDF id {}", data[6]);
+    }
+    let kdf = KdfParams {
+        m_cost: u32_at(7),
+        t_cost: u32_at(11),
+        p_cost: u32_at(15),
+    };
+    // Before Argon2 runs: the header is not authenticated until after it
+    kdf.check()?;
+    let salt = &data[19..19 + SALT_LEN];
+    let nonce = &data[35..35 + NONCE_LEN];
+    let meta_len = u32_at(47) as usize;
+    if data.len() < fixed + meta_len {
+        anyhow::bail!("key metadata truncated");
+    }
+    let (header, ciphertext) = data.split_at(fixed + meta_len);
+    let sk = decrypt(password, salt, &kdf, nonce, ciphertext, header)?;
+    let meta = serde_json::from_slice(&header[fixed..]).context("bad key metadata")?;
+    Ok(KeyFile {
+        sk,
+        version: 2,
+        kdf,
+        meta,
+    })
+}
+
+fn decrypt(
+    password: &str,
+    salt: &[u8],
+    kdf: &KdfParams,
+    nonce: &[u8],
+    ciphertext: &[u8],
+    aad: &[u8],
+) -> Result<SecretKey> {
     if ciphertext.len() < 16 {
         // must at least contain GCM tag
         anyhow::bail!("ciphertext truncated");
     }
 
-    let mut aes_key = derive_key(password, salt)?;
+    let mut aes_key = derive_key(password, salt, kdf)?;
     let cipher = Aes256Gcm::new_from_slice(&aes_key).context("bad AES key")?;
+    aes_key.zeroize();
 
-    let plaintext = cipher
-        .decrypt(nonce.into(), ciphertext)
+    let mut plaintext = cipher
+        .decrypt(
+            nonce.into(),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
         .map_err(|_| anyhow::anyhow!("decryption failed"))?;
-    aes_key.zeroize();
 
     if plaintext.len() != 32 {
+        plaintext.zeroize();
         anyhow::bail!("decrypted key wrong length");
     }
     let mut sk_bytes = [0u8; 32];
     sk_bytes.copy_from_slice(&plaintext);
-    Ok(sk_bytes.into())
+    plaintext.zeroize();
+    Ok(SecretKey::from_bytes(&mut sk_bytes))
+}
+
+/// Rewrite an HKYV1 file as HKYV2 in place, with the same password and
+/// KDF cost. Returns false if it was already HKYV2.
+pub fn migrate_key(path: &Path, password: &str, meta: &KeyMeta) -> Result<bool> {
+    let key = open_key(path, password)?;
+    if key.version == 2 {
+        return Ok(false);
+    }
+    save_key_with(path, password, key.sk.expose_secret(), &key.kdf, meta)?;
+    Ok(true)
+}
+
+/// Re-encrypt a key file under `new_password`, keeping its KDF cost and
+/// metadata. HKYV1 files come out as HKYV2.
+pub fn rekey_key(path: &Path, old_password: &str, new_password: &str) -> Result<()> {
+    let key = open_key(path, old_password)?;
+    save_key_with(
+        path,
+        new_password,
+        key.sk.expose_secret(),
+        &key.kdf,
+        &key.meta,
+    )
 }
 
 /// Loads a hot key for things like usher signing.
 /// Hot files are just [u8; 32] streams.
-pub fn load_key_hot(path: &Path) -> Result<[u8; 32]> {
-    let keydata = fs::read(path)?;
+pub fn load_key_hot(path: &Path) -> Result<SecretKey> {
+    let mut keydata = fs::read(path)?;
     if keydata.len() != 32 {
-        bail!("invalid key length: expected 32, got {}", keydata.len());
+        let len = keydata.len();
+        keydata.zeroize();
+        bail!("invalid key length: expected 32, got {}", len);
     }
     let mut key = [0u8; 32];
     key.copy_from_slice(&keydata);
-    Ok(key)
+    keydata.zeroize();
+    Ok(SecretKey::from_bytes(&mut key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tmp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("hl-io-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn v2_roundtrip_and_migrate() {
+        let kdf = KdfParams {
+            m_cost: 64,
+            t_cost: 1,
+            p_cost: 1,
+        };
+        let meta = KeyMeta {
+            label: Some("usher".to_string()),
+            created_at: Some(42),
+            scope: Some("acme".to_string()),
+            roles: vec!["authority".to_string()],
+        };
+        let path = tmp_path("v2.key");
+        save_key_with(&path, "pw", &[7u8; 32], &kdf, &meta).unwrap();
+        let key = open_key(&path, "pw").unwrap();
+        assert_eq!(key.sk.expose_secret(), &[7u8; 32]);
+        assert_eq!((key.version, key.kdf), (2, kdf));
+        assert_eq!(key.meta, meta);
+        assert!(open_key(&path, "wrong").is_err());
+
+        // Metadata is authenticated
+        let mut data = fs::read(&path).unwrap();
+        let at = data.windows(5).position(|w| w == b"usher").unwrap();
+        data[at] = b'U';
+        fs::write(&path, &data).unwrap();
+        assert!(open_key(&path, "pw").is_err());
+
+        // HKYV1, as the old save_key wrote it
+        let salt = [1u8; SALT_LEN];
+        let nonce = [2u8; NONCE_LEN];
+        let aes_key = derive_key("pw", &salt, &KdfParams::default()).unwrap();
+        let cipher = Aes256Gcm::new_from_slice(&aes_key).unwrap();
+        let mut v1 = MAGIC.to_vec();
+        v1.extend_from_slice(&salt);
+        v1.extend_from_slice(&nonce);
+        v1.extend(cipher.encrypt(&nonce.into(), [9u8; 32].as_ref()).unwrap());
+        fs::write(&path, &v1).unwrap();
+        assert_eq!(load_key(&path, "pw").unwrap().expose_secret(), &[9u8; 32]);
+        assert!(migrate_key(&path, "pw", &meta).unwrap());
+        assert!(!migrate_key(&path, "pw", &meta).unwrap());
+        let key = open_key(&path, "pw").unwrap();
+        assert_eq!((key.sk.expose_secret(), key.version), (&[9u8; 32], 2));
+        assert_eq!(key.meta, meta);
+
+        // An absurd cost in the header is refused without running Argon2
+        let mut data = fs::read(&path).unwrap();
+        data[7..11].copy_from_slice(&u32::MAX.to_be_bytes());
+        fs::write(&path, &data).unwrap();
+        let err = open_key(&path, "pw").unwrap_err().to_string();
+        assert!(err.contains("above the limit"), "{}", err);
+        save_key_with(&path, "pw", &[9u8; 32], &KdfParams::default(), &meta).unwrap();
+
+        rekey_key(&path, "pw", "new pw").unwrap();
+        assert!(open_key(&path, "pw").is_err());
+        let key = open_key(&path, "new pw").unwrap();
+        assert_eq!((key.sk.expose_secret(), key.meta), (&[9u8; 32], meta));
+        fs::remove_file(&path).unwrap();
+    }
 }
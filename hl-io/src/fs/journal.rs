@@ -0,0 +1,252 @@
+use std::{
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::Result;
+use hl_core::{
+    Rhex,
+    b32::b32::to_base32_crockford,
+    cbor::canonical::{from_canonical_cbor, to_canonical_cbor},
+    error,
+};
+
+use crate::{
+    db::{Cache, head::get_head},
+    fs::{read_dir, remove_durable, remove_temps, write_atomic},
+    store::RhexStore,
+};
+
+/// The appends one processing call makes to a store, through the journal.
+/// Each record lands in the entry before the store, so an usher that dies
+/// before `finish` can tell on restart whether they landed. Dropped
+/// unfinished, it takes them back out of the store, last first.
+pub struct Journal<'a> {
+    entry: PathBuf,
+    records: Mutex<Vec<Rhex>>,
+    store: &'a dyn RhexStore,
+    done: bool,
+}
+
+/// What `recover` did with an entry a crashed usher left behind, by the
+/// `current_hash` of the record whose processing made it.
+#[derive(Debug, PartialEq)]
+pub enum Recovered {
+    /// The cache committed the heads; the records are in the store.
+    Completed([u8; 32]),
+    /// The cache never committed; the records are out of the store.
+    RolledBack([u8; 32]),
+}
+
+impl<'a> Journal<'a> {
+    /// Open an entry in `dir` for processing the finalized `rhex`.
+    /// Append to `store` through the journal, then `finish` once the
+    /// cache has committed.
+    pub fn begin(dir: &Path, store: &'a dyn RhexStore, rhex: &Rhex) -> Result<Self> {
+        let Some(current_hash) = rhex.current_hash else {
+            anyhow::bail!(
+                "{}: only finalized records are journaled",
+                error::E_HASH_MISSING
+            );
+        };
+        fs::create_dir_all(dir)
+            .map_err(|e| anyhow::anyhow!("{}: {}: {}", error::E_FS_WRITE, dir.display(), e))?;
+        Ok(Self {
+            entry: dir.join(entry_name(&current_hash)),
+            records: Mutex::new(Vec::new()),
+            store,
+            done: false,
+        })
+    }
+
+    /// What was appended so far, in order.
+    pub fn records(&self) -> Vec<Rhex> {
+        self.lock().clone()
+    }
+
+    /// The appends are complete.
+    pub fn finish(mut self) -> Result<()> {
+        self.done = true;
+        remove_durable(&self.entry)
+    }
+
+    /// Take back the records, then the entry.
+    pub fn abort(mut self) -> Result<()> {
+        self.done = true;
+        self.undo()
+    }
+
+    fn undo(&self) -> Result<()> {
+        for rhex in self.lock().iter().rev() {
+            self.store.unappend(rhex)?;
+        }
+        remove_durable(&self.entry)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Rhex>> {
+        self.records.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for Journal<'_> {
+    fn drop(&mut self) {
+        if !self.done
+            && let Err(e) = self.undo()
+        {
+            eprintln!("Error undoing append {}: {}", self.entry.display(), e);
+        }
+    }
+}
+
+/// Journals each append, then hands everything to the store.
+impl RhexStore for Journal<'_> {
+    fn append(&self, rhex: &Rhex) -> Result<()> {
+        let mut records = self.lock();
+        records.push(rhex.clone());
+        let appended = write_atomic(&self.entry, &to_canonical_cbor(&*records)?)
+            .and_then(|_| self.store.append(rhex));
+        if appended.is_err() {
+            records.pop();
+        }
+        appended
+    }
+
+    fn unappend(&self, rhex: &Rhex) -> Result<()> {
+        self.store.unappend(rhex)
+    }
+
+    fn get(&self, scope: &str, current_hash: &[u8; 32]) -> Result<Option<Rhex>> {
+        self.store.get(scope, current_hash)
+    }
+
+    fn get_by_previous(
+        &self,
+        scope: &str,
+        previous_hash: Option<&[u8; 32]>,
+    ) -> Result<Option<Rhex>> {
+        self.store.get_by_previous(scope, previous_hash)
+    }
+
+    fn head(&self, scope: &str) -> Result<Option<[u8; 32]>> {
+        self.store.head(scope)
+    }
+
+    fn range(&self, scope: &str, range: Range<usize>) -> Result<Vec<Rhex>> {
+        self.store.range(scope, range)
+    }
+
+    fn scopes(&self) -> Result<Vec<String>> {
+        self.store.scopes()
+    }
+}
+
+fn entry_name(current_hash: &[u8; 32]) -> String {
+    format!(
+        "{}.rhex",
+        to_base32_crockford(current_hash).to_ascii_lowercase()
+    )
+}
+
+/// Settle the appends a crashed usher left half done, from the entries
+/// in `dir`. One whose heads the cache committed is completed in
+/// `store`, any other taken out of it. Temp files from entry writes that
+/// never got renamed are removed.
+pub fn recover(dir: &Path, cache: &Cache, store: &dyn RhexStore) -> Result<Vec<Recovered>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    remove_temps(dir)?;
+
+    let mut recovered = Vec::new();
+    for entry in read_dir(dir)? {
+        let bytes = fs::read(&entry)
+            .map_err(|e| anyhow::anyhow!("{}: {}: {}", error::E_FS_READ, entry.display(), e))?;
+        let records: Vec<Rhex> = from_canonical_cbor(&bytes)?;
+        let Some(hashes) = records
+            .iter()
+            .map(|rhex| rhex.current_hash)
+            .collect::<Option<Vec<_>>>()
+            .filter(|hashes| !hashes.is_empty())
+        else {
+            anyhow::bail!("{}: {}", error::E_HASH_MISSING, entry.display());
+        };
+        // One transaction moved every head, so the last record tells
+        let last = &records[records.len() - 1];
+        if get_head(cache, &last.intent.scope).ok() == hashes.last().copied() {
+            for (rhex, current_hash) in records.iter().zip(hashes.iter()) {
+                if store.get(&rhex.intent.scope, current_hash)?.is_none() {
+                    store.append(rhex)?;
+                }
+            }
+            recovered.push(Recovered::Completed(hashes[0]));
+        } else {
+            for rhex in records.iter().rev() {
+                store.unappend(rhex)?;
+            }
+            recovered.push(Recovered::RolledBack(hashes[0]));
+        }
+        remove_durable(&entry)?;
+    }
+    Ok(recovered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::head::{build_table, set_head},
+        store::MemoryStore,
+    };
+
+    fn record(n: u8) -> Rhex {
+        let mut rhex = Rhex::new();
+        rhex.intent.previous_hash = (n > 1).then_some([n - 1; 32]);
+        rhex.current_hash = Some([n; 32]);
+        rhex
+    }
+
+    #[test]
+    fn recover_completes_committed_appends_and_rolls_back_the_rest() {
+        let root = std::env::temp_dir().join(format!("hl-io-{}-journal", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let cache = Cache::open_in_memory().unwrap();
+        build_table(&cache).unwrap();
+        let store = MemoryStore::new();
+
+        // Dropped unfinished: undone on the spot
+        let journal = Journal::begin(&root, &store, &record(1)).unwrap();
+        journal.append(&record(1)).unwrap();
+        drop(journal);
+        assert_eq!(store.head("").unwrap(), None);
+
+        // Crashed after the cache committed, and before
+        let committed = Journal::begin(&root, &store, &record(1)).unwrap();
+        committed.append(&record(1)).unwrap();
+        set_head(&cache, "", &[1; 32]).unwrap();
+        let uncommitted = Journal::begin(&root, &store, &record(2)).unwrap();
+        uncommitted.append(&record(2)).unwrap();
+        let mut child = record(9);
+        child.intent.scope = "acme".to_string();
+        child.intent.previous_hash = None;
+        uncommitted.append(&child).unwrap();
+        std::mem::forget((committed, uncommitted));
+        fs::write(root.join("stray.tmp"), b"").unwrap();
+
+        let mut recovered = recover(&root, &cache, &store).unwrap();
+        recovered.sort_by_key(|r| matches!(r, Recovered::Completed(_)));
+        assert_eq!(
+            recovered,
+            vec![
+                Recovered::RolledBack([2; 32]),
+                Recovered::Completed([1; 32])
+            ]
+        );
+        assert_eq!(store.head("").unwrap(), Some([1; 32]));
+        assert_eq!(store.head("acme").unwrap(), None);
+        assert!(!root.join("stray.tmp").exists());
+        assert!(recover(&root, &cache, &store).unwrap().is_empty());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
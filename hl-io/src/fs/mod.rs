@@ -1,4 +1,13 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use hl_core::error;
+
 pub mod authority;
+pub mod journal;
 pub mod rhex;
 
 pub enum StoreStream {
@@ -14,3 +23,65 @@ pub enum StoreSink {
     FileSystem = 2,
     QrCode = 3,
 }
+
+/// Write to a synced temp file, then rename it over `path`, so a crash
+/// leaves either the old file or the new one.
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), anyhow::Error> {
+    let tmp = path.with_extension("tmp");
+    let file = fs::File::create(&tmp)
+        .and_then(|mut file| file.write_all(bytes).map(|_| file))
+        .map_err(|e| anyhow::anyhow!("{}: {}: {}", error::E_FS_WRITE, tmp.display(), e))?;
+    file.sync_all()
+        .map_err(|e| anyhow::anyhow!("{}: {}: {}", error::E_FS_FSYNC, tmp.display(), e))?;
+    fs::rename(&tmp, path).map_err(|e| {
+        anyhow::anyhow!(
+            "{}: {} -> {}: {}",
+            error::E_FS_RENAME,
+            tmp.display(),
+            path.display(),
+            e
+        )
+    })?;
+    sync_parent(path)
+}
+
+/// Remove `path` if it is there, durably.
+pub(crate) fn remove_durable(path: &Path) -> Result<(), anyhow::Error> {
+    match fs::remove_file(path) {
+        Ok(()) => sync_parent(path),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(anyhow::anyhow!(
+            "{}: remove {}: {}",
+            error::E_FS_WRITE,
+            path.display(),
+            e
+        )),
+    }
+}
+
+/// Remove temp files left by `write_atomic` calls that never renamed.
+pub fn remove_temps(dir: &Path) -> Result<(), anyhow::Error> {
+    for path in read_dir(dir)? {
+        if path.extension().is_some_and(|ext| ext == "tmp") {
+            remove_durable(&path)?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn read_dir(dir: &Path) -> Result<Vec<PathBuf>, anyhow::Error> {
+    let entries = fs::read_dir(dir)
+        .and_then(|entries| entries.map(|e| e.map(|e| e.path())).collect())
+        .map_err(|e| anyhow::anyhow!("{}: {}: {}", error::E_FS_READ, dir.display(), e))?;
+    Ok(entries)
+}
+
+/// Make a rename or removal in `path`'s directory itself durable.
+fn sync_parent(path: &Path) -> Result<(), anyhow::Error> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::File::open(dir)
+            .and_then(|dir| dir.sync_all())
+            .map_err(|e| anyhow::anyhow!("{}: {}: {}", error::E_FS_FSYNC, dir.display(), e))?;
+    }
+    Ok(())
+}
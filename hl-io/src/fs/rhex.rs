@@ -1,4 +1,4 @@
-use crate::{sink::RhexSink, source::RhexSource};
+use crate::{fs::write_atomic, sink::RhexSink, source::RhexSource};
 use anyhow::{Context, Result, bail};
 use hl_core::Rhex;
 use hl_core::b32::b32::to_base32_crockford;
@@ -141,23 +141,23 @@ impl DirSink {
 }
 
 impl RhexSink for DirSink {
+    /// Lands the file whole or not at all; see `fs::write_atomic`.
     fn send(&mut self, r: &Rhex) -> Result<()> {
         let bytes = r.into_cbor()?;
-        let name = if r.intent.previous_hash == None {
-            "genesis.rhex".to_string()
-        } else {
-            format!(
-                "{}.rhex",
-                to_base32_crockford(r.intent.previous_hash.as_ref().unwrap()).to_ascii_lowercase()
-            )
-        };
-        /*println!(
-            "Writing {} bytes to {}/{}",
-            bytes.len(),
-            self.root.display(),
-            name
-        );*/
-        fs::write(self.root.join(name), bytes)?;
-        Ok(())
+        write_atomic(&self.root.join(file_name(r)), &bytes)
+    }
+}
+
+/// The name a record is stored under in its scope dir: `genesis.rhex`,
+/// or its previous hash in lowercase Crockford base32.
+pub fn file_name(r: &Rhex) -> String {
+    file_name_after(r.intent.previous_hash.as_ref())
+}
+
+/// The name of the file holding the record chained onto `previous_hash`.
+pub fn file_name_after(previous_hash: Option<&[u8; 32]>) -> String {
+    match previous_hash {
+        None => "genesis.rhex".to_string(),
+        Some(prev) => format!("{}.rhex", to_base32_crockford(prev).to_ascii_lowercase()),
     }
 }
@@ -1,6 +1,9 @@
+pub mod agent;
 pub mod db;
 pub mod fs;
 pub mod net;
 pub mod screen;
+pub mod signer;
 pub mod sink;
 pub mod source;
+pub mod store;
@@ -1,4 +1,4 @@
-use hl_core::{Rhex, to_base64};
+use hl_core::{Rhex, key::fingerprint::fingerprint, to_base64};
 
 pub fn pretty_print(rhex: &Rhex) -> Result<(), anyhow::Error> {
     println!("{{");
@@ -18,13 +18,34 @@ pub fn pretty_print(rhex: &Rhex) -> Result<(), anyhow::Error> {
         "        \"author_pk\": \"{}\",",
         to_base64(&rhex.intent.author_pk)
     );
+    println!(
+        "        \"author_fp\": \"{}\",",
+        fingerprint(&rhex.intent.author_pk)
+    );
     println!(
         "        \"usher_pk\": \"{}\",",
         to_base64(&rhex.intent.usher_pk)
     );
+    println!(
+        "        \"usher_fp\": \"{}\",",
+        fingerprint(&rhex.intent.usher_pk)
+    );
     println!("        \"record_type\": \"{}\",", rhex.intent.record_type);
     println!("        \"data\":");
     println!("            {},", serde_json::to_string(&rhex.intent.data)?);
+    if let Some(certs) = &rhex.intent.delegation {
+        println!("        \"delegation\": [");
+        for cert in certs.iter() {
+            println!(
+                "            {{ \"issuer_fp\": \"{}\", \"subject_fp\": \"{}\", \"record_types\": {:?}, \"exp\": {} }},",
+                fingerprint(&cert.issuer_pk),
+                fingerprint(&cert.subject_pk),
+                cert.record_types,
+                cert.exp
+            );
+        }
+        println!("        ],");
+    }
     println!("        }},");
     println!("    }},");
     println!("    \"context\": {{");
@@ -52,6 +73,7 @@ pub fn pretty_print(rhex: &Rhex) -> Result<(), anyhow::Error> {
             "           \"public_key\": \"{}\",",
             to_base64(&sig.public_key)
         );
+        println!("           \"fp\": \"{}\",", fingerprint(&sig.public_key));
         println!("           \"sig\": \"{}\"", to_base64(&sig.sig));
         println!("        }},");
     }
@@ -0,0 +1,33 @@
+use std::{path::Path, sync::Arc};
+
+use anyhow::Context;
+use hl_core::{Key, keymaster::signer::SignerSpec};
+
+use crate::{
+    agent::client::{AgentSigner, list_keys},
+    fs::authority::{load_key, load_key_hot},
+};
+
+/// Open a signer backend, giving one key per public key it can sign for.
+pub fn open(spec: &SignerSpec) -> anyhow::Result<Vec<Key>> {
+    match spec {
+        SignerSpec::Hot { path } => Ok(vec![Key::from_secret(load_key_hot(Path::new(path))?)]),
+        SignerSpec::Hkyv { path, password_env } => {
+            let password = std::env::var(password_env)
+                .with_context(|| format!("{} not set to unlock {}", password_env, path))?;
+            Ok(vec![Key::from_secret(load_key(
+                Path::new(path),
+                &password,
+            )?)])
+        }
+        SignerSpec::Agent { socket } => Ok(list_keys(Path::new(socket))?
+            .into_iter()
+            .map(|pk| {
+                Key::from_signer(Arc::new(AgentSigner {
+                    socket: socket.into(),
+                    pk,
+                }))
+            })
+            .collect()),
+    }
+}
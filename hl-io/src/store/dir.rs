@@ -0,0 +1,147 @@
+use std::{fs, ops::Range, path::PathBuf};
+
+use anyhow::Result;
+use hl_core::{Rhex, error};
+
+use crate::{
+    fs::{
+        remove_durable,
+        rhex::{DirSource, file_name, file_name_after},
+        write_atomic,
+    },
+    source::RhexSource,
+    store::{RhexStore, check_append},
+};
+
+/// One file per record, named by its previous hash (see `DirSource`).
+/// Lookups other than by previous hash walk the chain.
+pub struct DirStore {
+    root: PathBuf,
+}
+
+impl DirStore {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// `""` lives in the root, every other scope in a subdir named for it.
+    pub fn dir(&self, scope: &str) -> PathBuf {
+        match scope {
+            "" => self.root.clone(),
+            scope => self.root.join(scope),
+        }
+    }
+
+    /// Each record of `scope` in order until `f` says stop.
+    fn walk(&self, scope: &str, mut f: impl FnMut(Rhex) -> bool) -> Result<()> {
+        let dir = self.dir(scope);
+        if !dir.join(file_name_after(None)).is_file() {
+            return Ok(());
+        }
+        let mut source = DirSource::new(dir)?;
+        while let Some(rhex) = source.next()? {
+            if !f(rhex) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RhexStore for DirStore {
+    fn append(&self, rhex: &Rhex) -> Result<()> {
+        let dir = self.dir(&rhex.intent.scope);
+        let path = dir.join(file_name(rhex));
+        check_append(rhex, path.exists())?;
+        fs::create_dir_all(&dir)
+            .map_err(|e| anyhow::anyhow!("{}: {}: {}", error::E_FS_WRITE, dir.display(), e))?;
+        write_atomic(&path, &rhex.into_cbor()?)
+    }
+
+    fn unappend(&self, rhex: &Rhex) -> Result<()> {
+        let dir = self.dir(&rhex.intent.scope);
+        // Only while nothing has been chained onto it
+        if dir
+            .join(file_name_after(rhex.current_hash.as_ref()))
+            .exists()
+        {
+            return Ok(());
+        }
+        let stored = self.get_by_previous(&rhex.intent.scope, rhex.intent.previous_hash.as_ref());
+        if stored
+            .ok()
+            .flatten()
+            .is_some_and(|r| r.current_hash == rhex.current_hash)
+        {
+            remove_durable(&dir.join(file_name(rhex)))?;
+        }
+        Ok(())
+    }
+
+    fn get(&self, scope: &str, current_hash: &[u8; 32]) -> Result<Option<Rhex>> {
+        let mut found = None;
+        self.walk(scope, |rhex| {
+            if rhex.current_hash.as_ref() == Some(current_hash) {
+                found = Some(rhex);
+            }
+            found.is_none()
+        })?;
+        Ok(found)
+    }
+
+    fn get_by_previous(
+        &self,
+        scope: &str,
+        previous_hash: Option<&[u8; 32]>,
+    ) -> Result<Option<Rhex>> {
+        let path = self.dir(scope).join(file_name_after(previous_hash));
+        if !path.exists() {
+            return Ok(None);
+        }
+        let bytes = fs::read(&path)
+            .map_err(|e| anyhow::anyhow!("{}: {}: {}", error::E_FS_READ, path.display(), e))?;
+        Ok(Some(Rhex::from_cbor(&bytes)?))
+    }
+
+    fn head(&self, scope: &str) -> Result<Option<[u8; 32]>> {
+        let mut head = None;
+        self.walk(scope, |rhex| {
+            head = rhex.current_hash;
+            true
+        })?;
+        Ok(head)
+    }
+
+    fn range(&self, scope: &str, range: Range<usize>) -> Result<Vec<Rhex>> {
+        let mut records = Vec::new();
+        let mut position = 0;
+        self.walk(scope, |rhex| {
+            if range.contains(&position) {
+                records.push(rhex);
+            }
+            position += 1;
+            position < range.end
+        })?;
+        Ok(records)
+    }
+
+    fn scopes(&self) -> Result<Vec<String>> {
+        let genesis = file_name_after(None);
+        let mut scopes = Vec::new();
+        if self.root.join(&genesis).is_file() {
+            scopes.push(String::new());
+        }
+        let entries = fs::read_dir(&self.root)
+            .map_err(|e| anyhow::anyhow!("{}: {}: {}", error::E_FS_READ, self.root.display(), e))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.join(&genesis).is_file()
+                && let Some(name) = path.file_name()
+            {
+                scopes.push(name.to_string_lossy().into_owned());
+            }
+        }
+        scopes.sort();
+        Ok(scopes)
+    }
+}
@@ -0,0 +1,98 @@
+use std::{
+    collections::BTreeMap,
+    ops::Range,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use hl_core::Rhex;
+
+use crate::store::{RhexStore, check_append};
+
+/// Chains held in memory. Clones share the same records.
+#[derive(Clone, Default)]
+pub struct MemoryStore {
+    chains: Arc<Mutex<BTreeMap<String, Vec<Rhex>>>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_chain<T>(&self, scope: &str, f: impl FnOnce(&[Rhex]) -> T) -> T {
+        let chains = self.chains.lock().unwrap_or_else(|e| e.into_inner());
+        f(chains.get(scope).map(Vec::as_slice).unwrap_or_default())
+    }
+}
+
+impl RhexStore for MemoryStore {
+    fn append(&self, rhex: &Rhex) -> Result<()> {
+        let mut chains = self.chains.lock().unwrap_or_else(|e| e.into_inner());
+        let chain = chains.entry(rhex.intent.scope.clone()).or_default();
+        let taken = chain
+            .iter()
+            .any(|r| r.intent.previous_hash == rhex.intent.previous_hash);
+        check_append(rhex, taken)?;
+        chain.push(rhex.clone());
+        Ok(())
+    }
+
+    fn unappend(&self, rhex: &Rhex) -> Result<()> {
+        let mut chains = self.chains.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(chain) = chains.get_mut(&rhex.intent.scope)
+            && chain
+                .last()
+                .is_some_and(|r| r.current_hash == rhex.current_hash)
+        {
+            chain.pop();
+        }
+        Ok(())
+    }
+
+    fn get(&self, scope: &str, current_hash: &[u8; 32]) -> Result<Option<Rhex>> {
+        Ok(self.with_chain(scope, |chain| {
+            chain
+                .iter()
+                .find(|r| r.current_hash.as_ref() == Some(current_hash))
+                .cloned()
+        }))
+    }
+
+    fn get_by_previous(
+        &self,
+        scope: &str,
+        previous_hash: Option<&[u8; 32]>,
+    ) -> Result<Option<Rhex>> {
+        Ok(self.with_chain(scope, |chain| {
+            chain
+                .iter()
+                .find(|r| r.intent.previous_hash.as_ref() == previous_hash)
+                .cloned()
+        }))
+    }
+
+    fn head(&self, scope: &str) -> Result<Option<[u8; 32]>> {
+        Ok(self.with_chain(scope, |chain| chain.last().and_then(|r| r.current_hash)))
+    }
+
+    fn range(&self, scope: &str, range: Range<usize>) -> Result<Vec<Rhex>> {
+        Ok(self.with_chain(scope, |chain| {
+            chain
+                .iter()
+                .skip(range.start)
+                .take(range.len())
+                .cloned()
+                .collect()
+        }))
+    }
+
+    fn scopes(&self) -> Result<Vec<String>> {
+        let chains = self.chains.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(chains
+            .iter()
+            .filter(|(_, chain)| !chain.is_empty())
+            .map(|(scope, _)| scope.clone())
+            .collect())
+    }
+}
@@ -0,0 +1,178 @@
+//! Where finalized records live, behind one trait so each deployment can
+//! pick its storage and tests can keep theirs in memory.
+
+use std::{ops::Range, path::PathBuf, sync::OnceLock};
+
+use anyhow::{Result, bail};
+use hl_core::{Config, Rhex, error};
+
+use crate::source::RhexSource;
+
+mod dir;
+mod memory;
+mod sqlite;
+
+pub use dir::DirStore;
+pub use memory::MemoryStore;
+pub use sqlite::SqliteStore;
+
+/// Append-only storage for finalized records, one chain per scope.
+/// Linkage against the head is the processor's to check; a store only
+/// refuses a second record onto the same previous hash.
+pub trait RhexStore: Send + Sync {
+    /// Add a finalized record to the end of its scope's chain.
+    fn append(&self, rhex: &Rhex) -> Result<()>;
+
+    /// Take back `rhex` after an append the cache never committed.
+    fn unappend(&self, rhex: &Rhex) -> Result<()>;
+
+    fn get(&self, scope: &str, current_hash: &[u8; 32]) -> Result<Option<Rhex>>;
+
+    /// The record chained onto `previous_hash`; the genesis for `None`.
+    fn get_by_previous(
+        &self,
+        scope: &str,
+        previous_hash: Option<&[u8; 32]>,
+    ) -> Result<Option<Rhex>>;
+
+    /// `current_hash` of the scope's last record.
+    fn head(&self, scope: &str) -> Result<Option<[u8; 32]>>;
+
+    /// Records at chain positions `range`, genesis being 0, in order.
+    fn range(&self, scope: &str, range: Range<usize>) -> Result<Vec<Rhex>>;
+
+    /// Every scope with a chain here.
+    fn scopes(&self) -> Result<Vec<String>>;
+
+    /// The scope's whole chain.
+    fn chain(&self, scope: &str) -> Result<Vec<Rhex>> {
+        self.range(scope, 0..usize::MAX)
+    }
+}
+
+/// Reads one scope's chain out of a store, genesis first.
+pub struct StoreSource<'a> {
+    store: &'a dyn RhexStore,
+    scope: String,
+    // records already yielded
+    offset: usize,
+}
+
+impl<'a> StoreSource<'a> {
+    pub fn new(store: &'a dyn RhexStore, scope: String) -> Self {
+        Self {
+            store,
+            scope,
+            offset: 0,
+        }
+    }
+}
+
+impl RhexSource for StoreSource<'_> {
+    fn next(&mut self) -> Result<Option<Rhex>> {
+        let next = self
+            .store
+            .range(&self.scope, self.offset..self.offset + 1)?
+            .pop();
+        if next.is_some() {
+            self.offset += 1;
+        }
+        Ok(next)
+    }
+}
+
+/// The store `config.store` names. A `memory` store is shared by every
+/// open in the process and gone when it exits.
+pub fn open(config: &Config) -> Result<Box<dyn RhexStore>> {
+    static MEMORY: OnceLock<MemoryStore> = OnceLock::new();
+    match config.store.as_str() {
+        "dir" => Ok(Box::new(DirStore::new(PathBuf::from(&config.fs_dir)))),
+        "sqlite" => Ok(Box::new(SqliteStore::open(&config.store_db)?)),
+        "memory" => Ok(Box::new(MEMORY.get_or_init(MemoryStore::new).clone())),
+        other => bail!("Unknown store {:?}; use dir, sqlite or memory", other),
+    }
+}
+
+/// What every store checks before taking a record.
+fn check_append(rhex: &Rhex, taken: bool) -> Result<[u8; 32]> {
+    let Some(current_hash) = rhex.current_hash else {
+        bail!(
+            "{}: only finalized records are stored",
+            error::E_HASH_MISSING
+        );
+    };
+    if taken {
+        bail!(
+            "{}: scope {:?} already has a record after this one's previous hash",
+            error::E_FORK_DETECTED,
+            rhex.intent.scope
+        );
+    }
+    Ok(current_hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(scope: &str, previous_hash: Option<[u8; 32]>, n: u8) -> Rhex {
+        let mut rhex = Rhex::new();
+        rhex.intent.scope = scope.to_string();
+        rhex.intent.previous_hash = previous_hash;
+        rhex.current_hash = Some([n; 32]);
+        rhex
+    }
+
+    fn exercise(store: &dyn RhexStore) {
+        let genesis = record("", None, 1);
+        let second = record("", Some([1; 32]), 2);
+        let third = record("", Some([2; 32]), 3);
+        let child = record("acme", None, 9);
+        for rhex in [&genesis, &second, &third, &child] {
+            store.append(rhex).unwrap();
+        }
+
+        let err = store.append(&record("", Some([1; 32]), 7)).unwrap_err();
+        assert!(err.to_string().starts_with(error::E_FORK_DETECTED));
+        let mut unfinalized = record("", Some([3; 32]), 4);
+        unfinalized.current_hash = None;
+        assert!(store.append(&unfinalized).is_err());
+
+        assert_eq!(store.head("").unwrap(), Some([3; 32]));
+        assert_eq!(store.head("acme").unwrap(), Some([9; 32]));
+        assert_eq!(store.head("nope").unwrap(), None);
+        let found = store.get("", &[2; 32]).unwrap().unwrap();
+        assert_eq!(found.intent.previous_hash, Some([1; 32]));
+        let found = store.get_by_previous("", Some(&[2; 32])).unwrap().unwrap();
+        assert_eq!(found.current_hash, Some([3; 32]));
+        let found = store.get_by_previous("acme", None).unwrap().unwrap();
+        assert_eq!(found.current_hash, Some([9; 32]));
+        assert!(store.get("", &[9; 32]).unwrap().is_none());
+
+        let hashes = |records: Vec<Rhex>| -> Vec<_> {
+            records
+                .into_iter()
+                .map(|r| r.current_hash.unwrap())
+                .collect()
+        };
+        assert_eq!(hashes(store.range("", 1..3).unwrap()), [[2; 32], [3; 32]]);
+        assert_eq!(hashes(store.chain("").unwrap()).len(), 3);
+        assert_eq!(store.scopes().unwrap(), ["", "acme"]);
+
+        store.unappend(&third).unwrap();
+        assert_eq!(store.head("").unwrap(), Some([2; 32]));
+        store.append(&third).unwrap();
+        assert_eq!(store.head("").unwrap(), Some([3; 32]));
+    }
+
+    #[test]
+    fn backends_agree() {
+        exercise(&MemoryStore::new());
+        exercise(&SqliteStore::open_in_memory().unwrap());
+
+        let root = std::env::temp_dir().join(format!("hl-io-{}-store", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        exercise(&DirStore::new(root.clone()));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    ops::Range,
+    sync::{Mutex, MutexGuard},
+};
+
+use anyhow::Result;
+use hl_core::{Rhex, error, to_base64};
+use rusqlite::{Connection, OptionalExtension, params};
+
+use crate::store::{RhexStore, check_append};
+
+/// Chains in a SQLite file of their own. Kept apart from the cache,
+/// which a rebuild throws away.
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    pub fn open(path: &str) -> Result<Self> {
+        let conn = Connection::open(path)
+            .map_err(|e| anyhow::anyhow!("{}: {}: {}", error::E_DB_CONNECT, path, e))?;
+        Self::with_conn(conn)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_conn(Connection::open_in_memory()?)
+    }
+
+    fn with_conn(conn: Connection) -> Result<Self> {
+        // `seq` is the chain position; genesis has an empty previous_hash
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS records (
+                scope TEXT NOT NULL,
+                seq INTEGER NOT NULL,
+                previous_hash TEXT NOT NULL,
+                current_hash TEXT NOT NULL,
+                cbor BLOB NOT NULL,
+                PRIMARY KEY (scope, seq),
+                UNIQUE (scope, previous_hash),
+                UNIQUE (scope, current_hash)
+            )",
+            [],
+        )?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn conn(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn one(&self, sql: &str, scope: &str, key: &str) -> Result<Option<Rhex>> {
+        let cbor: Option<Vec<u8>> = self
+            .conn()
+            .query_row(sql, params![scope, key], |row| row.get(0))
+            .optional()?;
+        cbor.map(|cbor| Rhex::from_cbor(&cbor)).transpose()
+    }
+}
+
+fn previous_key(previous_hash: Option<&[u8; 32]>) -> String {
+    previous_hash.map(|h| to_base64(h)).unwrap_or_default()
+}
+
+impl RhexStore for SqliteStore {
+    fn append(&self, rhex: &Rhex) -> Result<()> {
+        let previous = previous_key(rhex.intent.previous_hash.as_ref());
+        let taken = self
+            .conn()
+            .query_row(
+                "SELECT 1 FROM records WHERE scope = ?1 AND previous_hash = ?2",
+                params![rhex.intent.scope, previous],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        let current_hash = check_append(rhex, taken)?;
+        self.conn().execute(
+            "INSERT INTO records (scope, seq, previous_hash, current_hash, cbor)
+                SELECT ?1, COALESCE(MAX(seq) + 1, 0), ?2, ?3, ?4 FROM records WHERE scope = ?1",
+            params![
+                rhex.intent.scope,
+                previous,
+                to_base64(&current_hash),
+                rhex.into_cbor()?
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn unappend(&self, rhex: &Rhex) -> Result<()> {
+        let Some(current_hash) = rhex.current_hash else {
+            return Ok(());
+        };
+        self.conn().execute(
+            "DELETE FROM records WHERE scope = ?1 AND current_hash = ?2
+                AND seq = (SELECT MAX(seq) FROM records WHERE scope = ?1)",
+            params![rhex.intent.scope, to_base64(&current_hash)],
+        )?;
+        Ok(())
+    }
+
+    fn get(&self, scope: &str, current_hash: &[u8; 32]) -> Result<Option<Rhex>> {
+        self.one(
+            "SELECT cbor FROM records WHERE scope = ?1 AND current_hash = ?2",
+            scope,
+            &to_base64(current_hash),
+        )
+    }
+
+    fn get_by_previous(
+        &self,
+        scope: &str,
+        previous_hash: Option<&[u8; 32]>,
+    ) -> Result<Option<Rhex>> {
+        self.one(
+            "SELECT cbor FROM records WHERE scope = ?1 AND previous_hash = ?2",
+            scope,
+            &previous_key(previous_hash),
+        )
+    }
+
+    fn head(&self, scope: &str) -> Result<Option<[u8; 32]>> {
+        let head: Option<String> = self
+            .conn()
+            .query_row(
+                "SELECT current_hash FROM records WHERE scope = ?1 ORDER BY seq DESC LIMIT 1",
+                params![scope],
+                |row| row.get(0),
+            )
+            .optional()?;
+        head.map(|h| hl_core::b64::b64::from_base64_to_32(&h))
+            .transpose()
+    }
+
+    fn range(&self, scope: &str, range: Range<usize>) -> Result<Vec<Rhex>> {
+        let bound = |n: usize| i64::try_from(n).unwrap_or(i64::MAX);
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT cbor FROM records WHERE scope = ?1 AND seq >= ?2 AND seq < ?3 ORDER BY seq",
+        )?;
+        let rows = stmt.query_map(
+            params![scope, bound(range.start), bound(range.end)],
+            |row| row.get::<_, Vec<u8>>(0),
+        )?;
+        rows.map(|cbor| Rhex::from_cbor(&cbor?)).collect()
+    }
+
+    fn scopes(&self) -> Result<Vec<String>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT DISTINCT scope FROM records ORDER BY scope")?;
+        let scopes = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(scopes)
+    }
+}
@@ -0,0 +1,466 @@
+//! Full-chain audit.
+//!
+//! Walks a scope from `genesis.rhex` to its head through any
+//! [`RhexSource`] and re-checks every record without touching the cache:
+//! signatures, `current_hash`, `previous_hash` linkage and quorum against
+//! the policy that was in force when the record was appended.
+
+use hl_core::{
+    Rhex, error,
+    rhex::{
+        batch::{BatchReport, verify_records},
+        signature::{SigCheck, SigType},
+    },
+    to_base64,
+};
+use hl_io::source::RhexSource;
+
+use crate::{audit::state::ChainState, quorum};
+
+pub mod report;
+mod state;
+
+pub use report::{AuditReport, RecordReport};
+
+/// Audit every record `source` yields, in order.
+pub fn audit_chain<S: RhexSource + ?Sized>(source: &mut S) -> AuditReport {
+    let mut report = AuditReport::default();
+    let mut records = Vec::new();
+    loop {
+        match source.next() {
+            Ok(Some(rhex)) => records.push(rhex),
+            Ok(None) => break,
+            Err(e) => {
+                report.source_error = Some(format!("{:#}", e));
+                break;
+            }
+        }
+    }
+    if let Some(genesis) = records.first() {
+        report.scope = Some(genesis.intent.scope.clone());
+    }
+
+    // Every signature in one pass up front; the per-record checks below
+    // just look up the outcome.
+    let sigs = verify_records(&records);
+    let mut state: Option<ChainState> = None;
+    let mut prev_hash: Option<[u8; 32]> = None;
+    for (index, rhex) in records.iter().enumerate() {
+        let record = audit_record(index, rhex, &sigs, &mut state, prev_hash);
+        prev_hash = rhex.current_hash;
+        report.records.push(record);
+    }
+    report
+}
+
+fn audit_record(
+    index: usize,
+    rhex: &Rhex,
+    sigs: &BatchReport,
+    state: &mut Option<ChainState>,
+    prev_hash: Option<[u8; 32]>,
+) -> RecordReport {
+    let mut record = RecordReport::new(index, rhex);
+
+    if rhex.version().is_err() {
+        record.push(error::E_MAGIC_BAD, "unknown magic/format version");
+        // Nothing below can be trusted without a known format.
+        return record;
+    }
+
+    check_linkage(rhex, index, prev_hash, &mut record);
+    check_signatures(rhex, index, sigs, &mut record);
+    check_current_hash(rhex, &mut record);
+
+    if index == 0 && rhex.intent.record_type == "scope:genesis" {
+        *state = Some(ChainState::from_genesis(rhex));
+    }
+    match state {
+        Some(state) => {
+            check_revoked(rhex, state, &mut record);
+            check_quorum(rhex, index, sigs, state, &mut record);
+            // A record that failed its checks changes nothing later ones
+            // are judged against
+            if record.is_ok()
+                && let Err(e) = state.apply(rhex)
+            {
+                record.push(
+                    error::E_DATA_SCHEMA_INVALID,
+                    format!("record data could not be applied: {}", e),
+                );
+            }
+        }
+        None => record.push(
+            error::E_POLICY_MISSING,
+            "no genesis, so no policy to check quorum against",
+        ),
+    }
+    record
+}
+
+fn check_linkage(
+    rhex: &Rhex,
+    index: usize,
+    prev_hash: Option<[u8; 32]>,
+    record: &mut RecordReport,
+) {
+    if index == 0 {
+        if rhex.intent.record_type != "scope:genesis" {
+            record.push(
+                error::E_GENESIS_SCOPE_INVALID,
+                "chain does not start with scope:genesis",
+            );
+        }
+        if rhex.intent.previous_hash.is_some() {
+            record.push(
+                error::E_GENESIS_SCOPE_INVALID,
+                "genesis must not carry a previous_hash",
+            );
+        }
+        return;
+    }
+    match (rhex.intent.previous_hash, prev_hash) {
+        (None, _) => record.push(
+            error::E_PREVIOUS_HASH_MISSING,
+            "previous_hash missing on non-genesis record",
+        ),
+        (Some(_), None) => record.push(
+            error::E_CHAIN_BREAK_PREV_MISMATCH,
+            "prior record has no current_hash to link to",
+        ),
+        (Some(ours), Some(theirs)) if ours != theirs => record.push(
+            error::E_CHAIN_BREAK_PREV_MISMATCH,
+            format!(
+                "previous_hash {} does not match prior current_hash {}",
+                to_base64(&ours),
+                to_base64(&theirs)
+            ),
+        ),
+        _ => {}
+    }
+}
+
+fn check_signatures(rhex: &Rhex, index: usize, sigs: &BatchReport, record: &mut RecordReport) {
+    let author = rhex
+        .signatures
+        .iter()
+        .find(|s| s.sig_type == SigType::Author);
+    match author {
+        None => record.push(error::E_SIG_MISSING, "no author signature"),
+        Some(sig) if sig.public_key != rhex.intent.author_pk => record.push(
+            error::E_AUTHOR_MISMATCH,
+            "author signature is not from intent.author_pk",
+        ),
+        _ => {}
+    }
+    let usher = rhex
+        .signatures
+        .iter()
+        .find(|s| s.sig_type == SigType::Usher);
+    match usher {
+        None => record.push(error::E_SIG_MISSING, "no usher signature"),
+        Some(sig) if sig.public_key != rhex.intent.usher_pk => record.push(
+            error::E_USHER_MISMATCH,
+            "usher signature is not from intent.usher_pk",
+        ),
+        _ => {}
+    }
+
+    for (i, sig) in rhex.signatures.iter().enumerate() {
+        match sigs.check(index, i) {
+            Ok(check) => {
+                if let Some(code) = check.error_code() {
+                    record.push(
+                        code,
+                        format!(
+                            "{} signature from {} does not verify",
+                            sig.sig_type,
+                            to_base64(&sig.public_key)
+                        ),
+                    );
+                }
+            }
+            Err(e) => record.push(
+                error::E_SIG_MISSING,
+                format!("{} signature cannot be checked: {}", sig.sig_type, e),
+            ),
+        }
+    }
+}
+
+fn check_current_hash(rhex: &Rhex, record: &mut RecordReport) {
+    let Some(advertised) = rhex.current_hash else {
+        record.push(error::E_HASH_MISSING, "record was never finalized");
+        return;
+    };
+    match rhex.generate_current_hash() {
+        Ok(computed) if computed == advertised => {}
+        Ok(_) => record.push(
+            error::E_HASH_MISMATCH,
+            "current_hash does not match recomputed hash",
+        ),
+        Err(e) => record.push(
+            error::E_HASH_MISMATCH,
+            format!("current_hash could not be recomputed: {}", e),
+        ),
+    }
+}
+
+/// Nobody revoked before the record's `at` may have signed it, as
+/// `validate_signers_not_revoked` enforces on append.
+fn check_revoked(rhex: &Rhex, state: &ChainState, record: &mut RecordReport) {
+    let mut signers: Vec<&[u8; 32]> = std::iter::once(&rhex.intent.author_pk)
+        .chain(rhex.signatures.iter().map(|sig| &sig.public_key))
+        .collect();
+    signers.sort();
+    signers.dedup();
+    for pk in signers {
+        if let Some(revoked_at) = state.revoked_at(pk)
+            && rhex.context.at > revoked_at
+        {
+            record.push(
+                error::E_KEY_REVOKED,
+                format!(
+                    "{} was revoked at {} before this record's {}",
+                    to_base64(pk),
+                    revoked_at,
+                    rhex.context.at
+                ),
+            );
+        }
+    }
+}
+
+fn check_quorum(
+    rhex: &Rhex,
+    index: usize,
+    sigs: &BatchReport,
+    state: &ChainState,
+    record: &mut RecordReport,
+) {
+    let record_type = &rhex.intent.record_type;
+    let Some(rule) = state.rule_for(record_type) else {
+        record.push(
+            error::E_POLICY_MISSING,
+            format!("no rule in force covers {}", record_type),
+        );
+        return;
+    };
+
+    let mut seen: Vec<[u8; 32]> = Vec::new();
+    let mut counted: u16 = 0;
+    for (i, sig) in rhex
+        .signatures
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.sig_type == SigType::Quorum)
+    {
+        // Delegated sub-keys sign for the authority their chain leads to
+        let member = match quorum::signing_for(rhex, &sig.public_key) {
+            Ok(member) => member,
+            Err(e) => {
+                record.push(e.code, e.message);
+                continue;
+            }
+        };
+        if seen.contains(&member) {
+            record.push(
+                error::E_ROLE_QUORUM_DUP_KEYS,
+                format!("{} signed quorum twice", to_base64(&member)),
+            );
+            continue;
+        }
+        seen.push(member);
+
+        let genesis_usher = record_type == "scope:genesis" && state.genesis_usher == Some(member);
+        if !genesis_usher && !state.holds_role(&member, &rule.quorum_roles, rhex.context.at) {
+            record.push(
+                error::E_QUORUM_INVALID_MEMBER,
+                format!(
+                    "{} holds none of {:?} at {}",
+                    to_base64(&member),
+                    rule.quorum_roles,
+                    rhex.context.at
+                ),
+            );
+            continue;
+        }
+        if matches!(sigs.check(index, i), Ok(SigCheck::Valid)) {
+            counted += 1;
+        }
+    }
+
+    if counted < rule.quorum_k {
+        record.push(
+            error::E_QUORUM_INSUFFICIENT,
+            format!(
+                "quorum needs {} valid signatures from {:?}, found {}",
+                rule.quorum_k, rule.quorum_roles, counted
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build;
+    use hl_core::Key;
+
+    struct VecSource(std::vec::IntoIter<Rhex>);
+
+    impl RhexSource for VecSource {
+        fn next(&mut self) -> Result<Option<Rhex>, anyhow::Error> {
+            Ok(self.0.next())
+        }
+    }
+
+    fn full_sign(mut rhex: Rhex, key: &Key) -> Rhex {
+        rhex.intent.author_pk = key.pk.unwrap();
+        rhex.intent.usher_pk = key.pk.unwrap();
+        rhex = build::author_sign(&rhex, key).unwrap();
+        rhex = build::usher_sign(&rhex, key).unwrap();
+        rhex = build::quorum_sign(&rhex, key).unwrap();
+        build::finalize(&rhex).unwrap()
+    }
+
+    #[test]
+    fn flags_broken_link_and_uncovered_record() {
+        let key = Key::generated();
+
+        let mut genesis = Rhex::new();
+        genesis.intent.record_type = "scope:genesis".to_string();
+        let genesis = full_sign(genesis, &key);
+
+        let mut next = Rhex::new();
+        next.intent.previous_hash = Some([7u8; 32]);
+        next.intent.record_type = "record:data".to_string();
+        let next = full_sign(next, &key);
+
+        let mut source = VecSource(vec![genesis, next].into_iter());
+        let report = audit_chain(&mut source);
+
+        assert!(report.records[0].is_ok(), "{:?}", report.records[0]);
+        let codes = &report.records[1].codes;
+        assert!(codes.contains(&error::E_CHAIN_BREAK_PREV_MISMATCH.to_string()));
+        assert!(codes.contains(&error::E_POLICY_MISSING.to_string()));
+        assert!(!report.is_clean());
+    }
+
+    #[test]
+    fn forged_grant_is_not_applied() {
+        let key = Key::generated();
+        let forged = Key::generated();
+
+        let mut genesis = Rhex::new();
+        genesis.intent.record_type = "scope:genesis".to_string();
+        let genesis = full_sign(genesis, &key);
+
+        let mut rule = hl_core::policy::rule::Rule::new("");
+        rule.record_types = vec!["key:grant".to_string(), "record:data".to_string()];
+        rule.append_roles = vec!["authority".to_string()];
+        rule.quorum_k = 1;
+        rule.quorum_roles = vec!["authority".to_string()];
+        let mut policy = Rhex::new();
+        policy.intent.previous_hash = genesis.current_hash;
+        policy.intent.record_type = "policy:set".to_string();
+        policy.intent.data = serde_json::json!({ "rules": [rule] });
+        let policy = full_sign(policy, &key);
+
+        let mut grant = Rhex::new();
+        grant.intent.previous_hash = policy.current_hash;
+        grant.intent.record_type = "key:grant".to_string();
+        grant.intent.data = serde_json::json!({
+            "public_key": hl_core::to_base64(&forged.pk.unwrap()),
+            "note": "forged",
+            "roles": ["authority"],
+            "effective_micromark": 0,
+            "expiration_micromark": u64::MAX,
+        });
+        let mut grant = full_sign(grant, &key);
+        grant.signatures[2].sig[0] ^= 1;
+
+        let mut data = Rhex::new();
+        data.intent.previous_hash = grant.current_hash;
+        data.intent.record_type = "record:data".to_string();
+        let data = full_sign(data, &forged);
+
+        let mut source = VecSource(vec![genesis, policy, grant, data].into_iter());
+        let report = audit_chain(&mut source);
+
+        assert!(report.records[1].is_ok(), "{:?}", report.records[1]);
+        assert!(!report.records[2].is_ok());
+        let codes = &report.records[3].codes;
+        assert!(
+            codes.contains(&error::E_QUORUM_INVALID_MEMBER.to_string()),
+            "{:?}",
+            report.records[3]
+        );
+    }
+
+    #[test]
+    fn rotate_hands_roles_over() {
+        let old = Key::generated();
+        let new = Key::generated();
+        let roles = vec!["authority".to_string()];
+
+        let mut genesis = Rhex::new();
+        genesis.intent.record_type = "scope:genesis".to_string();
+        let mut state = state::ChainState::from_genesis(&full_sign(genesis, &old));
+
+        let mut rotate = Rhex::new();
+        rotate.intent.record_type = "key:rotate".to_string();
+        rotate.intent.data = serde_json::json!({ "pk": hl_core::to_base64(&new.pk.unwrap()) });
+        rotate.context.at = 100;
+        state.apply(&full_sign(rotate, &old)).unwrap();
+
+        assert!(state.holds_role(&old.pk.unwrap(), &roles, 100));
+        assert!(!state.holds_role(&old.pk.unwrap(), &roles, 101));
+        assert!(!state.holds_role(&new.pk.unwrap(), &roles, 99));
+        assert!(state.holds_role(&new.pk.unwrap(), &roles, 101));
+    }
+
+    #[test]
+    fn grant_after_revoke_does_not_restore_a_key() {
+        let key = Key::generated();
+        let revoked = Key::generated();
+        let roles = vec!["authority".to_string()];
+
+        let mut genesis = Rhex::new();
+        genesis.intent.record_type = "scope:genesis".to_string();
+        let mut state = state::ChainState::from_genesis(&full_sign(genesis, &key));
+
+        let grant_at = |at: u64| {
+            let mut grant = Rhex::new();
+            grant.intent.record_type = "key:grant".to_string();
+            grant.intent.data = serde_json::json!({
+                "public_key": hl_core::to_base64(&revoked.pk.unwrap()),
+                "note": "regrant",
+                "roles": ["authority"],
+                "effective_micromark": 0,
+                "expiration_micromark": u64::MAX,
+            });
+            grant.context.at = at;
+            full_sign(grant, &key)
+        };
+        state.apply(&grant_at(10)).unwrap();
+        let mut revoke = Rhex::new();
+        revoke.intent.record_type = "key:revoke".to_string();
+        revoke.intent.data =
+            serde_json::json!({ "public_key": hl_core::to_base64(&revoked.pk.unwrap()) });
+        revoke.context.at = 100;
+        state.apply(&full_sign(revoke, &key)).unwrap();
+        state.apply(&grant_at(200)).unwrap();
+
+        assert!(state.holds_role(&revoked.pk.unwrap(), &roles, 100));
+        assert!(!state.holds_role(&revoked.pk.unwrap(), &roles, 201));
+
+        let mut data = Rhex::new();
+        data.intent.record_type = "record:data".to_string();
+        data.context.at = 201;
+        let data = full_sign(data, &revoked);
+        let mut record = RecordReport::new(0, &data);
+        check_revoked(&data, &state, &mut record);
+        assert_eq!(record.codes, vec![error::E_KEY_REVOKED.to_string()]);
+    }
+}
@@ -0,0 +1,82 @@
+use hl_core::{Rhex, to_base64};
+
+/// Findings for a single record in the chain.
+#[derive(Debug, Clone)]
+pub struct RecordReport {
+    pub index: usize,
+    pub record_type: String,
+    pub current_hash: Option<[u8; 32]>,
+    pub codes: Vec<String>,
+    pub messages: Vec<String>,
+}
+
+impl RecordReport {
+    pub fn new(index: usize, rhex: &Rhex) -> Self {
+        Self {
+            index,
+            record_type: rhex.intent.record_type.clone(),
+            current_hash: rhex.current_hash,
+            codes: Vec::new(),
+            messages: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, code: &str, msg: impl Into<String>) {
+        self.codes.push(code.to_string());
+        self.messages.push(msg.into());
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.codes.is_empty()
+    }
+
+    pub fn print(&self) {
+        let hash = self
+            .current_hash
+            .map(|h| to_base64(&h))
+            .unwrap_or_else(|| "<no current_hash>".to_string());
+        let mark = if self.is_ok() { "✅" } else { "❌" };
+        println!("[{}] #{} {} {}", mark, self.index, self.record_type, hash);
+        for (code, msg) in self.codes.iter().zip(self.messages.iter()) {
+            println!("    - {}: {}", code, msg);
+        }
+    }
+}
+
+/// Result of auditing one scope's chain end to end.
+#[derive(Debug, Clone, Default)]
+pub struct AuditReport {
+    pub scope: Option<String>,
+    pub records: Vec<RecordReport>,
+    /// The source gave up before the end of the chain (unreadable file,
+    /// non-canonical CBOR, broken link on disk, ...).
+    pub source_error: Option<String>,
+}
+
+impl AuditReport {
+    pub fn is_clean(&self) -> bool {
+        self.source_error.is_none() && self.records.iter().all(|r| r.is_ok())
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &RecordReport> {
+        self.records.iter().filter(|r| !r.is_ok())
+    }
+
+    pub fn print(&self) {
+        println!(
+            "Audit of scope \"{}\"",
+            self.scope.as_deref().unwrap_or("<unknown>")
+        );
+        for record in self.records.iter() {
+            record.print();
+        }
+        if let Some(e) = &self.source_error {
+            println!("[❌] chain unreadable after #{}: {}", self.records.len(), e);
+        }
+        println!(
+            "{} records, {} with errors",
+            self.records.len(),
+            self.failed().count()
+        );
+    }
+}
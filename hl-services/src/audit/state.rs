@@ -0,0 +1,112 @@
+use hl_core::{Authority, Key, Policy, Rhex, policy::rule::Rule, rhex::payload::KeyRevoke};
+
+use crate::process::{
+    key::{authority_from_rhex, rotated_authority},
+    policy::policy_from_rhex,
+};
+
+/// Scope state as it stood just before a given record: who holds which
+/// roles and which policy is in force. Rebuilt purely from the chain, the
+/// same way the processors build it in the cache.
+pub struct ChainState {
+    pub authorities: Vec<Authority>,
+    pub policy: Policy,
+    /// Genesis may be quorum signed by its usher rather than an authority.
+    pub genesis_usher: Option<[u8; 32]>,
+    /// Keys revoked or rotated out, and from when; a later grant does not
+    /// lift this, matching the cache's `revocations` table.
+    pub revoked: Vec<([u8; 32], u64)>,
+}
+
+impl ChainState {
+    /// What `scope:genesis` puts in place, as `process::scope` does.
+    pub fn from_genesis(genesis: &Rhex) -> Self {
+        let scope = &genesis.intent.scope;
+        // The genesis itself is checked under what stood before it
+        let mut policy = Policy::genesis(scope);
+        policy.rules.splice(0..0, Policy::pre_genesis(scope).rules);
+
+        Self {
+            authorities: vec![Authority {
+                scope: scope.clone(),
+                key: Key::from_pk_bytes(genesis.intent.author_pk),
+                roles: vec!["authority".to_string()],
+                eff: None,
+                exp: None,
+                note: Some("Default genesis authority".to_string()),
+            }],
+            policy,
+            genesis_usher: Some(genesis.intent.usher_pk),
+            revoked: Vec::new(),
+        }
+    }
+
+    /// The rule that governs `record_type`; later rules win.
+    pub fn rule_for(&self, record_type: &str) -> Option<&Rule> {
+        self.policy
+            .rules
+            .iter()
+            .rev()
+            .find(|rule| rule.applies_to(record_type))
+    }
+
+    /// When `pk` was revoked, if it was.
+    pub fn revoked_at(&self, pk: &[u8; 32]) -> Option<u64> {
+        self.revoked
+            .iter()
+            .find(|(revoked, _)| revoked == pk)
+            .map(|(_, at)| *at)
+    }
+
+    /// Does `pk` hold one of `roles` at micromark `at`?
+    pub fn holds_role(&self, pk: &[u8; 32], roles: &[String], at: u64) -> bool {
+        if self.revoked_at(pk).is_some_and(|revoked| at > revoked) {
+            return false;
+        }
+        self.authorities.iter().any(|auth| {
+            auth.key.pk.as_ref() == Some(pk)
+                && auth.is_valid(at)
+                && auth.roles.iter().any(|r| roles.contains(r))
+        })
+    }
+
+    /// `pk` may sign nothing after `at`; revoked twice, the earlier wins.
+    fn revoke(&mut self, pk: &[u8; 32], at: u64) {
+        match self.revoked.iter_mut().find(|(revoked, _)| revoked == pk) {
+            Some((_, revoked_at)) => *revoked_at = (*revoked_at).min(at),
+            None => self.revoked.push((*pk, at)),
+        }
+    }
+
+    /// Fold an accepted record's effects into the state.
+    pub fn apply(&mut self, rhex: &Rhex) -> Result<(), anyhow::Error> {
+        match rhex.intent.record_type.as_str() {
+            "policy:set" => {
+                self.policy = policy_from_rhex(rhex)?;
+            }
+            "key:grant" => {
+                let authority = authority_from_rhex(rhex)?;
+                let pk = authority.key.pk;
+                self.authorities.retain(|a| a.key.pk != pk);
+                self.authorities.push(authority);
+            }
+            "key:revoke" => {
+                let revoke: KeyRevoke = rhex.payload()?;
+                self.revoke(&revoke.public_key, rhex.context.at);
+            }
+            "key:rotate" => {
+                let old_pk = rhex.intent.author_pk;
+                let Some(old) = self.authorities.iter().find(|a| a.key.pk == Some(old_pk)) else {
+                    return Ok(());
+                };
+                let authority = rotated_authority(rhex, old)?;
+                let pk = authority.key.pk;
+                self.revoke(&old_pk, rhex.context.at);
+                self.authorities.retain(|a| a.key.pk != pk);
+                self.authorities.push(authority);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use hl_core::{Context, Intent, Key, Rhex, Signature, rhex::signature::SigType};
+use hl_core::{Context, Intent, Key, Rhex, Signature, rhex::rhex::RHEX_MAGIC};
 
 pub mod error;
 
@@ -9,7 +9,7 @@ pub fn build_rhex(
     current_hash: Option<[u8; 32]>,
 ) -> Result<Rhex, anyhow::Error> {
     Ok(Rhex {
-        magic: *b"RHEX\x00\x00",
+        magic: RHEX_MAGIC,
         intent: intent.clone(),
         context: context.clone(),
         signatures: signatures.clone(),
@@ -18,53 +18,20 @@ pub fn build_rhex(
 }
 
 pub fn author_sign(rhex: &Rhex, key: &Key) -> Result<Rhex, anyhow::Error> {
-    let author_hash = rhex.author_hash()?;
-    let author_sig = key.sign(&author_hash)?;
     let mut new_rhex = rhex.clone();
-    new_rhex.signatures.push(Signature {
-        sig_type: SigType::Author,
-        public_key: key.pk.unwrap(),
-        sig: author_sig,
-    });
+    new_rhex.sign_author(key)?;
     Ok(new_rhex)
 }
 
 pub fn usher_sign(rhex: &Rhex, key: &Key) -> Result<Rhex, anyhow::Error> {
-    let author_sig = rhex
-        .signatures
-        .iter()
-        .find(|s| s.sig_type == SigType::Author)
-        .ok_or_else(|| anyhow::anyhow!("No author signature found"))?;
-    let usher_hash = rhex.usher_hash(author_sig)?;
-    let usher_sig = key.sign(&usher_hash)?;
     let mut new_rhex = rhex.clone();
-    new_rhex.signatures.push(Signature {
-        sig_type: SigType::Usher,
-        public_key: key.pk.unwrap(),
-        sig: usher_sig,
-    });
+    new_rhex.sign_usher(key)?;
     Ok(new_rhex)
 }
 
 pub fn quorum_sign(rhex: &Rhex, key: &Key) -> Result<Rhex, anyhow::Error> {
-    let author_sig = rhex
-        .signatures
-        .iter()
-        .find(|s| s.sig_type == SigType::Author)
-        .ok_or_else(|| anyhow::anyhow!("No author signature found"))?;
-    let usher_sig = rhex
-        .signatures
-        .iter()
-        .find(|s| s.sig_type == SigType::Usher)
-        .ok_or_else(|| anyhow::anyhow!("No usher signature found"))?;
-    let quorum_hash = rhex.quorum_hash(author_sig, usher_sig)?;
-    let quorum_sig = key.sign(&quorum_hash)?;
     let mut new_rhex = rhex.clone();
-    new_rhex.signatures.push(Signature {
-        sig_type: SigType::Quorum,
-        public_key: key.pk.unwrap(),
-        sig: quorum_sig,
-    });
+    new_rhex.sign_quorum(key)?;
     Ok(new_rhex)
 }
 
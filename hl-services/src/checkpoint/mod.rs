@@ -0,0 +1,182 @@
+//! Merkle checkpoints over a scope's chain, and inclusion proofs against
+//! them. Verification for light clients lives in `hl_core::merkle`.
+
+use anyhow::bail;
+use hl_core::{
+    Config, Context, Intent, Rhex, error,
+    keymaster::keymaster::Keymaster,
+    merkle::merkle::{InclusionProof, merkle_root},
+    rhex::payload::Checkpoint,
+};
+use hl_io::{
+    db::{self, Cache},
+    store::RhexStore,
+};
+
+use crate::quorum;
+
+fn record_hashes(chain: &[Rhex]) -> Result<Vec<[u8; 32]>, anyhow::Error> {
+    chain
+        .iter()
+        .map(|r| {
+            r.current_hash
+                .ok_or_else(|| anyhow::anyhow!(error::E_HASH_MISSING))
+        })
+        .collect()
+}
+
+/// The `scope:checkpoint` this usher should append next, or `None` if
+/// nothing was appended since the last one. Signed by us as author and
+/// usher, plus our quorum signature if we hold a member key; the rest of
+/// quorum is gathered and the record finalized like any other.
+/// Scopes whose policy has no `scope:checkpoint` rule refuse it.
+pub fn build_checkpoint(
+    cache: &Cache,
+    store: &dyn RhexStore,
+    config: &Config,
+    keymaster: &Keymaster,
+    scope: &str,
+) -> Result<Option<Rhex>, anyhow::Error> {
+    let chain = store.chain(scope)?;
+    let usher_pk = match chain.last() {
+        None => return Ok(None),
+        Some(last) if last.intent.record_type == "scope:checkpoint" => return Ok(None),
+        Some(last) => last.intent.usher_pk,
+    };
+    let hashes = record_hashes(&chain)?;
+    let last = hashes[hashes.len() - 1];
+    // Sign as the scope's current usher if we hold its key
+    let key = match keymaster.get_matching(&usher_pk) {
+        Ok(key) => key,
+        Err(_) => keymaster.get_primary_key()?,
+    };
+    let pk = key.public_key_bytes()?;
+
+    let mut rhex = Rhex::new();
+    rhex.intent = Intent {
+        previous_hash: Some(last),
+        scope: scope.to_string(),
+        nonce: Intent::gen_nonce(),
+        author_pk: pk,
+        usher_pk: pk,
+        record_type: "scope:checkpoint".to_string(),
+        data: serde_json::to_value(Checkpoint {
+            root: merkle_root(&hashes),
+            size: hashes.len() as u64,
+            last,
+        })?,
+        delegation: None,
+    };
+    rhex.sign_author(&key)?;
+    rhex.context = Context::from_at(db::epoch::now_at(cache, scope, config.clock.as_ref())?);
+    rhex.sign_usher(&key)?;
+    if let Some(member) = quorum::unsigned_member(cache, &rhex, keymaster)? {
+        rhex.sign_quorum(&member)?;
+    }
+    Ok(Some(rhex))
+}
+
+/// Does `checkpoint` cover exactly the records before it in `chain`?
+pub fn check_checkpoint(chain: &[Rhex], checkpoint: &Rhex) -> Result<(), anyhow::Error> {
+    let covered: Checkpoint = checkpoint.payload()?;
+    let hashes = record_hashes(chain)?;
+    let size = covered.size as usize;
+    if size == 0 || size > hashes.len() {
+        bail!(
+            "{}: checkpoint covers {} records, scope has {}",
+            error::E_CHECKPOINT_INVALID,
+            size,
+            hashes.len()
+        );
+    }
+    if checkpoint.intent.previous_hash != Some(covered.last) || hashes[size - 1] != covered.last {
+        bail!(
+            "{}: checkpoint does not end at its previous record",
+            error::E_CHECKPOINT_INVALID
+        );
+    }
+    if merkle_root(&hashes[..size]) != covered.root {
+        bail!("{}: checkpoint root mismatch", error::E_CHECKPOINT_INVALID);
+    }
+    Ok(())
+}
+
+/// Proof that the record with `hash` is in `scope`, against the latest
+/// checkpoint covering it.
+pub fn prove(
+    store: &dyn RhexStore,
+    scope: &str,
+    hash: &[u8; 32],
+) -> Result<(InclusionProof, Rhex), anyhow::Error> {
+    let chain = store.chain(scope)?;
+    let hashes = record_hashes(&chain)?;
+    let Some(index) = hashes.iter().position(|h| h == hash) else {
+        bail!("{}: record not in scope {}", error::E_NOT_FOUND, scope);
+    };
+    let Some(checkpoint) = chain
+        .iter()
+        .rev()
+        .find(|r| r.intent.record_type == "scope:checkpoint")
+    else {
+        bail!(
+            "{}: scope {} has no checkpoint",
+            error::E_CHECKPOINT_NOT_FOUND,
+            scope
+        );
+    };
+    let covered: Checkpoint = checkpoint.payload()?;
+    let size = covered.size as usize;
+    if index >= size || size > hashes.len() {
+        bail!(
+            "{}: record is newer than the latest checkpoint",
+            error::E_CHECKPOINT_NOT_FOUND
+        );
+    }
+    let proof = InclusionProof::new(&hashes[..size], index)?;
+    Ok((proof, checkpoint.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{process, testing::Ledger};
+
+    #[test]
+    fn fresh_scope_appends_its_checkpoint() {
+        let ledger = Ledger::new("checkpoint");
+        let Ledger {
+            cache,
+            store,
+            config,
+            keymaster,
+            ..
+        } = &ledger;
+        let genesis = store.head("").unwrap().unwrap();
+
+        let mut rhex = build_checkpoint(cache, store, config, keymaster, "")
+            .unwrap()
+            .unwrap();
+        // The genesis policy covers checkpoints at K=1, which we sign
+        assert_eq!(quorum::count(&rhex), 1);
+        assert_eq!(quorum::needed(cache, &rhex).unwrap(), 0);
+        rhex.finalize().unwrap();
+
+        let out = process::process_rhex(&rhex, true, cache, store, config, keymaster).unwrap();
+        assert!(
+            out.iter().all(|r| r.intent.record_type != "response:error"),
+            "{:?}",
+            out
+        );
+        assert_eq!(store.head("").unwrap(), rhex.current_hash);
+        assert_eq!(db::head::get_head(cache, "").ok(), rhex.current_hash);
+
+        // Nothing new to cover, and the genesis proves against it
+        assert!(
+            build_checkpoint(cache, store, config, keymaster, "")
+                .unwrap()
+                .is_none()
+        );
+        let (_, checkpoint) = prove(store, "", &genesis).unwrap();
+        assert_eq!(checkpoint.current_hash, rhex.current_hash);
+    }
+}
@@ -1,5 +1,5 @@
-use hl_core::{Config, config::ConfigFile};
-use hl_io::fs;
+use hl_core::{Config, Key, config::ConfigFile};
+use hl_io::{fs, signer};
 use std::{fs as file_fs, path::Path};
 
 pub fn load_config(path: &str) -> Result<Config, anyhow::Error> {
@@ -13,10 +13,16 @@ pub fn load_config(path: &str) -> Result<Config, anyhow::Error> {
     // I have the energy for right now. Wanna fix it? Be my guest.
     let mut incoming_hot = Vec::new();
     for key in config_file.hot_keys.unwrap_or(vec![]) {
-        let hot_key = fs::authority::load_key_hot(&Path::new(&key))?;
+        let hot_key = Key::from_secret(fs::authority::load_key_hot(&Path::new(&key))?);
         println!("Loaded key file {}", key);
         incoming_hot.push(hot_key);
     }
+    // Encrypted key files and agents, which keep secrets out of here
+    for spec in config_file.signers.unwrap_or(vec![]) {
+        let keys = signer::open(&spec)?;
+        println!("Loaded {} key(s) from {:?}", keys.len(), spec);
+        incoming_hot.extend(keys);
+    }
 
     let config = Config {
         host: config_file.host.unwrap_or("0.0.0.0".to_string()),
@@ -25,8 +31,12 @@ pub fn load_config(path: &str) -> Result<Config, anyhow::Error> {
         fs_dir: config_file.fs_dir.unwrap_or("./fs".to_string()),
         data_dir: config_file.data_dir.unwrap_or("./data".to_string()),
         cache_db: config_file.cache_db.unwrap_or("./cache.db".to_string()),
+        store: config_file.store.unwrap_or("dir".to_string()),
+        store_db: config_file.store_db.unwrap_or("./ledger.db".to_string()),
+        journal_dir: config_file.journal_dir.unwrap_or("./journal".to_string()),
         hot_keys: incoming_hot,
         verbose: config_file.verbose.unwrap_or(false),
+        ..Config::new()
     };
     Ok(config)
 }
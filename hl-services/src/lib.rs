@@ -1,5 +1,12 @@
+pub mod audit;
 pub mod bootstrap;
 pub mod build;
+pub mod checkpoint;
 pub mod config;
 pub mod process;
+pub mod quorum;
+pub mod rebuild;
 pub mod scope;
+
+#[cfg(test)]
+mod testing;
@@ -0,0 +1,40 @@
+use hl_core::{Rhex, spatial::frame::Frame};
+use hl_io::{
+    db::{self, Cache},
+    store::RhexStore,
+};
+
+pub fn process_frame(
+    rhex: &Rhex,
+    first_time: bool,
+    cache: &Cache,
+    store: &dyn RhexStore,
+) -> Result<Vec<Rhex>, anyhow::Error> {
+    match rhex.intent.record_type.as_str() {
+        "frame:define" => frame_define(rhex, first_time, cache, store),
+        _ => Err(anyhow::anyhow!(
+            "Unsupported record type for frame processing"
+        )),
+    }
+}
+
+pub fn frame_define(
+    rhex: &Rhex,
+    first_time: bool,
+    cache: &Cache,
+    store: &dyn RhexStore,
+) -> Result<Vec<Rhex>, anyhow::Error> {
+    print!("[🧭:🟢]=~=");
+    let frame: Frame = rhex.payload()?;
+    frame.check()?;
+    db::frame::store_frame(cache, &rhex.intent.scope, &frame)?;
+
+    if first_time {
+        println!(
+            "Frame {} ({}) defined for scope {}",
+            frame.name, frame.units, rhex.intent.scope
+        );
+        store.append(rhex)?;
+    }
+    Ok(Vec::new())
+}
@@ -1,18 +1,24 @@
-use std::{path::PathBuf, str::FromStr, sync::Arc};
-
 use anyhow::bail;
-use hl_core::{Authority, Config, Rhex, b64::b64::from_base64_to_32};
-use hl_io::{db::connect_db, fs, sink::RhexSink};
-
-use crate::process::data::{get_data_array, get_data_string, get_data_u64};
+use hl_core::{
+    Authority, Key, Rhex, error,
+    rhex::payload::{KeyGrant, KeyRevoke, KeyRotate},
+    to_base64,
+};
+use hl_io::{
+    db::{self, Cache},
+    store::RhexStore,
+};
 
 pub fn process_key(
     rhex: &Rhex,
     first_time: &bool,
-    config: &Arc<Config>,
+    cache: &Cache,
+    store: &dyn RhexStore,
 ) -> Result<Vec<Rhex>, anyhow::Error> {
     let out_rhex = match rhex.intent.record_type.as_str() {
-        "key:grant" => key_grant(&rhex, first_time, &config),
+        "key:grant" => key_grant(rhex, first_time, cache, store),
+        "key:revoke" => key_revoke(rhex, first_time, cache, store),
+        "key:rotate" => key_rotate(rhex, first_time, cache, store),
         _ => Ok(Vec::new()),
     };
     Ok(out_rhex?)
@@ -21,63 +27,227 @@ pub fn process_key(
 pub fn key_grant(
     rhex: &Rhex,
     first_time: &bool,
-    config: &Arc<Config>,
+    cache: &Cache,
+    store: &dyn RhexStore,
 ) -> Result<Vec<Rhex>, anyhow::Error> {
     print!("[🔑:🟢]=~=");
-    let cache = connect_db(&config.cache_db)?;
 
-    let data_key = get_data_string(
-        rhex,
-        &vec!["pk".to_string(), "public_key".to_string(), "🔓".to_string()],
-    );
-
-    if data_key.is_err() {
-        bail!("Key Grant missing public key in data");
+    let authority = authority_from_rhex(rhex)?;
+    hl_io::db::authority::store_authority(cache, &rhex.intent.scope, &authority)?;
+    if *first_time {
+        println!(
+            "Stored new authority key {} for scope {} with roles {:?}",
+            to_base64(&authority.key.public_key_bytes()?),
+            rhex.intent.scope,
+            authority.roles
+        );
+        store.append(rhex)?;
     }
 
-    let public_key_b64 = data_key.unwrap();
-    let public_key_bytes = from_base64_to_32(&public_key_b64)?;
-
-    let mut authority = Authority::new();
-    let note = Some(get_data_string(
-        rhex,
-        &vec!["n".to_string(), "note".to_string(), "🗒️".to_string()],
-    )?);
-    let roles = get_data_array(
-        rhex,
-        &vec!["r".to_string(), "roles".to_string(), "🥐".to_string()],
-    );
-
-    authority.scope = rhex.intent.scope.clone();
-    authority.note = note;
-    authority.key = hl_core::Key::from_pk_bytes(public_key_bytes);
-    authority.roles = roles.unwrap_or(vec![]);
-    authority.eff = Some(get_data_u64(
-        rhex,
-        &vec![
-            "eff".to_string(),
-            "effective_micromark".to_string(),
-            "🟢🕑".to_string(),
-        ],
-    )?);
-    authority.exp = Some(get_data_u64(
-        rhex,
-        &vec![
-            "exp".to_string(),
-            "expiration_micromark".to_string(),
-            "🔴🕑".to_string(),
-        ],
-    )?);
-
-    hl_io::db::authority::store_authority(&cache, &rhex.intent.scope, &authority)?;
+    Ok(Vec::new())
+}
+
+pub fn key_revoke(
+    rhex: &Rhex,
+    first_time: &bool,
+    cache: &Cache,
+    store: &dyn RhexStore,
+) -> Result<Vec<Rhex>, anyhow::Error> {
+    print!("[🔑:🔴]=~=");
+    let revoke: KeyRevoke = rhex.payload()?;
+    let scope = &rhex.intent.scope;
+    db::authority::expire_authority(cache, scope, &revoke.public_key, rhex.context.at)?;
+    db::revocation::store_revocation(cache, scope, &revoke.public_key, rhex.context.at)?;
     if *first_time {
         println!(
-            "Stored new authority key {} for scope {} with roles {:?}",
-            public_key_b64, rhex.intent.scope, authority.roles
+            "Revoked key {} in scope {}",
+            to_base64(&revoke.public_key),
+            scope
         );
-        let mut dir_sink = fs::rhex::DirSink::new(PathBuf::from_str(&config.fs_dir)?);
-        dir_sink.send(rhex)?;
+        store.append(rhex)?;
     }
+    Ok(Vec::new())
+}
 
+pub fn key_rotate(
+    rhex: &Rhex,
+    first_time: &bool,
+    cache: &Cache,
+    store: &dyn RhexStore,
+) -> Result<Vec<Rhex>, anyhow::Error> {
+    print!("[🔑:🔄]=~=");
+    let scope = &rhex.intent.scope;
+    let old_pk = rhex.intent.author_pk;
+    let Some(old) = db::authority::get_authority(cache, scope, &old_pk)? else {
+        bail!(
+            "{}: {} holds no authority in scope {} to rotate",
+            error::E_ROLE_NOT_PERMITTED,
+            to_base64(&old_pk),
+            scope
+        );
+    };
+    let authority = rotated_authority(rhex, &old)?;
+    db::authority::store_authority(cache, scope, &authority)?;
+    db::authority::expire_authority(cache, scope, &old_pk, rhex.context.at)?;
+    db::revocation::store_revocation(cache, scope, &old_pk, rhex.context.at)?;
+    if *first_time {
+        println!(
+            "Rotated key {} to {} in scope {} with roles {:?}",
+            to_base64(&old_pk),
+            to_base64(&authority.key.public_key_bytes()?),
+            scope,
+            authority.roles
+        );
+        store.append(rhex)?;
+    }
     Ok(Vec::new())
 }
+
+/// The authority a `key:rotate` record hands to its new key: the old
+/// one's roles and expiry, in force from the record's `at`.
+pub(crate) fn rotated_authority(rhex: &Rhex, old: &Authority) -> Result<Authority, anyhow::Error> {
+    let rotate: KeyRotate = rhex.payload()?;
+    Ok(Authority {
+        scope: rhex.intent.scope.clone(),
+        key: Key::from_pk_bytes(rotate.public_key),
+        roles: old.roles.clone(),
+        eff: Some(rhex.context.at),
+        exp: old.exp,
+        note: rotate.note.or(old.note.clone()),
+    })
+}
+
+/// Build the `Authority` a `key:grant` record adds to its scope.
+pub(crate) fn authority_from_rhex(rhex: &Rhex) -> Result<Authority, anyhow::Error> {
+    let grant: KeyGrant = rhex.payload()?;
+    Ok(Authority {
+        scope: rhex.intent.scope.clone(),
+        key: hl_core::Key::from_pk_bytes(grant.public_key),
+        roles: grant.roles,
+        eff: Some(grant.eff),
+        exp: Some(grant.exp),
+        note: Some(grant.note),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Ledger;
+    use serde_json::json;
+
+    fn record(record_type: &str, data: serde_json::Value) -> Rhex {
+        let mut rhex = Rhex::new();
+        rhex.intent.record_type = record_type.to_string();
+        rhex.intent.data = data;
+        rhex
+    }
+
+    /// A root ledger whose authorities may grant, revoke and rotate keys
+    /// and append data.
+    fn ledger(name: &str) -> Ledger {
+        let ledger = Ledger::new(name);
+        let policy = record(
+            "policy:set",
+            json!({
+                "quorum_ttl": 1_000_000_000u64,
+                "rules": [{
+                    "record_types": ["policy:set", "key:grant", "key:revoke", "key:rotate", "record:data"],
+                    "append_roles": ["authority"],
+                    "quorum_k": 1,
+                    "quorum_roles": ["authority"],
+                    "rate_per_mark": 1000,
+                }],
+            }),
+        );
+        assert_accepted(&ledger.append(policy, &ledger.key));
+        ledger
+    }
+
+    fn grant(ledger: &Ledger, key: &Key, roles: &[&str], exp: u64) {
+        let grant = record(
+            "key:grant",
+            json!({
+                "public_key": to_base64(&key.pk.unwrap()),
+                "note": "test",
+                "roles": roles,
+                "effective_micromark": 0,
+                "expiration_micromark": exp,
+            }),
+        );
+        assert_accepted(&ledger.append(grant, &ledger.key));
+    }
+
+    fn data_at(at: u64) -> Rhex {
+        let mut rhex = record("record:data", json!({ "n": at }));
+        rhex.context.at = at;
+        rhex
+    }
+
+    /// Why the processor wouldn't take a record, if it didn't.
+    fn refusal(out: &Result<Vec<Rhex>, anyhow::Error>) -> Option<String> {
+        match out {
+            Err(e) => Some(format!("{:#}", e)),
+            Ok(out) => out
+                .iter()
+                .find(|r| r.intent.record_type == "response:error")
+                .map(|r| r.intent.data.to_string()),
+        }
+    }
+
+    fn assert_accepted(out: &Result<Vec<Rhex>, anyhow::Error>) {
+        assert_eq!(refusal(out), None);
+    }
+
+    #[test]
+    fn revoked_key_is_refused_after_revocation_only() {
+        let ledger = ledger("revoke");
+        let revoked = Key::generated();
+        grant(&ledger, &revoked, &["authority"], u64::MAX / 2);
+
+        let at = ledger.now_at("");
+        let mut revoke = record(
+            "key:revoke",
+            json!({ "public_key": to_base64(&revoked.pk.unwrap()) }),
+        );
+        revoke.context.at = at;
+        assert_accepted(&ledger.append(revoke, &ledger.key));
+
+        let head = ledger.store.head("").unwrap();
+        let out = ledger.append(data_at(at + 1), &revoked);
+        let reason = refusal(&out).expect("appended with a revoked key");
+        assert!(reason.starts_with(error::E_KEY_REVOKED), "{}", reason);
+        assert_eq!(ledger.store.head("").unwrap(), head);
+
+        // Signed while it still held
+        assert_accepted(&ledger.append(data_at(at - 1), &revoked));
+        assert_accepted(&ledger.append(data_at(at), &revoked));
+    }
+
+    #[test]
+    fn rotated_key_inherits_roles_and_expiry() {
+        let ledger = ledger("rotate");
+        let (old, new) = (Key::generated(), Key::generated());
+        let exp = u64::MAX / 2;
+        grant(&ledger, &old, &["authority", "steward"], exp);
+
+        let at = ledger.now_at("");
+        let mut rotate = record(
+            "key:rotate",
+            json!({ "public_key": to_base64(&new.pk.unwrap()) }),
+        );
+        rotate.context.at = at;
+        assert_accepted(&ledger.append(rotate, &old));
+
+        let rotated = db::authority::get_authority(&ledger.cache, "", &new.pk.unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(rotated.roles, ["authority", "steward"]);
+        assert_eq!(rotated.exp, Some(exp));
+        assert_eq!(rotated.eff, Some(at));
+
+        assert_accepted(&ledger.append(data_at(at + 1), &new));
+        let reason = refusal(&ledger.append(data_at(at + 1), &old)).expect("old key still appends");
+        assert!(reason.starts_with(error::E_KEY_REVOKED), "{}", reason);
+    }
+}
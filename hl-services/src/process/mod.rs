@@ -1,13 +1,17 @@
 use hl_core::{Config, Rhex, keymaster::keymaster::Keymaster};
+use hl_io::{db::Cache, store::RhexStore};
 
 use std::sync::Arc;
 
 mod data;
-mod key;
-mod policy;
+mod frame;
+pub(crate) mod key;
+pub(crate) mod policy;
 mod processor;
 mod record;
+mod record_type;
 mod request;
+mod schema;
 mod scope;
 mod scope_request;
 mod usher;
@@ -15,8 +19,22 @@ mod usher;
 pub fn process_rhex(
     rhex: &Rhex,
     first_time: bool,
+    cache: &Cache,
+    store: &dyn RhexStore,
     config: &Arc<Config>,
     keymaster: &Keymaster,
 ) -> Result<Vec<Rhex>, anyhow::Error> {
-    processor::process_rhex(rhex, first_time, config, keymaster)
+    processor::process_rhex(rhex, first_time, cache, store, config, keymaster)
+}
+
+/// Replay a record whose signatures were already batch-verified (see
+/// `hl_core::rhex::batch`), skipping the per-signature checks.
+pub fn replay_verified(
+    rhex: &Rhex,
+    cache: &Cache,
+    store: &dyn RhexStore,
+    config: &Arc<Config>,
+    keymaster: &Keymaster,
+) -> Result<Vec<Rhex>, anyhow::Error> {
+    processor::process(rhex, false, true, cache, store, config, keymaster)
 }
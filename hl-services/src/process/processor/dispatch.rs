@@ -2,29 +2,48 @@ use std::sync::Arc;
 
 use hl_core::{Config, Rhex, keymaster::keymaster::Keymaster};
 
+use hl_io::{
+    db::{self, Cache},
+    store::RhexStore,
+};
+
 use crate::process;
 
+/// Run the record's processor. `None` when it failed and its cache
+/// writes were rolled back.
 pub fn dispatch(
     rhex: &Rhex,
     first_time: bool,
+    cache: &Cache,
+    store: &dyn RhexStore,
     config: &Arc<Config>,
     keymaster: &Keymaster,
-) -> Result<Vec<Rhex>, anyhow::Error> {
+) -> Result<Option<Vec<Rhex>>, anyhow::Error> {
+    // A processor that fails leaves nothing of itself in the cache
+    let tx = cache.transaction()?;
     let prefix = rhex.intent.record_type.split(":").next().unwrap_or("");
     let out_rhex = match prefix {
-        "key" => process::key::process_key(rhex, &first_time, config),
-        "policy" => process::policy::process_policy(rhex, first_time, config),
-        "scope" => process::scope::process_scope(rhex, first_time, config, keymaster),
-        "request" => process::request::process_request(rhex, first_time, config),
-        "record" => process::record::process_record(rhex, first_time, config).map(|_| vec![]),
-        "usher" => process::usher::process_usher(rhex, first_time, config),
-        _ => Err(anyhow::anyhow!(
-            "Unsupported record type for dispatch processing"
-        )),
+        "frame" => process::frame::process_frame(rhex, first_time, cache, store),
+        "key" => process::key::process_key(rhex, &first_time, cache, store),
+        "policy" => process::policy::process_policy(rhex, first_time, cache, store),
+        "schema" => process::schema::process_schema(rhex, first_time, cache, store),
+        "scope" => process::scope::process_scope(rhex, first_time, cache, store, config, keymaster),
+        "request" => process::request::process_request(rhex, first_time, cache, store, config),
+        "record" => process::record::process_record(rhex, first_time, cache, store).map(|_| vec![]),
+        "type" => process::record_type::process_record_type(rhex, first_time, cache, store),
+        "usher" => process::usher::process_usher(rhex, first_time, cache, store),
+        // Anything else got past validation as a scope-defined type
+        _ => process::record::process_record(rhex, first_time, cache, store).map(|_| vec![]),
     };
     if out_rhex.is_err() {
         eprintln!("Error processing rhex: {:?}", out_rhex.err());
-        return Ok(Vec::new());
+        tx.rollback()?;
+        return Ok(None);
+    }
+    // Appended and located: make it findable by place
+    if rhex.current_hash.is_some() && rhex.context.refer.is_some() {
+        db::spatial::index_rhex(cache, rhex)?;
     }
-    Ok(out_rhex.unwrap())
+    tx.commit()?;
+    Ok(Some(out_rhex.unwrap()))
 }
@@ -1,20 +1,27 @@
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 
 use hl_core::{Config, Rhex, error, keymaster::keymaster::Keymaster, to_base64};
-use hl_io::db;
+use hl_io::{
+    db::{self, Cache},
+    fs::journal::Journal,
+    store::RhexStore,
+};
 
 use crate::{
     build,
     process::processor::{
         errors::Errors,
-        signatures::{signature_check_quorum, signature_quorum, signature_usher_and_quorum},
+        signatures::{
+            signature_check_quorum, signature_quorum, signature_usher_and_quorum, signature_verify,
+        },
         validation::{
             validate_context_at, validate_context_spacial, validate_current_hash,
             validate_intent_author_pk, validate_intent_data, validate_intent_nonce,
             validate_intent_previous_hash, validate_intent_record_type, validate_intent_scope,
-            validate_intent_usher_pk, validate_magic,
+            validate_intent_usher_pk, validate_magic, validate_signers_not_revoked,
         },
     },
+    quorum,
 };
 
 mod dispatch;
@@ -26,13 +33,33 @@ mod validation;
 pub fn process_rhex(
     rhex: &Rhex,
     first_time: bool,
+    cache: &Cache,
+    store: &dyn RhexStore,
+    config: &Arc<Config>,
+    keymaster: &Keymaster,
+) -> Result<Vec<Rhex>, anyhow::Error> {
+    process(rhex, first_time, false, cache, store, config, keymaster)
+}
+
+/// `sigs_verified` skips signature verification for records the caller
+/// already batch-checked. Everything one record writes to the cache is
+/// committed together, or rolled back if processing fails. Every record
+/// a first-time append adds to the store is journaled, so the files,
+/// cache rows and heads land together.
+pub fn process(
+    rhex: &Rhex,
+    first_time: bool,
+    sigs_verified: bool,
+    cache: &Cache,
+    store: &dyn RhexStore,
     config: &Arc<Config>,
     keymaster: &Keymaster,
 ) -> Result<Vec<Rhex>, anyhow::Error> {
     let mut outbound: Vec<Rhex> = Vec::new();
     let verbose = config.verbose;
     let mut errors = Errors::new();
-    let cache = db::connect_db(&config.cache_db)?;
+    let tx = cache.transaction()?;
+    let mut journal = None;
 
     if verbose {
         let ph_b64 = to_base64(&rhex.intent.previous_hash.unwrap_or([0u8; 32]));
@@ -43,45 +70,52 @@ pub fn process_rhex(
     validate_magic(rhex, &mut errors)?;
 
     // Intent
-    let current_hash = db::head::get_head(&rhex.intent.scope)?;
+    let current_hash = db::head::get_head(cache, &rhex.intent.scope)?;
     validate_intent_previous_hash(rhex, current_hash, &mut errors)?;
     validate_intent_scope(rhex, &mut errors)?;
-    validate_intent_nonce(rhex, &mut errors, &cache)?;
+    validate_intent_nonce(rhex, &mut errors, cache)?;
     validate_intent_author_pk(rhex, &mut errors)?;
-    validate_intent_usher_pk(rhex, &mut errors, &keymaster, &cache)?;
-    validate_intent_record_type(rhex, &mut errors, &cache)?;
-    validate_intent_data(rhex, &mut errors)?;
+    validate_signers_not_revoked(rhex, &mut errors, cache, config.clock.as_ref())?;
+    validate_intent_usher_pk(rhex, &mut errors, keymaster, cache)?;
+    validate_intent_record_type(rhex, &mut errors, cache, config.clock.as_ref())?;
+    validate_intent_data(rhex, &mut errors, cache)?;
 
     // Context
     if rhex.signatures.len() > 1 {
-        validate_context_at(rhex, &mut errors, &cache, first_time)?;
-        validate_context_spacial(rhex, &mut errors)?;
+        validate_context_at(rhex, &mut errors, cache, first_time, config.clock.as_ref())?;
+        validate_context_spacial(rhex, &mut errors, cache)?;
     }
 
     // Signatures
+    let signatures_valid = sigs_verified || signature_verify(rhex, &mut errors)?;
+    // Short of quorum nothing is appended; the gathering usher brings the
+    // finalized record back through here once it has enough signatures.
+    let mut gathering = false;
     match rhex.signatures.len() {
         0 => {
             errors.push(error::E_NO_SIGNATURES, "No signatures present");
         }
+        // Don't countersign anything that didn't verify
+        _ if !signatures_valid && rhex.current_hash.is_none() => {}
         1 => {
             // We should have an author sig and be looking for usher sig
             // and first quorum
             let mut out_rhex = rhex.clone();
             signature_usher_and_quorum(&mut out_rhex, &mut errors, keymaster)?;
+            gathering = quorum::needed(cache, &out_rhex)? > 0;
             outbound.push(out_rhex);
         }
-        2 => {
-            // We're looking for quorum. We're not gonna match usher_pk,
-            // but we should know who they are
+        _ if rhex.current_hash.is_none() => {
+            // Another usher is gathering quorum. We're not gonna match
+            // usher_pk, but we may hold a quorum member's key.
             let mut out_rhex = rhex.clone();
-            signature_quorum(&mut out_rhex, &mut errors, keymaster)?;
-            outbound.push(out_rhex)
+            signature_quorum(&mut out_rhex, &mut errors, keymaster, cache)?;
+            outbound.push(out_rhex);
+            gathering = true;
         }
         _ => {
-            // 3+, we have full quorum, we are looking to append. Make sure
-            // we are usher_pk and are a write authority for this scope.
-            // signature_check_quorum()
-            signature_check_quorum(rhex, &mut errors, &cache)?;
+            // Finalized with full quorum, we are looking to append.
+            signature_check_quorum(rhex, &mut errors, cache, sigs_verified)?;
         }
     }
 
@@ -89,12 +123,6 @@ pub fn process_rhex(
     if rhex.current_hash.is_some() {
         validate_current_hash(rhex, &mut errors)?;
     }
-    if rhex.signatures.len() != 1 && rhex.current_hash.is_none() {
-        errors.push(
-            error::E_HASH_MISSING,
-            "Expected 1 signature for initial Rhex, but found multiple without a current_hash.",
-        );
-    }
 
     // All done checking the R⬢ for stability. Either
     // process or show the list of errors.
@@ -102,9 +130,36 @@ pub fn process_rhex(
         if verbose {
             print!("[✅ R⬢ Valid]");
         };
-        outbound.append(&mut dispatch::dispatch(
-            rhex, first_time, config, &keymaster,
-        )?);
+        if !gathering {
+            if first_time && rhex.current_hash.is_some() {
+                journal = Some(Journal::begin(Path::new(&config.journal_dir), store, rhex)?);
+            }
+            let target: &dyn RhexStore = match &journal {
+                Some(journal) => journal,
+                None => store,
+            };
+            match dispatch::dispatch(rhex, first_time, cache, target, config, keymaster)? {
+                Some(mut out_rhex) => {
+                    // Replays advance the head too, so a rebuild can follow it
+                    if let Some(head) = rhex.current_hash {
+                        db::head::set_head(cache, &rhex.intent.scope, &head)?;
+                    }
+                    // Then each scope's head moves to the last record
+                    // appended to it, which chains on whatever came before
+                    for appended in journal.iter().flat_map(Journal::records) {
+                        if let Some(head) = appended.current_hash {
+                            db::head::set_head(cache, &appended.intent.scope, &head)?;
+                        }
+                    }
+                    outbound.append(&mut out_rhex);
+                }
+                None => {
+                    if let Some(journal) = journal.take() {
+                        journal.abort()?;
+                    }
+                }
+            }
+        }
     } else {
         if verbose {
             print!("[❌ R⬢ Invalid]");
@@ -124,5 +179,9 @@ pub fn process_rhex(
         )?;
         outbound.push(error_rhex);
     }
+    tx.commit()?;
+    if let Some(journal) = journal {
+        journal.finish()?;
+    }
     Ok(outbound)
 }
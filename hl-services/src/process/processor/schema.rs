@@ -1,7 +1,11 @@
-use hl_core::schema::Schema;
+use hl_core::schema::{Schema, parse_schema_uri};
+use hl_io::db;
+use hl_io::db::Cache;
 
-pub fn get_schema(schema: &String) -> Result<Schema, anyhow::Error> {
-    // TODO: Implement schema retrieval
-    println!("Getting schema: {}", schema);
-    Ok(Schema::new("".to_string(), &Vec::new())?)
+/// Load the schema a record names, e.g. `rhex://schema/policy-set@0`,
+/// from the `schema:define` records cached for that scope. `None` if it
+/// hasn't been defined yet.
+pub fn get_schema(cache: &Cache, uri: &str) -> Result<Option<Schema>, anyhow::Error> {
+    let (scope, name) = parse_schema_uri(uri)?;
+    db::schema::retrieve_schema(cache, scope, name)
 }
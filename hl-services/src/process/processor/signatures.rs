@@ -1,87 +1,112 @@
 use hl_core::{
-    Key, Policy, Rhex, Signature, error, keymaster::keymaster::Keymaster, policy::rule::Rule,
+    Key, Policy, Rhex, error, keymaster::keymaster::Keymaster, policy::rule::Rule,
     rhex::signature::SigType, to_base64,
 };
 use hl_io::db;
-use rusqlite::Connection;
-
-use crate::process::processor::errors::Errors;
+use hl_io::db::Cache;
+
+use crate::{process::processor::errors::Errors, quorum};
+
+/// Verify every signature present against its role's preimage. A
+/// signature that only verifies under another role or format version is
+/// flagged `E_SIG_DOMAIN_MISMATCH`.
+pub fn signature_verify(rhex: &Rhex, errors: &mut Errors) -> Result<bool, anyhow::Error> {
+    let mut all_valid = true;
+    for sig in rhex.signatures.iter() {
+        let check = match rhex.verify_signature(sig) {
+            Ok(check) => check,
+            Err(e) => {
+                errors.push(
+                    error::E_SIG_MISSING,
+                    format!("{} signature has nothing to sign over: {}", sig.sig_type, e),
+                );
+                all_valid = false;
+                continue;
+            }
+        };
+        if let Some(code) = check.error_code() {
+            errors.push(
+                code,
+                format!(
+                    "{} signature from {} failed verification",
+                    sig.sig_type,
+                    to_base64(&sig.public_key)
+                ),
+            );
+            all_valid = false;
+        }
+    }
+    Ok(all_valid)
+}
 
 pub fn signature_usher_and_quorum(
     rhex: &mut Rhex,
     errors: &mut Errors,
     keymaster: &Keymaster,
 ) -> Result<(), anyhow::Error> {
-    // First check to make sure we don't have some sig bullshit going
-    // on. Should be author only.
-    if rhex.signatures[0].sig_type != SigType::Author {
-        errors.push(error::E_SIG_INVALID, "Signature must be of type Author");
+    let Some(usher_key) = usher_key(rhex, errors, keymaster) else {
+        return Ok(());
+    };
+    // Sign as selected usher. The lifecycle refuses unless we're sitting
+    // at AuthorSigned with the author named in the intent.
+    if let Err(e) = rhex.sign_usher(&usher_key) {
+        errors.push(e.error_code(), e.to_string());
         return Ok(());
     }
-    // Next, sign as selected usher.
-    let usher_pk = rhex.intent.usher_pk;
-    let usher_key = Key::from_bytes(keymaster.get_matching(&usher_pk).unwrap());
-    let usher_sig = usher_key.sign(&rhex.usher_hash(&rhex.signatures[0].clone())?);
-    rhex.signatures.push(Signature {
-        sig_type: SigType::Usher,
-        public_key: usher_pk,
-        sig: usher_sig.unwrap(),
-    });
 
     // Finally sign as quorum
     // TODO: Eventually we want this to be a policy option or something
     // that dictates if the signing usher also signs quorum, but for now
     // we assume we need to sign our own quorum
-    let quorum_sig = usher_key
-        .sign(&rhex.quorum_hash(&rhex.signatures[0].clone(), &rhex.signatures[1].clone())?);
-    rhex.signatures.push(Signature {
-        sig_type: SigType::Quorum,
-        public_key: usher_pk,
-        sig: quorum_sig.unwrap(),
-    });
+    if let Err(e) = rhex.sign_quorum(&usher_key) {
+        errors.push(e.error_code(), e.to_string());
+    }
 
     Ok(())
 }
 
+/// Add our quorum signature to a record another usher is gathering
+/// quorum for, with whichever member key we hold that hasn't signed.
 pub fn signature_quorum(
     rhex: &mut Rhex,
     errors: &mut Errors,
     keymaster: &Keymaster,
+    cache: &Cache,
 ) -> Result<(), anyhow::Error> {
-    // Make sure the sigs aren't all fucked up. Author should always
-    // be first, followed by Usher.
-    if rhex.signatures[0].sig_type != SigType::Author {
-        errors.push(error::E_SIG_INVALID, "Signature 0 must be of type Author");
-        return Ok(());
-    }
-    if rhex.signatures[1].sig_type != SigType::Usher {
-        errors.push(error::E_SIG_INVALID, "Signature 1 must be of type Usher");
+    let Some(key) = quorum::unsigned_member(cache, rhex, keymaster)? else {
+        errors.push(
+            error::E_QUORUM_INVALID_MEMBER,
+            format!(
+                "No unsigned quorum member key held for {}",
+                rhex.intent.record_type
+            ),
+        );
         return Ok(());
+    };
+    if let Err(e) = rhex.sign_quorum(&key) {
+        errors.push(e.error_code(), e.to_string());
     }
-
-    // FIXME: This needs to load quorum members from policy (cache, most likely)
-    // so we are making sure we are quorum member.
-
-    // Finally sign as quorum
-    // TODO: Eventually we want this to be a policy option or something
-    // that dictates if the signing usher also signs quorum, but for now
-    // we assume we need to sign our own quorum
-    let usher_pk = rhex.intent.usher_pk;
-    let usher_key = Key::from_bytes(keymaster.get_matching(&usher_pk).unwrap());
-    let quorum_sig = usher_key
-        .sign(&rhex.quorum_hash(&rhex.signatures[0].clone(), &rhex.signatures[1].clone())?);
-    rhex.signatures.push(Signature {
-        sig_type: SigType::Quorum,
-        public_key: usher_pk,
-        sig: quorum_sig.unwrap(),
-    });
     Ok(())
 }
 
+fn usher_key(rhex: &Rhex, errors: &mut Errors, keymaster: &Keymaster) -> Option<Key> {
+    match keymaster.get_matching(&rhex.intent.usher_pk) {
+        Ok(key) => Some(key),
+        Err(_) => {
+            errors.push(
+                error::E_USHER_KEY_MISSING,
+                format!("No key held for usher {}", to_base64(&rhex.intent.usher_pk)),
+            );
+            None
+        }
+    }
+}
+
 pub fn signature_check_quorum(
     rhex: &Rhex,
     errors: &mut Errors,
-    cache: &Connection,
+    cache: &Cache,
+    sigs_verified: bool,
 ) -> Result<bool, anyhow::Error> {
     // Check if there is a quorum signature
     let mut quorum_sigs = Vec::new();
@@ -97,29 +122,16 @@ pub fn signature_check_quorum(
     }
 
     // Get the list of quorum members from policy
-    let policy = db::policy::retrieve_policy(cache, &rhex.intent.scope);
-    let mut policy = if policy.is_err() {
-        let mut rule = Rule::new(&rhex.intent.scope);
-        rule.record_types = vec!["scope:genesis".to_string()];
-        rule.append_roles = vec!["authority".to_string()];
-        rule.quorum_k = 1;
-        rule.quorum_roles = vec!["authority".to_string()];
-        rule.rate_per_mark = 1;
-        let mut policy = Policy::new();
-        policy.scope = rhex.intent.scope.clone();
-        policy.quorum_ttl = 1000000000;
-        policy.rules = vec![rule];
-
-        policy
-    } else {
-        policy.unwrap()
-    };
+    let mut policy = db::policy::retrieve_policy(cache, &rhex.intent.scope)
+        .unwrap_or_else(|_| Policy::pre_genesis(&rhex.intent.scope));
+    // Stored rules win; a scope without a policy keeps the genesis default
     let rules = db::rule::get_rules(cache, &rhex.intent.scope)?;
-    if rules.len() == 0 {
+    if !rules.is_empty() {
+        policy.rules = rules;
+    }
+    if policy.rules.is_empty() {
         errors.push(error::E_POLICY_INVALID, "No rules found for this scope");
         return Ok(false);
-    } else {
-        policy.rules = rules;
     }
     let mut done = false;
     let mut da_rule = Rule::new(&rhex.intent.scope);
@@ -160,10 +172,26 @@ pub fn signature_check_quorum(
         return Ok(false);
     }
 
-    // Make sure each sig is in the list of quorum members
+    // Make sure each sig is in the list of quorum members. A delegated
+    // sub-key counts as the authority its chain leads back to, once.
     let authorities = db::authority::get_authorities(&cache, &rhex.intent.scope)?;
+    let mut seen: Vec<[u8; 32]> = Vec::new();
     for sig in quorum_sigs.iter() {
-        let quorum_pk = sig.public_key;
+        let quorum_pk = match quorum::signing_for(rhex, &sig.public_key) {
+            Ok(member) => member,
+            Err(e) => {
+                errors.push(e.code, e.message);
+                return Ok(false);
+            }
+        };
+        if seen.contains(&quorum_pk) {
+            errors.push(
+                error::E_ROLE_QUORUM_DUP_KEYS,
+                format!("{} signed quorum twice", to_base64(&quorum_pk)),
+            );
+            return Ok(false);
+        }
+        seen.push(quorum_pk);
         for auth in authorities.iter() {
             if auth.key.pk == Some(quorum_pk) {
                 // Found a matching authority, break and continue to the next signature
@@ -188,19 +216,14 @@ pub fn signature_check_quorum(
                     return Ok(false);
                 }
 
-                // Verify signature while we are here
-                let author_sig = rhex
-                    .signatures
-                    .get(0)
-                    .ok_or_else(|| anyhow::anyhow!("Author signature missing for quorum check"))?;
-                let usher_sig = rhex
-                    .signatures
-                    .get(1)
-                    .ok_or_else(|| anyhow::anyhow!("Usher signature missing for quorum check"))?;
-                let quorum_hash = rhex.quorum_hash(author_sig, usher_sig)?;
-                if !auth.key.verify(&quorum_hash, &sig.sig)? {
+                // Verify signature while we are here, unless the caller
+                // already batch-checked it
+                if sigs_verified {
+                    break;
+                }
+                if let Some(code) = rhex.verify_signature(sig)?.error_code() {
                     errors.push(
-                        error::E_SIG_INVALID,
+                        code,
                         format!("Quorum signature from {} is invalid", to_base64(&quorum_pk)),
                     );
                     return Ok(false);
@@ -1,9 +1,15 @@
 use hl_core::{
-    Policy, Rhex, error, keymaster::keymaster::Keymaster, policy::rule::Rule,
-    rhex::record_types::is_valid_record_type, time::clock::GTClock,
+    Policy, Rhex,
+    authority::delegation,
+    error,
+    keymaster::keymaster::Keymaster,
+    policy::rule::Rule,
+    schema::{SCHEMA_DEFINE, Schema, Violation},
+    time::{clock::Clock, gt},
+    to_base64,
 };
 use hl_io::db;
-use rusqlite::Connection;
+use hl_io::db::Cache;
 
 use crate::process::{
     data::get_data_string,
@@ -12,9 +18,10 @@ use crate::process::{
 use once_cell::sync::Lazy;
 use regex::Regex;
 
-/// Validate the `magic` value of the Rhex
+/// Validate the `magic` value of the Rhex. Any format version we know
+/// how to verify is accepted.
 pub fn validate_magic(rhex: &Rhex, errors: &mut Errors) -> Result<(), anyhow::Error> {
-    if rhex.magic != *b"RHEX\x00\x00" {
+    if rhex.version().is_err() {
         errors.push(error::E_MAGIC_BAD, "Invalid magic value");
         return Err(anyhow::anyhow!("Invalid magic value"));
     }
@@ -88,7 +95,7 @@ pub fn validate_intent_scope(rhex: &Rhex, errors: &mut Errors) -> Result<(), any
 pub fn validate_intent_nonce(
     rhex: &Rhex,
     errors: &mut Errors,
-    cache: &Connection,
+    cache: &Cache,
 ) -> Result<(), anyhow::Error> {
     if rhex.intent.nonce.len() != 16 {
         errors.push(
@@ -123,12 +130,47 @@ pub fn validate_intent_author_pk(rhex: &Rhex, errors: &mut Errors) -> Result<(),
     Ok(())
 }
 
+/// Reject anything signed by a key revoked in this scope before the
+/// record's `at`. A record with only its author's signature has no `at`
+/// yet; the usher is about to stamp it with now.
+pub fn validate_signers_not_revoked(
+    rhex: &Rhex,
+    errors: &mut Errors,
+    cache: &Cache,
+    clock: &dyn Clock,
+) -> Result<(), anyhow::Error> {
+    let scope = &rhex.intent.scope;
+    let signers = std::iter::once(&rhex.intent.author_pk)
+        .chain(rhex.signatures.iter().map(|sig| &sig.public_key));
+    for pk in signers {
+        let Some(revoked_at) = db::revocation::get_revocation(cache, scope, pk)? else {
+            continue;
+        };
+        let at = if rhex.signatures.len() > 1 {
+            rhex.context.at
+        } else {
+            db::epoch::now_at(cache, scope, clock)?
+        };
+        if at > revoked_at {
+            let message = format!(
+                "Key {} was revoked in scope {} at {}",
+                to_base64(pk),
+                scope,
+                gt::format(revoked_at as i128)
+            );
+            errors.push(error::E_KEY_REVOKED, message.clone());
+            return Err(anyhow::anyhow!("{}: {}", error::E_KEY_REVOKED, message));
+        }
+    }
+    Ok(())
+}
+
 /// Validate that the `usher_pk` is valid
 pub fn validate_intent_usher_pk(
     rhex: &Rhex,
     errors: &mut Errors,
     keymaster: &Keymaster,
-    cache: &Connection,
+    cache: &Cache,
 ) -> Result<(), anyhow::Error> {
     if rhex.intent.usher_pk == [0u8; 32] {
         errors.push(error::E_USHER_KEY_DECODE, "rhex.intent.usher_pk invalid");
@@ -157,10 +199,12 @@ pub fn validate_intent_usher_pk(
 pub fn validate_intent_record_type(
     rhex: &Rhex,
     errors: &mut Errors,
-    cache: &Connection,
+    cache: &Cache,
+    clock: &dyn Clock,
 ) -> Result<(), anyhow::Error> {
-    // validate that the author has permission to append this record type
-    let valid = is_valid_record_type(&rhex.intent.record_type);
+    // Built-ins, plus whatever this scope and its ancestors defined
+    let registry = db::record_type::load_registry(cache, &rhex.intent.scope)?;
+    let valid = registry.is_known(&rhex.intent.scope, &rhex.intent.record_type);
     if !valid {
         errors.push(
             error::E_RECORD_TYPE_UNKNOWN,
@@ -214,6 +258,7 @@ pub fn validate_intent_record_type(
         return Err(anyhow::anyhow!("Append denied based on rules"));
     }
     let last_rule = last_rule.unwrap();
+    validate_delegated_author(rhex, errors, cache, clock, &last_rule)?;
     let rate_per_mark = last_rule.rate_per_mark;
     let last_append = db::rhex::get_last_append(
         &cache,
@@ -225,8 +270,8 @@ pub fn validate_intent_record_type(
         // The author has previously submitted a R⬢, make sure this
         // one isn't too soon.
         let last_append = last_append.unwrap();
-        let clock = GTClock::new(0);
-        if clock.now_micromarks_u64() - last_append.0 < rate_per_mark.into() {
+        let now = db::epoch::now_at(cache, &rhex.intent.scope, clock)?;
+        if now.saturating_sub(last_append.0) < rate_per_mark.into() {
             errors.push(
                 error::E_RATE_LIMITED,
                 format!(
@@ -240,56 +285,137 @@ pub fn validate_intent_record_type(
     Ok(())
 }
 
-/// Validate intent `data` based off schema
-pub fn validate_intent_data(rhex: &Rhex, errors: &mut Errors) -> Result<(), anyhow::Error> {
-    // TODO: Validate via schema
-    // I'm tired as fuck and this doesn't affect append so we're skipping
-    // it for now.
-    let schema = get_data_string(rhex, &vec!["sch".to_string(), "schema".to_string()]);
-    // If an error, that just means we have no schema, which is fine
-    if schema.is_err() {
+/// A delegated author appends as the granted authority its chain leads
+/// back to, within what every certificate on the way allows.
+fn validate_delegated_author(
+    rhex: &Rhex,
+    errors: &mut Errors,
+    cache: &Cache,
+    clock: &dyn Clock,
+    rule: &Rule,
+) -> Result<(), anyhow::Error> {
+    let Some(certs) = &rhex.intent.delegation else {
         return Ok(());
+    };
+    let scope = &rhex.intent.scope;
+    let at = if rhex.signatures.len() > 1 {
+        rhex.context.at
+    } else {
+        db::epoch::now_at(cache, scope, clock)?
+    };
+    let root = match delegation::resolve(
+        certs,
+        &rhex.intent.author_pk,
+        scope,
+        &rhex.intent.record_type,
+        at,
+    ) {
+        Ok(Some(root)) => root,
+        Ok(None) => {
+            errors.push(
+                error::E_DELEGATION_INVALID,
+                "Delegation carried, but no certificate names the author",
+            );
+            return Err(anyhow::anyhow!("Delegation does not name the author"));
+        }
+        Err(e) => {
+            errors.push(e.code, e.message.clone());
+            return Err(e.into());
+        }
+    };
+    // A revoked key takes everything it delegated down with it
+    for pk in certs.iter().flat_map(|c| [&c.issuer_pk, &c.subject_pk]) {
+        if let Some(revoked_at) = db::revocation::get_revocation(cache, scope, pk)?
+            && at > revoked_at
+        {
+            let message = format!("Delegating key {} was revoked", to_base64(pk));
+            errors.push(error::E_KEY_REVOKED, message.clone());
+            return Err(anyhow::anyhow!("{}: {}", error::E_KEY_REVOKED, message));
+        }
+    }
+    let granted = db::authority::get_authority(cache, scope, &root)?.filter(|a| a.is_valid(at));
+    let Some(granted) = granted else {
+        let message = format!(
+            "Delegation ends at {}, which holds no authority in {}",
+            to_base64(&root),
+            scope
+        );
+        errors.push(error::E_DELEGATION_INVALID, message.clone());
+        return Err(anyhow::anyhow!(message));
+    };
+    let roles: Vec<&str> = granted.roles.iter().map(|r| r.as_str()).collect();
+    if !rule.can_append(&roles) {
+        errors.push(
+            error::E_APPEND_DENIED,
+            "Delegating authority may not append this record type",
+        );
+        return Err(anyhow::anyhow!("Append denied based on rules"));
     }
-    let schema = schema.unwrap();
+    Ok(())
+}
 
-    // Load schema from cache
-    let loaded_schema = get_schema(&schema);
-    if loaded_schema.is_err() {
-        // FIXME: This is kind of an area of contention. Should schema
-        // silently fail? It kind of has to until we get the schema
-        // scope fully populated.
+/// Validate intent `data` based off schema
+pub fn validate_intent_data(
+    rhex: &Rhex,
+    errors: &mut Errors,
+    cache: &Cache,
+) -> Result<(), anyhow::Error> {
+    // Named in the data, or else by the scope's definition of a custom type
+    let schema = match get_data_string(rhex, &vec!["sch".to_string(), "schema".to_string()]) {
+        Ok(schema) => Some(schema),
+        Err(_) => db::record_type::load_registry(cache, &rhex.intent.scope)?
+            .custom(&rhex.intent.scope, &rhex.intent.record_type)
+            .and_then(|def| def.schema.clone()),
+    };
+    // No schema at all is fine
+    let Some(schema) = schema else {
+        return Ok(());
+    };
+
+    // Schema definitions are checked against the definition format
+    // itself; the rest against whatever the schema scope published.
+    let violations = if schema == SCHEMA_DEFINE {
+        match Schema::from_define(&rhex.intent.data) {
+            Ok(_) => Vec::new(),
+            Err(e) => vec![Violation::new(
+                error::E_DATA_SCHEMA_INVALID,
+                "$",
+                e.to_string(),
+            )],
+        }
     } else {
-        // Verify the schema we did get.
-        let loaded_schema = loaded_schema.unwrap();
-        for constraint in loaded_schema.constraints {
-            let data_value = rhex.intent.data.get(constraint.name.clone());
-            if constraint.required == 1 && data_value.is_none() {
-                errors.push(
-                    error::E_SCHEMA_CONSTRAINT_VIOLATION,
-                    format!(
-                        "Schema constraint violation: {} is required",
-                        constraint.name
-                    ),
-                );
-                return Err(anyhow::anyhow!(
-                    "Schema constraint violation: {} is required",
-                    constraint.name
-                ));
-            } else if constraint.required == 0 && data_value.is_none() {
-                // Not required, so it's fine if it's missing
-                continue;
-            } else if constraint.required == 0 && data_value.is_some() {
-                // Not required, but present. Validate it.
-                // TODO: Add type validation here
-                continue;
-            } else if constraint.required == 1 && data_value.is_some() {
-                // Required and present. Validate it.
-                // TODO: Add type validation here
-            }
+        match get_schema(cache, &schema) {
+            Ok(Some(loaded_schema)) => loaded_schema.validate(&rhex.intent.data),
+            Ok(None) => vec![Violation::new(
+                error::E_DATA_SCHEMA_INVALID,
+                "$.schema",
+                format!("{} is not defined", schema),
+            )],
+            Err(e) => vec![Violation::new(
+                error::E_DATA_SCHEMA_INVALID,
+                "$.schema",
+                format!("{} could not be loaded: {}", schema, e),
+            )],
         }
+    };
+    if violations.is_empty() {
+        return Ok(());
     }
-
-    Ok(())
+    for violation in violations.iter() {
+        errors.push(
+            violation.code,
+            format!("Schema {} violation at {}", schema, violation),
+        );
+    }
+    Err(anyhow::anyhow!(
+        "Schema {} violation at {}",
+        schema,
+        violations
+            .iter()
+            .map(|v| v.path.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
 }
 
 /// Validate context at - this is if we are seeking quorum because
@@ -297,21 +423,22 @@ pub fn validate_intent_data(rhex: &Rhex, errors: &mut Errors) -> Result<(), anyh
 pub fn validate_context_at(
     rhex: &Rhex,
     errors: &mut Errors,
-    cache: &Connection,
+    cache: &Cache,
     first_time: bool,
+    clock: &dyn Clock,
 ) -> Result<(), anyhow::Error> {
     // If we are looking for quorum we give a shit, otherwise, keep moving
     if rhex.signatures.len() > 2 && first_time {
         let policy = db::policy::retrieve_policy(&cache, &rhex.intent.scope)?;
         let quorum_ttl = policy.quorum_ttl;
-        let clock = GTClock::new(0);
-        if clock.now_micromarks_u64() - rhex.context.at > quorum_ttl {
+        let elapsed =
+            db::epoch::now_at(cache, &rhex.intent.scope, clock)?.saturating_sub(rhex.context.at);
+        if elapsed > quorum_ttl {
             errors.push(
                 error::E_TIME_WINDOW_EXPIRED,
                 format!(
                     "Quorum TTL exceeded. Must be signed within {} micromarks. {} micromarks have passed.",
-                    quorum_ttl,
-                    clock.now_micromarks_u64() - rhex.context.at
+                    quorum_ttl, elapsed
                 ),
             );
             return Err(anyhow::anyhow!("Quorum TTL exceeded"));
@@ -336,7 +463,11 @@ pub fn validate_context_at(
 }
 
 /// Validate that if we have one spacial coordinate we have them all.
-pub fn validate_context_spacial(rhex: &Rhex, errors: &mut Errors) -> Result<(), anyhow::Error> {
+pub fn validate_context_spacial(
+    rhex: &Rhex,
+    errors: &mut Errors,
+    cache: &Cache,
+) -> Result<(), anyhow::Error> {
     if rhex.context.x.is_some()
         || rhex.context.y.is_some()
         || rhex.context.z.is_some()
@@ -379,6 +510,12 @@ pub fn validate_context_spacial(rhex: &Rhex, errors: &mut Errors) -> Result<(),
                 "rhex.context.refer missing when spacial data is present"
             ));
         }
+        // refer names a frame this scope knows, and we're inside it
+        let frames = db::frame::load_registry(cache, &rhex.intent.scope)?;
+        if let Err((code, message)) = frames.check_context(&rhex.intent.scope, &rhex.context) {
+            errors.push(code, message.clone());
+            return Err(anyhow::anyhow!(message));
+        }
     }
     Ok(())
 }
@@ -401,3 +538,37 @@ pub fn validate_current_hash(rhex: &Rhex, errors: &mut Errors) -> Result<(), any
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn undefined_schema_is_refused() {
+        let cache = Cache::open_in_memory().unwrap();
+        db::migrate::migrate(&cache).unwrap();
+        let mut rhex = Rhex::new();
+        rhex.intent.record_type = "scope:request".to_string();
+        rhex.intent.data = json!({ "schema": "rhex://schema/scope-request@0" });
+
+        let mut errors = Errors::new();
+        assert!(validate_intent_data(&rhex, &mut errors, &cache).is_err());
+        assert_eq!(errors.stack, [error::E_DATA_SCHEMA_INVALID]);
+        assert!(
+            errors.messages[0].contains("$.schema"),
+            "{:?}",
+            errors.messages
+        );
+
+        let schema = Schema::from_define(&json!({
+            "name": "scope-request@0",
+            "fields": [{ "id": 0, "name": "new_scope", "req": 1, "type": "string" }]
+        }))
+        .unwrap();
+        db::schema::store_schema(&cache, "schema", &schema).unwrap();
+        let mut errors = Errors::new();
+        assert!(validate_intent_data(&rhex, &mut errors, &cache).is_err());
+        assert_eq!(errors.stack, [error::E_SCHEMA_CONSTRAINT_VIOLATION]);
+    }
+}
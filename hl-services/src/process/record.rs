@@ -1,18 +1,30 @@
-use std::{path::PathBuf, str::FromStr, sync::Arc};
-
-use hl_core::{Config, Rhex};
-use hl_io::{fs::rhex::DirSink, sink::RhexSink};
+use hl_core::Rhex;
+use hl_io::{
+    db::{self, Cache, rhex::CacheSink},
+    sink::RhexSink,
+    store::RhexStore,
+};
 
 pub fn process_record(
     rhex: &Rhex,
     first_time: bool,
-    config: &Arc<Config>,
+    cache: &Cache,
+    store: &dyn RhexStore,
 ) -> Result<(), anyhow::Error> {
     print!("[📦:📊]=~=");
 
     if first_time {
-        let mut dir_sink = DirSink::new(PathBuf::from_str(&config.fs_dir)?);
-        dir_sink.send(rhex)?;
+        store.append(rhex)?;
+    }
+
+    // Custom types defined as stateful are kept queryable in the cache
+    let registry = db::record_type::load_registry(cache, &rhex.intent.scope)?;
+    if registry
+        .custom(&rhex.intent.scope, &rhex.intent.record_type)
+        .is_some_and(|def| def.stateful)
+    {
+        let mut cache_sink = CacheSink::new(cache);
+        cache_sink.send(rhex)?;
     }
     Ok(())
 }
@@ -0,0 +1,40 @@
+use hl_core::{Rhex, rhex::record_types::RecordTypeDef};
+use hl_io::{
+    db::{self, Cache},
+    store::RhexStore,
+};
+
+pub fn process_record_type(
+    rhex: &Rhex,
+    first_time: bool,
+    cache: &Cache,
+    store: &dyn RhexStore,
+) -> Result<Vec<Rhex>, anyhow::Error> {
+    match rhex.intent.record_type.as_str() {
+        "type:define" => type_define(rhex, first_time, cache, store),
+        _ => Err(anyhow::anyhow!(
+            "Unsupported record type for type processing"
+        )),
+    }
+}
+
+pub fn type_define(
+    rhex: &Rhex,
+    first_time: bool,
+    cache: &Cache,
+    store: &dyn RhexStore,
+) -> Result<Vec<Rhex>, anyhow::Error> {
+    print!("[🏷️:🟢]=~=");
+    let def: RecordTypeDef = rhex.payload()?;
+    def.check_name()?;
+    db::record_type::store_record_type(cache, &rhex.intent.scope, &def)?;
+
+    if first_time {
+        println!(
+            "Record type {} defined for scope {}",
+            def.name, rhex.intent.scope
+        );
+        store.append(rhex)?;
+    }
+    Ok(Vec::new())
+}
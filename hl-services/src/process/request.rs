@@ -1,21 +1,34 @@
-use std::{path::PathBuf, str::FromStr, sync::Arc};
+use std::sync::Arc;
 
 use hl_core::{
-    Config, Context, Intent, Key, Rhex, Signature, keymaster::keymaster::Keymaster,
-    rhex::signature::SigType, time::clock::GTClock, to_base64,
+    Config, Context, Intent, Rhex, Signature, error,
+    keymaster::keymaster::Keymaster,
+    rhex::{
+        payload::{ProofRequest, RhexRequest, ScopeQuery},
+        signature::SigType,
+    },
+    to_base64,
+};
+use hl_io::{
+    db::{self, Cache},
+    store::RhexStore,
 };
-use hl_io::{fs::rhex::DirSource, source::RhexSource};
 use serde_json::json;
 
+use crate::checkpoint;
+
 pub fn process_request(
     rhex: &Rhex,
     first_time: bool,
+    cache: &Cache,
+    store: &dyn RhexStore,
     config: &Arc<Config>,
 ) -> Result<Vec<Rhex>, anyhow::Error> {
     match rhex.intent.record_type.as_str() {
-        "request:rhex" => request_rhex(rhex, first_time, config),
-        "request:head" => request_head(rhex, first_time, config),
-        "request:scope" => request_scope(rhex, first_time, config),
+        "request:rhex" => request_rhex(rhex, first_time, cache, store),
+        "request:head" => request_head(rhex, first_time, cache, store, config),
+        "request:scope" => request_scope(rhex, first_time, cache, config),
+        "request:proof" => request_proof(rhex, first_time, cache, store, config),
         _ => Err(anyhow::anyhow!(
             "Unsupported record type for request processing"
         )),
@@ -25,18 +38,39 @@ pub fn process_request(
 pub fn request_rhex(
     rhex: &Rhex,
     first_time: bool,
-    config: &Arc<Config>,
+    cache: &Cache,
+    store: &dyn RhexStore,
 ) -> Result<Vec<Rhex>, anyhow::Error> {
     let mut rhex_out = Vec::new();
     print!("[📥:R⬢]=~=");
     if first_time {
         println!("Getting scope request...");
         let scope = rhex.intent.scope.clone();
-        let mut scope_data = DirSource::new(PathBuf::from_str(&config.fs_dir)?)?;
-        while let Some(rhex_item) = scope_data.next()? {
-            if rhex_item.intent.scope == scope {
-                rhex_out.push(rhex_item);
+        let request: RhexRequest = rhex.payload_or_default()?;
+        // Narrow to the spatial index's hits, if asked
+        let wanted = match &request.spatial {
+            Some(query) => {
+                let frames = db::frame::load_registry(cache, &scope)?;
+                let Some(frame) = frames.frame(&scope, &query.frame) else {
+                    anyhow::bail!(
+                        "{}: {} is not a known frame",
+                        error::E_CONTEXT_REFER_INVALID,
+                        query.frame
+                    );
+                };
+                Some(db::spatial::query(cache, &scope, &frame, query)?)
+            }
+            None => None,
+        };
+        for rhex_item in store.chain(&scope)? {
+            if let Some(wanted) = &wanted
+                && !rhex_item
+                    .current_hash
+                    .is_some_and(|hash| wanted.contains(&hash))
+            {
+                continue;
             }
+            rhex_out.push(rhex_item);
         }
         println!("Found {} records for scope {}", rhex_out.len(), scope);
     }
@@ -46,51 +80,49 @@ pub fn request_rhex(
 pub fn request_head(
     rhex: &Rhex,
     first_time: bool,
+    cache: &Cache,
+    store: &dyn RhexStore,
     config: &Arc<Config>,
 ) -> Result<Vec<Rhex>, anyhow::Error> {
     if first_time {
         println!("[📥:➡️🧬]=~= Getting head request...");
+        let _: ScopeQuery = rhex.payload_or_default()?;
         let mut keymaster = Keymaster::new();
         keymaster.load_keys(&config.hot_keys)?;
         let parent_scope = rhex.intent.scope.clone();
-        let scope_path = format!("{}/{}", config.fs_dir, parent_scope);
-
-        let mut dir_source = DirSource::new(PathBuf::from_str(&scope_path)?)?;
-        let mut latest: Option<Rhex> = None;
-
-        while let Some(rhex_item) = dir_source.next()? {
-            latest = Some(rhex_item);
-        }
 
-        if let Some(genesis) = latest {
+        if let Some(head) = store.head(&parent_scope)? {
             println!(
                 "Found head record for scope {}: {}",
                 parent_scope,
-                to_base64(&genesis.current_hash.clone().unwrap())
+                to_base64(&head)
             );
 
             // Intent: flip author/usher roles (response mode)
             let intent = Intent {
-                previous_hash: genesis.current_hash.clone(),
+                previous_hash: Some(head),
                 scope: parent_scope.clone(),
                 nonce: Intent::gen_nonce(),
                 author_pk: rhex.intent.usher_pk, // requester’s usher becomes author
                 usher_pk: rhex.intent.author_pk, // we usher on their behalf
                 record_type: "head".to_string(),
                 data: json!({
-                    "head": to_base64(&genesis.current_hash.unwrap()),
+                    "head": to_base64(&head),
                 }),
+                delegation: None,
             };
 
-            let clock = GTClock::new(0);
-            let context = Context::from_at(clock.now_micromarks_u64());
+            let context = Context::from_at(db::epoch::now_at(
+                cache,
+                &rhex.intent.scope,
+                config.clock.as_ref(),
+            )?);
             let signatures = vec![];
             let current_hash = None;
 
             let mut return_rhex =
                 crate::build::build_rhex(&intent, &context, &signatures, current_hash)?;
-            let key_bytes = keymaster.get_matching(&return_rhex.intent.author_pk)?;
-            let key = Key::from_bytes(key_bytes);
+            let key = keymaster.get_matching(&return_rhex.intent.author_pk)?;
             let signature = Signature {
                 sig_type: SigType::Author,
                 public_key: return_rhex.intent.author_pk,
@@ -110,19 +142,20 @@ pub fn request_head(
 pub fn request_scope(
     rhex: &Rhex,
     first_time: bool,
+    cache: &Cache,
     config: &Arc<Config>,
 ) -> Result<Vec<Rhex>, anyhow::Error> {
     if first_time {
         println!("[📥:🌐]=~= Getting scope request...");
+        let _: ScopeQuery = rhex.payload_or_default()?;
         let mut keymaster = Keymaster::new();
         keymaster.load_keys(&config.hot_keys)?;
         let parent_scope = rhex.intent.scope.clone();
 
-        let cache = hl_io::db::connect_db(&config.cache_db)?;
-        let mut scope_data = hl_io::db::scope::retrieve_scope(&cache, &parent_scope)?;
-        let ushers = hl_io::db::usher::get_ushers(&cache, &parent_scope)?;
-        let authorities = hl_io::db::authority::get_authorities(&cache, &parent_scope)?;
-        let policy = hl_io::db::policy::retrieve_policy(&cache, &parent_scope)?;
+        let mut scope_data = hl_io::db::scope::retrieve_scope(cache, &parent_scope)?;
+        let ushers = hl_io::db::usher::get_ushers(cache, &parent_scope)?;
+        let authorities = hl_io::db::authority::get_authorities(cache, &parent_scope)?;
+        let policy = hl_io::db::policy::retrieve_policy(cache, &parent_scope)?;
 
         scope_data.ushers = ushers;
         scope_data.authorities = authorities;
@@ -137,11 +170,15 @@ pub fn request_scope(
             usher_pk: rhex.intent.author_pk, // we usher on their behalf
             record_type: "scope".to_string(),
             data: serde_json::to_value(&scope_data)?,
+            delegation: None,
         };
-        return_rhex.context = Context::from_at(GTClock::new(0).now_micromarks_u64());
+        return_rhex.context = Context::from_at(db::epoch::now_at(
+            cache,
+            &rhex.intent.scope,
+            config.clock.as_ref(),
+        )?);
 
-        let key_bytes = keymaster.get_matching(&return_rhex.intent.author_pk)?;
-        let key = Key::from_bytes(key_bytes);
+        let key = keymaster.get_matching(&return_rhex.intent.author_pk)?;
         let signature = Signature {
             sig_type: SigType::Author,
             public_key: return_rhex.intent.author_pk,
@@ -154,3 +191,45 @@ pub fn request_scope(
         Ok(Vec::new())
     }
 }
+
+pub fn request_proof(
+    rhex: &Rhex,
+    first_time: bool,
+    cache: &Cache,
+    store: &dyn RhexStore,
+    config: &Arc<Config>,
+) -> Result<Vec<Rhex>, anyhow::Error> {
+    if first_time {
+        println!("[📥:🌳]=~= Getting inclusion proof...");
+        let request: ProofRequest = rhex.payload()?;
+        let (proof, checkpoint) = checkpoint::prove(store, &rhex.intent.scope, &request.hash)?;
+
+        let mut keymaster = Keymaster::new();
+        keymaster.load_keys(&config.hot_keys)?;
+        let mut return_rhex = Rhex::new();
+        return_rhex.intent = Intent {
+            previous_hash: rhex.current_hash,
+            scope: rhex.intent.scope.clone(),
+            nonce: Intent::gen_nonce(),
+            author_pk: rhex.intent.usher_pk, // requester’s usher becomes author
+            usher_pk: rhex.intent.author_pk, // we usher on their behalf
+            record_type: "proof".to_string(),
+            data: json!({
+                "proof": proof,
+                "checkpoint": to_base64(&checkpoint.into_cbor()?),
+            }),
+            delegation: None,
+        };
+        return_rhex.context = Context::from_at(db::epoch::now_at(
+            cache,
+            &rhex.intent.scope,
+            config.clock.as_ref(),
+        )?);
+
+        let key = keymaster.get_matching(&return_rhex.intent.author_pk)?;
+        return_rhex.sign_author(&key)?;
+        Ok(vec![return_rhex])
+    } else {
+        Ok(Vec::new())
+    }
+}
@@ -0,0 +1,35 @@
+use hl_core::{Rhex, schema::Schema};
+use hl_io::{
+    db::{self, Cache},
+    store::RhexStore,
+};
+
+pub fn process_schema(
+    rhex: &Rhex,
+    first_time: bool,
+    cache: &Cache,
+    store: &dyn RhexStore,
+) -> Result<Vec<Rhex>, anyhow::Error> {
+    match rhex.intent.record_type.as_str() {
+        "schema:define" => schema_define(rhex, first_time, cache, store),
+        _ => Err(anyhow::anyhow!(
+            "Unsupported record type for schema processing"
+        )),
+    }
+}
+
+pub fn schema_define(
+    rhex: &Rhex,
+    first_time: bool,
+    cache: &Cache,
+    store: &dyn RhexStore,
+) -> Result<Vec<Rhex>, anyhow::Error> {
+    print!("[📐:🟢]=~=");
+    let schema = Schema::from_define(&rhex.intent.data)?;
+    db::schema::store_schema(cache, &rhex.intent.scope, &schema)?;
+
+    if first_time {
+        store.append(rhex)?;
+    }
+    Ok(Vec::new())
+}
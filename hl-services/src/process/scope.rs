@@ -3,25 +3,33 @@ use std::sync::Arc;
 use hl_core::{
     Authority, Config, Key, Policy, Rhex,
     keymaster::keymaster::Keymaster,
-    policy::rule::Rule,
+    rhex::payload::ScopeGenesis,
     scope::scope::{Scope, ScopeRoles},
+    time::clock::GTClock,
+};
+use hl_io::{
+    db::{self, Cache},
+    store::RhexStore,
 };
-use hl_io::db::{self, connect_db};
-use rusqlite::Connection;
 
-use crate::process::scope_request::scope_request;
+use crate::{
+    checkpoint,
+    process::scope_request::{scope_create, scope_request},
+};
 
 pub fn process_scope(
     rhex: &Rhex,
     first_time: bool,
+    cache: &Cache,
+    store: &dyn RhexStore,
     config: &Arc<Config>,
     keymaster: &Keymaster,
 ) -> Result<Vec<Rhex>, anyhow::Error> {
-    let cache = connect_db(&config.cache_db)?;
     match rhex.intent.record_type.as_str() {
-        "scope:genesis" => scope_genesis(rhex, first_time, &cache),
-        "scope:request" => scope_request(rhex, first_time, config, keymaster),
-        "scope:create" => scope_create(rhex, first_time, config, keymaster),
+        "scope:genesis" => scope_genesis(rhex, first_time, cache, store),
+        "scope:request" => scope_request(rhex, first_time, cache, store, config, keymaster),
+        "scope:create" => scope_create(rhex, first_time, cache, store, config, keymaster),
+        "scope:checkpoint" => scope_checkpoint(rhex, first_time, store),
         _ => {
             return Err(anyhow::anyhow!(
                 "Unsupported record type for scope processing"
@@ -30,24 +38,17 @@ pub fn process_scope(
     }
 }
 
-fn scope_genesis(
+pub fn scope_genesis(
     rhex: &Rhex,
     first_time: bool,
-    cache: &Connection,
+    cache: &Cache,
+    store: &dyn RhexStore,
 ) -> Result<Vec<Rhex>, anyhow::Error> {
     // Ok, we have a genesis, which is our starting point of the scope.
     print!("[🌐:💡]=~=");
     // Flush the info we have for this scope
     db::scope::flush_scope_full(&cache, &rhex.intent.scope)?;
 
-    // Make the primary rule
-    let mut basic_rule = Rule::new(&rhex.intent.scope);
-    basic_rule.append_roles = vec!["authority".to_string()];
-    basic_rule.record_types = vec!["policy:set".to_string()];
-    basic_rule.quorum_k = 1;
-    basic_rule.quorum_roles = vec!["authority".to_string()];
-    basic_rule.rate_per_mark = 90;
-
     // Build the Authority
     let authority = Authority {
         scope: rhex.intent.scope.clone(),
@@ -65,41 +66,36 @@ fn scope_genesis(
             name: rhex.intent.scope.clone(),
             role: ScopeRoles::NoCache,
             last_synced: 0,
-            policy: Some(Policy {
-                scope: rhex.intent.scope.clone(),
-                quorum_ttl: 1_000_000_000,
-                eff: None,
-                exp: None,
-                note: None,
-                rules: vec![basic_rule],
-            }),
+            policy: Some(Policy::genesis(&rhex.intent.scope)),
             authorities: vec![authority],
             ushers: vec![],
         },
     )?;
-    if first_time {
-        // this is one of those records we don't do anything with
-        // on creation since technically this gets bundled with
-        // scope:request.
 
-        // For where this gets written to the fs, see scope:request
+    // GT for this scope counts from here. A child genesis without its own
+    // epoch keeps its parent's.
+    let genesis: ScopeGenesis = rhex.payload()?;
+    if genesis.unix_ms.is_some() || rhex.intent.scope.is_empty() {
+        db::epoch::store_epoch(cache, &rhex.intent.scope, &GTClock::from_genesis(&genesis))?;
+    }
+    if first_time {
+        // A child's genesis, ushered by whoever finalized its scope:create
+        store.append(rhex)?;
     }
     Ok(Vec::new())
 }
 
-pub fn scope_create(
+fn scope_checkpoint(
     rhex: &Rhex,
     first_time: bool,
-    config: &Arc<Config>,
-    keymaster: &Keymaster,
+    store: &dyn RhexStore,
 ) -> Result<Vec<Rhex>, anyhow::Error> {
-    print!("[🌐:🟢]=~=");
-    if !first_time {
-        // Prolly should load a scope here. Not sure yet.
-        let _ = rhex;
-        let _ = first_time;
-        let _ = config;
-        let _ = keymaster;
+    print!("[🌐:🌳]=~=");
+    if first_time {
+        // Only take a checkpoint whose root we can reproduce
+        let chain = store.chain(&rhex.intent.scope)?;
+        checkpoint::check_checkpoint(&chain, rhex)?;
+        store.append(rhex)?;
     }
     Ok(Vec::new())
 }
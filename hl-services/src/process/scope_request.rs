@@ -1,42 +1,50 @@
-use crate::{build::error::error_rhex, process::data::get_data_string};
-use ed25519_dalek::{SigningKey, ed25519::signature::SignerMut};
+use crate::{build::error::error_rhex, process::scope::scope_genesis, quorum};
+use anyhow::bail;
 use hl_core::{
-    Config, Context, Intent, Key, Rhex, Signature,
-    error::{E_FS_DIR_EXISTS, E_GENESIS_SELF_USHER_FORBIDDEN, E_REQUEST_DECODE, stack::ErrorStack},
+    Config, Context, Intent, Rhex, Signature,
+    error::{
+        E_FS_DIR_EXISTS, E_GENESIS_SELF_USHER_FORBIDDEN, E_PREVIOUS_NOT_FOUND, E_REQUEST_DECODE,
+        E_SCOPE_NAME_INVALID, stack::ErrorStack,
+    },
     from_base64,
     keymaster::keymaster::Keymaster,
-    rhex::{context, signature::SigType},
-    scope::scope::{Scope, ScopeRoles},
+    rhex::{
+        context,
+        payload::{ScopeCreate, ScopeGenesis, ScopeRequest},
+        signature::SigType,
+    },
+    scope::scope::{Scope, ScopeRoles, is_child_scope},
     time::clock::GTClock,
 };
 use hl_io::{
-    db::{self, connect_db, rhex::CacheSink},
-    fs::rhex::DirSink,
-    sink::RhexSink,
+    db::{self, Cache},
+    store::RhexStore,
 };
-use serde_json::json;
-use std::path::PathBuf;
-use std::str::FromStr;
 use std::sync::Arc;
 
 pub fn scope_request(
     rhex: &Rhex,
     first_time: bool,
+    cache: &Cache,
+    store: &dyn RhexStore,
     config: &Arc<Config>,
     keymaster: &Keymaster,
 ) -> Result<Vec<Rhex>, anyhow::Error> {
     print!("[🌐:📥]=~=");
     let mut error_stack = ErrorStack::new();
-    let cache = connect_db(&config.cache_db)?;
+    let mut gathering = Vec::new();
     if first_time {
         // First we need to make sure we specified the new scope
-        let new_scope = get_data_string(rhex, &vec!["ns".to_string(), "new_scope".to_string()])?;
+        let request: ScopeRequest = rhex.payload()?;
+        let new_scope = request.new_scope;
 
         // check to make sure genesis is attached in base64
         // and formatted correctly
-        let genesis = get_data_string(rhex, &vec!["g".to_string(), "genesis".to_string()])?;
-        let mut genesis = parse_genesis(&genesis)?;
-        let validated = validate_genesis(&genesis)?;
+        let Some(genesis) = request.genesis else {
+            bail!("{}: scope:request data.genesis: missing", E_REQUEST_DECODE);
+        };
+        let genesis = parse_genesis(&genesis)?;
+        let validated = validate_genesis(&genesis, &new_scope)?;
         if !validated {
             error_stack.codes.push(E_REQUEST_DECODE.to_string());
             error_stack
@@ -44,8 +52,17 @@ pub fn scope_request(
                 .push("Invalid genesis attached".to_string());
         }
 
+        // Lineage is read back off the name, so it must extend ours by one label
+        if !is_child_scope(&rhex.intent.scope, &new_scope) {
+            error_stack.codes.push(E_SCOPE_NAME_INVALID.to_string());
+            error_stack.messages.push(format!(
+                "{} is not a child of {:?}; name it <parent>.<label>",
+                new_scope, rhex.intent.scope
+            ));
+        }
+
         // Check to see if the scope exists
-        let exists = db::scope::scope_exists(&cache, &new_scope)?;
+        let exists = db::scope::scope_exists(cache, &new_scope)?;
         if exists {
             error_stack.codes.push(E_FS_DIR_EXISTS.to_string());
             error_stack
@@ -53,142 +70,59 @@ pub fn scope_request(
                 .push("Scope already exists".to_string());
         }
 
-        // Create dir
+        // We usher the genesis, so it comes to us signed by its author only
+        if genesis.signatures.len() > 1 {
+            error_stack
+                .codes
+                .push(E_GENESIS_SELF_USHER_FORBIDDEN.to_string());
+            error_stack.messages.push(
+                "Genesis cannot be signed by multiple parties including the requester".to_string(),
+            );
+        }
+        if keymaster.get_matching(&genesis.intent.usher_pk).is_err() {
+            error_stack.codes.push(E_REQUEST_DECODE.to_string());
+            error_stack.messages.push(
+                "Genesis usher key must match a key in the requester's keymaster".to_string(),
+            );
+        }
+
         if error_stack.codes.len() == 0 {
-            let parent_scope = format!("{}/{}", config.fs_dir, rhex.intent.scope);
-            let scope_path = format!("{}/{}", config.fs_dir, new_scope);
-            dbg!(&config.fs_dir, &new_scope, &scope_path);
-            match std::fs::create_dir_all(&scope_path) {
-                Ok(_) => {
-                    println!("ok");
-                }
-                Err(e) => {
-                    error_stack.codes.push(E_FS_DIR_EXISTS.to_string());
-                    error_stack
-                        .messages
-                        .push(format!("Failed to create scope dir: {}", e));
-                }
-            }
-            dbg!(&error_stack.codes);
-            if error_stack.codes.len() == 0 {
-                println!("Creating scope:create...");
-                let intent = Intent {
-                    previous_hash: rhex.current_hash,
-                    scope: rhex.intent.scope.clone(),
-                    nonce: Intent::gen_nonce(),
-                    author_pk: rhex.intent.usher_pk,
-                    usher_pk: rhex.intent.usher_pk,
-                    record_type: "scope:create".to_string(),
-                    data: json!({
-                        "new_scope": new_scope
-                    }),
-                };
-                let clock = GTClock::new(0);
-                let context = context::Context::from_at(clock.now_micromarks_u64());
-                let signatures = vec![];
-                let current_hash = None;
-                let mut create_rhex =
-                    crate::build::build_rhex(&intent, &context, &signatures, current_hash)?;
-                let key_bytes = keymaster.get_matching(&rhex.intent.usher_pk)?;
-                if key_bytes == [0u8; 32] {
-                    error_stack.codes.push(E_REQUEST_DECODE.to_string());
-                    error_stack
-                        .messages
-                        .push("Requester's keymaster does not have the usher key".to_string());
-                }
-                let mut signing_key = SigningKey::from_bytes(&key_bytes);
-                create_rhex.signatures.push(Signature {
-                    sig_type: SigType::Author,
-                    public_key: rhex.intent.usher_pk,
-                    sig: signing_key.sign(&create_rhex.author_hash()?).into(),
-                });
-                create_rhex.signatures.push(Signature {
-                    sig_type: SigType::Usher,
-                    public_key: rhex.intent.usher_pk,
-                    sig: signing_key
-                        .sign(&create_rhex.usher_hash(&create_rhex.signatures[0])?)
-                        .into(),
-                });
-                // FIXME: this should do a quorum lookup to see how many
-                // quorum sigs we need to get and then get them, but we
-                // are just gonna assume K=1 here too
-                create_rhex.signatures.push(Signature {
-                    sig_type: SigType::Quorum,
-                    public_key: rhex.intent.usher_pk,
-                    sig: signing_key
-                        .sign(
-                            &create_rhex.quorum_hash(
-                                &create_rhex.signatures[0],
-                                &create_rhex.signatures[1],
-                            )?,
-                        )
-                        .into(),
-                });
-                create_rhex.finalize()?;
-                println!("Writing to disk... {}", parent_scope);
-                let mut dir_sink = DirSink::new(PathBuf::from_str(&parent_scope)?);
-                dir_sink.send(&rhex)?;
-                dir_sink.send(&create_rhex)?;
-                let mut cache_sink = CacheSink::new(config.cache_db.clone());
-                cache_sink.send(&create_rhex)?;
-            }
+            println!("Writing to store... {:?}", rhex.intent.scope);
+            store.append(rhex)?;
 
-            // Tack on genesis
-            // Sign first - We can do that here without lookups
-            // because scope:genesis is always K=1
-            if genesis.signatures.len() > 1 {
-                error_stack
-                    .codes
-                    .push(E_GENESIS_SELF_USHER_FORBIDDEN.to_string());
-                error_stack.messages.push(
-                    "Genesis cannot be signed by multiple parties including the requester"
-                        .to_string(),
-                );
+            println!("Creating scope:create...");
+            let mut create_rhex = Rhex::new();
+            create_rhex.intent = Intent {
+                previous_hash: rhex.current_hash,
+                scope: rhex.intent.scope.clone(),
+                nonce: Intent::gen_nonce(),
+                author_pk: rhex.intent.usher_pk,
+                usher_pk: rhex.intent.usher_pk,
+                record_type: "scope:create".to_string(),
+                data: serde_json::to_value(ScopeCreate {
+                    new_scope: new_scope.clone(),
+                })?,
+                delegation: None,
+            };
+            let signing_key = keymaster.get_matching(&rhex.intent.usher_pk)?;
+            create_rhex.sign_author(&signing_key)?;
+            create_rhex.context = context::Context::from_at(db::epoch::now_at(
+                cache,
+                &rhex.intent.scope,
+                config.clock.as_ref(),
+            )?);
+            create_rhex.sign_usher(&signing_key)?;
+            // Short of quorum it goes back out to be gathered like any
+            // other record, and returns through scope:create finalized
+            if sign_quorum(&mut create_rhex, cache, keymaster)? {
+                gathering = scope_create(&create_rhex, true, cache, store, config, keymaster)?;
             } else {
-                // set up our signing key
-                let sk = keymaster.get_matching(&genesis.intent.usher_pk)?;
-                if sk != [0u8; 32] {
-                    let mut signing_key = SigningKey::from_bytes(&sk);
-
-                    genesis.add_context(&None)?;
-                    let usher_hash = genesis.usher_hash(&genesis.signatures[0])?;
-                    let usher_sig = Signature {
-                        sig_type: SigType::Usher,
-                        public_key: genesis.intent.usher_pk,
-                        sig: signing_key.sign(&usher_hash).into(),
-                    };
-                    genesis.signatures.push(usher_sig);
-                } else {
-                    error_stack.codes.push(E_REQUEST_DECODE.to_string());
-                    error_stack.messages.push(
-                        "Genesis usher key must match a key in the requester's keymaster"
-                            .to_string(),
-                    );
-                }
-                if error_stack.codes.len() == 0 {
-                    // Since genesis only has k=1 we can sign quorum and attach
-                    // as well
-                    let quorum_hash =
-                        genesis.quorum_hash(&genesis.signatures[0], &genesis.signatures[1])?;
-                    let mut quorum_key = SigningKey::from_bytes(&sk);
-                    let quorum_sig = Signature {
-                        sig_type: SigType::Quorum,
-                        public_key: genesis.intent.usher_pk,
-                        sig: quorum_key.sign(&quorum_hash).into(),
-                    };
-                    genesis.signatures.push(quorum_sig);
-                    // Append
-                    let mut dir_sink = DirSink::new(PathBuf::from_str(&scope_path)?);
-                    dir_sink.send(&genesis)?;
-                    let mut cache_sink = CacheSink::new(config.cache_db.clone());
-                    cache_sink.send(&genesis)?;
-                }
+                gathering.push(create_rhex);
             }
         }
 
-        let cache = connect_db(&config.cache_db)?;
         let status = db::scope::store_scope_full(
-            &cache,
+            cache,
             &Scope {
                 name: rhex.intent.scope.clone(),
                 role: ScopeRoles::Authority,
@@ -205,14 +139,6 @@ pub fn scope_request(
                 return Err(anyhow::anyhow!("Failed to store scope"));
             }
         }
-        let status = db::head::set_head(&cache, &rhex.intent.scope, &[0u8; 32]);
-        match status {
-            Ok(_) => {}
-            Err(e) => {
-                eprintln!("Error: {}", e);
-                return Err(anyhow::anyhow!("Failed to set head for scope"));
-            }
-        }
     } else {
         // Really do nothing because all the bootstrap stuff happens in scope:create
     }
@@ -227,7 +153,7 @@ pub fn scope_request(
         )?;
         Ok(vec![erhex])
     } else {
-        let signing_key = Key::from_bytes(keymaster.get_matching(&rhex.intent.usher_pk)?);
+        let signing_key = keymaster.get_matching(&rhex.intent.usher_pk)?;
         let intent = Intent {
             previous_hash: rhex.current_hash,
             scope: rhex.intent.scope.clone(),
@@ -235,12 +161,17 @@ pub fn scope_request(
             author_pk: rhex.intent.usher_pk,
             usher_pk: rhex.intent.author_pk,
             record_type: "scope:request".to_string(),
-            data: json!({
-                "new_scope": get_data_string(rhex, &vec!["ns".to_string(), "new_scope".to_string()])?,
-            }),
+            data: serde_json::to_value(ScopeRequest {
+                new_scope: rhex.payload::<ScopeRequest>()?.new_scope,
+                genesis: None,
+            })?,
+            delegation: None,
         };
-        let clock = GTClock::new(0);
-        let context = Context::from_at(clock.now_micromarks_u64());
+        let context = Context::from_at(db::epoch::now_at(
+            cache,
+            &rhex.intent.scope,
+            config.clock.as_ref(),
+        )?);
         let mut ok_rhex = Rhex::new();
         ok_rhex.intent = intent;
         ok_rhex.context = context;
@@ -251,29 +182,248 @@ pub fn scope_request(
             sig: signing_key.sign(&ok_rhex.author_hash()?)?,
         });
 
-        Ok(vec![ok_rhex])
+        let mut out = vec![ok_rhex];
+        out.append(&mut gathering);
+        Ok(out)
     };
 
     out_rhex
 }
 
+/// A finalized `scope:create` goes onto the parent's chain, then the
+/// genesis its request carried is ushered onto the child's. A replay
+/// leaves the genesis to the child's own chain.
+pub fn scope_create(
+    rhex: &Rhex,
+    first_time: bool,
+    cache: &Cache,
+    store: &dyn RhexStore,
+    config: &Arc<Config>,
+    keymaster: &Keymaster,
+) -> Result<Vec<Rhex>, anyhow::Error> {
+    print!("[🌐:🟢]=~=");
+    if !first_time {
+        return Ok(Vec::new());
+    }
+    store.append(rhex)?;
+
+    // The request it answers is the record before it
+    let request = match rhex.intent.previous_hash {
+        Some(previous) => store.get(&rhex.intent.scope, &previous)?,
+        None => None,
+    };
+    let Some(request) = request.filter(|r| r.intent.record_type == "scope:request") else {
+        bail!(
+            "{}: scope:create must follow its scope:request",
+            E_PREVIOUS_NOT_FOUND
+        );
+    };
+    let Some(genesis) = request.payload::<ScopeRequest>()?.genesis else {
+        bail!("{}: scope:request data.genesis: missing", E_REQUEST_DECODE);
+    };
+    let mut genesis = parse_genesis(&genesis)?;
+
+    let signing_key = keymaster.get_matching(&genesis.intent.usher_pk)?;
+    // The child's own epoch if it declared one
+    let gt = match genesis.payload::<ScopeGenesis>()?.unix_ms {
+        Some(_) => GTClock::from_genesis(&genesis.payload()?),
+        None => db::epoch::gt_clock(cache, &genesis.intent.scope)?,
+    };
+    genesis.add_context(&gt, config.clock.as_ref(), &None)?;
+    genesis.sign_usher(&signing_key)?;
+    if !sign_quorum(&mut genesis, cache, keymaster)? {
+        return Ok(vec![genesis]);
+    }
+    scope_genesis(&genesis, true, cache, store)
+}
+
+/// Add a quorum signature from a member key we hold, and finalize once
+/// no more are needed. `false` if the rest must be gathered from peers.
+fn sign_quorum(
+    rhex: &mut Rhex,
+    cache: &Cache,
+    keymaster: &Keymaster,
+) -> Result<bool, anyhow::Error> {
+    if let Some(member) = quorum::unsigned_member(cache, rhex, keymaster)? {
+        rhex.sign_quorum(&member)?;
+    }
+    if quorum::needed(cache, rhex)? > 0 {
+        return Ok(false);
+    }
+    rhex.finalize()?;
+    Ok(true)
+}
+
 fn parse_genesis(blob: &str) -> Result<Rhex, anyhow::Error> {
     let genesis_cbor = from_base64(&blob)?;
     let genesis = Rhex::from_cbor(&genesis_cbor)?;
     Ok(genesis)
 }
 
-fn validate_genesis(genesis: &Rhex) -> Result<bool, anyhow::Error> {
+fn validate_genesis(genesis: &Rhex, new_scope: &str) -> Result<bool, anyhow::Error> {
     // Ensure the genesis is actually a genesis
     if genesis.intent.record_type != "scope:genesis" {
         println!("Not a genesis record");
         return Ok(false);
     }
     // Ensure the genesis scope matches the request scope
-    let new_scope = genesis.intent.scope.clone();
-    if !genesis.intent.scope.starts_with(&new_scope) {
+    if genesis.intent.scope != new_scope {
         println!("Genesis scope does not match requested scope");
         return Ok(false);
     }
     Ok(true)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build, testing::Ledger};
+    use hl_core::to_base64;
+    use serde_json::json;
+
+    fn record(record_type: &str, data: serde_json::Value) -> Rhex {
+        let mut rhex = Rhex::new();
+        rhex.intent.record_type = record_type.to_string();
+        rhex.intent.data = data;
+        rhex
+    }
+
+    fn assert_accepted(out: &Result<Vec<Rhex>, anyhow::Error>) {
+        let out = out.as_ref().expect("processing failed");
+        let refused = out
+            .iter()
+            .find(|r| r.intent.record_type == "response:error");
+        assert!(refused.is_none(), "{:?}", refused.map(|r| &r.intent.data));
+    }
+
+    /// A root ledger where `scope:create` needs `create_k` quorum
+    /// signatures from its authorities.
+    fn ledger(name: &str, create_k: u16) -> Ledger {
+        let ledger = Ledger::new(name);
+        let rule = |types: &[&str], k: u16| {
+            json!({
+                "record_types": types,
+                "append_roles": ["authority"],
+                "quorum_k": k,
+                "quorum_roles": ["authority"],
+                "rate_per_mark": 1000,
+            })
+        };
+        let policy = record(
+            "policy:set",
+            json!({
+                "quorum_ttl": 1_000_000_000u64,
+                "rules": [
+                    rule(&["policy:set", "key:grant", "scope:request", "record:data"], 1),
+                    rule(&["scope:create"], create_k),
+                ],
+            }),
+        );
+        assert_accepted(&ledger.append(policy, &ledger.key));
+        ledger
+    }
+
+    /// Ask for `acme`, with a genesis signed by its author only for us
+    /// to usher.
+    fn request_acme(ledger: &Ledger) -> Result<Vec<Rhex>, anyhow::Error> {
+        let pk = ledger.key.pk.unwrap();
+        let mut genesis = record(
+            "scope:genesis",
+            json!({ "note": "acme", "public_key": to_base64(&pk) }),
+        );
+        genesis.intent.scope = "acme".to_string();
+        genesis.intent.nonce = Intent::gen_nonce();
+        genesis.intent.author_pk = pk;
+        genesis.intent.usher_pk = pk;
+        let genesis = build::author_sign(&genesis, &ledger.key).unwrap();
+        let request = record(
+            "scope:request",
+            json!({
+                "new_scope": "acme",
+                "genesis": to_base64(&genesis.into_cbor().unwrap()),
+            }),
+        );
+        ledger.append(request, &ledger.key)
+    }
+
+    fn record_types(chain: &[Rhex]) -> Vec<String> {
+        chain.iter().map(|r| r.intent.record_type.clone()).collect()
+    }
+
+    #[test]
+    fn parent_keeps_appending_after_a_scope_request() {
+        let ledger = ledger("scope-request", 1);
+        assert_accepted(&request_acme(&ledger));
+
+        // Request, then the scope:create chained onto it
+        let chain = ledger.store.chain("").unwrap();
+        let types = record_types(&chain);
+        assert_eq!(types[types.len() - 2..], ["scope:request", "scope:create"]);
+        let create = chain.last().unwrap();
+        assert_eq!(
+            db::head::get_head(&ledger.cache, "").ok(),
+            create.current_hash
+        );
+        let child = ledger.store.chain("acme").unwrap();
+        assert_eq!(child.len(), 1);
+        assert_eq!(
+            db::head::get_head(&ledger.cache, "acme").ok(),
+            child[0].current_hash
+        );
+
+        assert_accepted(&ledger.append(record("record:data", json!({ "n": 1 })), &ledger.key));
+        assert_eq!(ledger.store.chain("").unwrap().len(), chain.len() + 1);
+    }
+
+    #[test]
+    fn scope_create_gathers_k_of_n() {
+        let ledger = ledger("scope-create-quorum", 2);
+        let peer = hl_core::Key::generated();
+        let grant = record(
+            "key:grant",
+            json!({
+                "public_key": to_base64(&peer.pk.unwrap()),
+                "note": "peer usher",
+                "roles": ["authority"],
+                "effective_micromark": 0,
+                "expiration_micromark": u64::MAX,
+            }),
+        );
+        assert_accepted(&ledger.append(grant, &ledger.key));
+
+        // Our quorum signature isn't enough, so it goes out to be gathered
+        let out = request_acme(&ledger);
+        assert_accepted(&out);
+        let create = out
+            .unwrap()
+            .into_iter()
+            .find(|r| r.intent.record_type == "scope:create")
+            .expect("no scope:create to gather");
+        assert!(create.current_hash.is_none());
+        assert_eq!(quorum::needed(&ledger.cache, &create).unwrap(), 1);
+        let types = record_types(&ledger.store.chain("").unwrap());
+        assert_eq!(types.last().unwrap(), "scope:request");
+        assert!(ledger.store.chain("acme").unwrap().is_empty());
+
+        // The peer's signature comes back and the finalized record is
+        // appended, bringing the child's genesis with it
+        let create = build::quorum_sign(&create, &peer).unwrap();
+        let create = build::finalize(&create).unwrap();
+        assert_accepted(&crate::process::process_rhex(
+            &create,
+            true,
+            &ledger.cache,
+            &ledger.store,
+            &ledger.config,
+            &ledger.keymaster,
+        ));
+        let types = record_types(&ledger.store.chain("").unwrap());
+        assert_eq!(types.last().unwrap(), "scope:create");
+        let child = ledger.store.chain("acme").unwrap();
+        assert_eq!(record_types(&child), ["scope:genesis"]);
+        assert_eq!(
+            db::head::get_head(&ledger.cache, "acme").ok(),
+            child[0].current_hash
+        );
+    }
+}
@@ -0,0 +1,304 @@
+//! K-of-N quorum bookkeeping: who may sign a record's quorum, which
+//! ushers to ask, and how long they have. The network side lives in
+//! usherd.
+
+use hl_core::{
+    Key, Policy, Rhex, Usher,
+    authority::delegation::{self, DelegationError},
+    error,
+    keymaster::keymaster::Keymaster,
+    policy::rule::Rule,
+    rhex::signature::SigType,
+    time::clock::GTClock,
+    to_base64,
+};
+use hl_io::db;
+use hl_io::db::Cache;
+
+use crate::build;
+
+/// The rule governing `rhex`'s record type and its scope's `quorum_ttl`.
+/// Scopes without a policy get the genesis default: one authority.
+pub fn rule_for(cache: &Cache, rhex: &Rhex) -> Result<Option<(Rule, u64)>, anyhow::Error> {
+    let scope = &rhex.intent.scope;
+    let mut policy =
+        db::policy::retrieve_policy(cache, scope).unwrap_or_else(|_| Policy::pre_genesis(scope));
+    let rules = db::rule::get_rules(cache, scope)?;
+    if !rules.is_empty() {
+        policy.rules = rules;
+    }
+    let rule = policy.rules.into_iter().rfind(|rule| {
+        rule.applies_to(&rhex.intent.record_type) || rule.record_types == vec!["defaults"]
+    });
+    Ok(rule.map(|rule| (rule, policy.quorum_ttl)))
+}
+
+/// Keys holding one of `rule.quorum_roles` in the record's scope. A
+/// genesis comes before any authority, so its usher stands in.
+pub fn members(cache: &Cache, rhex: &Rhex, rule: &Rule) -> Result<Vec<[u8; 32]>, anyhow::Error> {
+    if rhex.intent.record_type == "scope:genesis" {
+        return Ok(vec![rhex.intent.usher_pk]);
+    }
+    let authorities = db::authority::get_authorities(cache, &rhex.intent.scope)?;
+    Ok(authorities
+        .into_iter()
+        .filter(|auth| rhex.context.at == 0 || auth.is_valid(rhex.context.at))
+        .filter(|auth| {
+            rule.quorum_roles
+                .iter()
+                .any(|role| auth.roles.contains(role))
+        })
+        .filter_map(|auth| auth.key.pk)
+        .collect())
+}
+
+/// The granted key quorum signer `pk` signs for: the root of a delegation
+/// chain the record carries, or else `pk` itself. A delegated key counts
+/// only under certificates that grant `quorum`, and never on a record it
+/// authored.
+pub fn signing_for(rhex: &Rhex, pk: &[u8; 32]) -> Result<[u8; 32], DelegationError> {
+    let Some(certs) = &rhex.intent.delegation else {
+        return Ok(*pk);
+    };
+    let root = delegation::resolve_quorum(
+        certs,
+        pk,
+        &rhex.intent.scope,
+        &rhex.intent.record_type,
+        rhex.context.at,
+    )?;
+    match root {
+        Some(_) if pk == &rhex.intent.author_pk => Err(DelegationError::new(
+            error::E_QUORUM_INVALID_MEMBER,
+            format!(
+                "delegated key {} cannot sign quorum on its own record",
+                to_base64(pk)
+            ),
+        )),
+        root => Ok(root.unwrap_or(*pk)),
+    }
+}
+
+/// Quorum signatures on `rhex` so far.
+pub fn count(rhex: &Rhex) -> usize {
+    rhex.signatures
+        .iter()
+        .filter(|sig| sig.sig_type == SigType::Quorum)
+        .count()
+}
+
+fn has_signed(rhex: &Rhex, pk: &[u8; 32]) -> bool {
+    rhex.signatures
+        .iter()
+        .any(|sig| sig.sig_type == SigType::Quorum && &sig.public_key == pk)
+}
+
+/// Quorum signatures `rhex` still needs before it can be finalized.
+pub fn needed(cache: &Cache, rhex: &Rhex) -> Result<usize, anyhow::Error> {
+    let Some((rule, _)) = rule_for(cache, rhex)? else {
+        return Ok(0);
+    };
+    Ok((rule.quorum_k as usize).saturating_sub(count(rhex)))
+}
+
+/// A quorum member we hold a key for that hasn't signed `rhex` yet.
+pub fn unsigned_member(
+    cache: &Cache,
+    rhex: &Rhex,
+    keymaster: &Keymaster,
+) -> Result<Option<Key>, anyhow::Error> {
+    let Some((rule, _)) = rule_for(cache, rhex)? else {
+        return Ok(None);
+    };
+    Ok(members(cache, rhex, &rule)?
+        .iter()
+        .filter(|pk| !has_signed(rhex, pk))
+        .find_map(|pk| keymaster.get_matching(pk).ok()))
+}
+
+/// Ushers of the scope that are quorum members, haven't signed, and
+/// aren't us.
+pub fn peers(
+    cache: &Cache,
+    rhex: &Rhex,
+    keymaster: &Keymaster,
+) -> Result<Vec<Usher>, anyhow::Error> {
+    let Some((rule, _)) = rule_for(cache, rhex)? else {
+        return Ok(Vec::new());
+    };
+    let members = members(cache, rhex, &rule)?;
+    Ok(db::usher::get_ushers(cache, &rhex.intent.scope)?
+        .into_iter()
+        .filter(|usher| members.contains(&usher.public_key))
+        .filter(|usher| !has_signed(rhex, &usher.public_key))
+        .filter(|usher| keymaster.get_matching(&usher.public_key).is_err())
+        .collect())
+}
+
+/// Unix ms after which `rhex` can no longer gather quorum: `context.at`
+/// plus the policy's `quorum_ttl`.
+pub fn deadline_ms(cache: &Cache, rhex: &Rhex) -> Result<i128, anyhow::Error> {
+    let ttl = rule_for(cache, rhex)?.map(|(_, ttl)| ttl).unwrap_or(0);
+    let clock: GTClock = db::epoch::gt_clock(cache, &rhex.intent.scope)?;
+    Ok(clock.time_at_micromarks(rhex.context.at as i128 + ttl as i128))
+}
+
+/// Copy over quorum signatures from `reply` that verify against `rhex`
+/// and come from members. Returns how many were added.
+pub fn merge(cache: &Cache, rhex: &mut Rhex, reply: &Rhex) -> Result<usize, anyhow::Error> {
+    let Some((rule, _)) = rule_for(cache, rhex)? else {
+        return Ok(0);
+    };
+    let members = members(cache, rhex, &rule)?;
+    let mut added = 0;
+    for sig in reply.signatures.iter() {
+        if sig.sig_type != SigType::Quorum || has_signed(rhex, &sig.public_key) {
+            continue;
+        }
+        match signing_for(rhex, &sig.public_key) {
+            Ok(member) if members.contains(&member) => {}
+            _ => continue,
+        }
+        let valid = rhex
+            .verify_signature(sig)
+            .is_ok_and(|check| check.error_code().is_none());
+        if valid && rhex.add_signature(sig.clone()).is_ok() {
+            added += 1;
+        }
+    }
+    Ok(added)
+}
+
+/// The `response:error` sent back when `rhex` ran out of time with
+/// `have` of `k` quorum signatures.
+pub fn insufficient(rhex: &Rhex, have: usize, k: usize) -> Result<Rhex, anyhow::Error> {
+    build::error::error_rhex(
+        &rhex.intent.scope,
+        rhex.intent.usher_pk,
+        rhex.intent.author_pk,
+        &vec![error::E_QUORUM_INSUFFICIENT.to_string()],
+        &format!(
+            "Quorum not met within quorum_ttl. Expected at least {} signatures, but got {}",
+            k, have
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hl_core::{Authority, authority::delegation::Delegation};
+
+    /// A root scope whose `record:data` needs two of `members`.
+    fn two_of(members: &[&Key]) -> Cache {
+        let cache = Cache::open_in_memory().unwrap();
+        db::migrate::migrate(&cache).unwrap();
+        for member in members {
+            let mut authority = Authority::new();
+            authority.key = Key::from_pk_bytes(member.pk.unwrap());
+            authority.roles = vec!["authority".to_string()];
+            db::authority::store_authority(&cache, "", &authority).unwrap();
+        }
+        let mut rule = Rule::new("");
+        rule.record_types = vec!["record:data".to_string()];
+        rule.append_roles = vec!["authority".to_string()];
+        rule.quorum_k = 2;
+        rule.quorum_roles = vec!["authority".to_string()];
+        db::rule::store_rule(&cache, "", &rule).unwrap();
+        cache
+    }
+
+    fn usher_signed(usher: &Key) -> Rhex {
+        let mut rhex = Rhex::new();
+        rhex.intent.record_type = "record:data".to_string();
+        rhex.intent.author_pk = usher.pk.unwrap();
+        rhex.intent.usher_pk = usher.pk.unwrap();
+        let rhex = build::author_sign(&rhex, usher).unwrap();
+        build::usher_sign(&rhex, usher).unwrap()
+    }
+
+    #[test]
+    fn needed_counts_down_to_k() {
+        let (a, b) = (Key::generated(), Key::generated());
+        let cache = two_of(&[&a, &b]);
+        let rhex = usher_signed(&a);
+        assert_eq!(needed(&cache, &rhex).unwrap(), 2);
+        let rhex = build::quorum_sign(&rhex, &a).unwrap();
+        assert_eq!(needed(&cache, &rhex).unwrap(), 1);
+        let rhex = build::quorum_sign(&rhex, &b).unwrap();
+        assert_eq!(needed(&cache, &rhex).unwrap(), 0);
+
+        // No rule covers it, so nothing is needed
+        let mut other = usher_signed(&a);
+        other.intent.record_type = "frame:define".to_string();
+        assert_eq!(needed(&cache, &other).unwrap(), 0);
+    }
+
+    #[test]
+    fn merge_takes_only_valid_member_signatures() {
+        let (a, b, outsider) = (Key::generated(), Key::generated(), Key::generated());
+        let cache = two_of(&[&a, &b]);
+        let mut rhex = build::quorum_sign(&usher_signed(&a), &a).unwrap();
+
+        let stranger = build::quorum_sign(&rhex, &outsider).unwrap();
+        assert_eq!(merge(&cache, &mut rhex, &stranger).unwrap(), 0);
+
+        let mut forged = build::quorum_sign(&rhex, &b).unwrap();
+        forged.signatures[3].sig[0] ^= 1;
+        assert_eq!(merge(&cache, &mut rhex, &forged).unwrap(), 0);
+        assert_eq!(count(&rhex), 1);
+
+        let signed = build::quorum_sign(&rhex, &b).unwrap();
+        assert_eq!(merge(&cache, &mut rhex, &signed).unwrap(), 1);
+        // Already have it
+        assert_eq!(merge(&cache, &mut rhex, &signed).unwrap(), 0);
+        assert_eq!(needed(&cache, &rhex).unwrap(), 0);
+    }
+
+    #[test]
+    fn delegated_quorum_signers_need_the_grant_and_not_their_own_record() {
+        let (root, device, usher) = (Key::generated(), Key::generated(), Key::generated());
+        let device_pk = device.pk.unwrap();
+        let cert = |quorum: bool| {
+            let mut cert = Delegation::new(device_pk, "", vec!["record:data".to_string()], 100);
+            cert.quorum = quorum;
+            cert.sign(&root).unwrap();
+            cert
+        };
+        let mut rhex = usher_signed(&usher);
+        rhex.intent.delegation = Some(vec![cert(false)]);
+        let err = signing_for(&rhex, &device_pk).unwrap_err();
+        assert_eq!(err.code, error::E_DELEGATION_INVALID);
+
+        rhex.intent.delegation = Some(vec![cert(true)]);
+        assert_eq!(signing_for(&rhex, &device_pk).unwrap(), root.pk.unwrap());
+        assert_eq!(
+            signing_for(&rhex, &usher.pk.unwrap()).unwrap(),
+            usher.pk.unwrap()
+        );
+
+        // Its own record
+        rhex.intent.author_pk = device_pk;
+        let err = signing_for(&rhex, &device_pk).unwrap_err();
+        assert_eq!(err.code, error::E_QUORUM_INVALID_MEMBER);
+    }
+
+    #[test]
+    fn deadline_is_at_plus_quorum_ttl() {
+        let a = Key::generated();
+        let cache = two_of(&[&a]);
+        let clock = GTClock::new(1_700_000_000_000);
+        db::epoch::store_epoch(&cache, "", &clock).unwrap();
+        let mut policy = Policy::new();
+        policy.quorum_ttl = 5_000;
+        db::policy::store_policy(&cache, "", &policy).unwrap();
+
+        let mut rhex = usher_signed(&a);
+        rhex.context.at = 20_000;
+        assert_eq!(
+            deadline_ms(&cache, &rhex).unwrap(),
+            clock.time_at_micromarks(25_000)
+        );
+        assert!(deadline_ms(&cache, &rhex).unwrap() > clock.time_at_micromarks(20_000));
+    }
+}
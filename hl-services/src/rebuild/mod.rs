@@ -0,0 +1,288 @@
+//! Rebuild the cache from the record store.
+//!
+//! Lists every scope in the store and replays each chain, parents
+//! before children, through the processor with `first_time = false`. A
+//! record the processor refuses ends its scope's replay, since everything
+//! after it chains off it.
+
+use std::sync::Arc;
+
+use hl_core::{
+    Config, Rhex, keymaster::keymaster::Keymaster, rhex::batch::verify_records,
+    scope::scope::scope_lineage, to_base64,
+};
+use hl_io::{
+    db::{self, Cache},
+    store::RhexStore,
+};
+
+use crate::process;
+
+/// How far one scope's chain got.
+#[derive(Debug, Clone)]
+pub struct ScopeSummary {
+    pub scope: String,
+    /// Records read from the store.
+    pub records: usize,
+    pub replayed: usize,
+    pub head: Option<[u8; 32]>,
+    /// The stored chain could not be read.
+    pub source_error: Option<String>,
+}
+
+/// A record the processor would not take back.
+#[derive(Debug, Clone)]
+pub struct Refused {
+    pub scope: String,
+    pub index: usize,
+    pub record_type: String,
+    pub current_hash: Option<[u8; 32]>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RebuildReport {
+    pub scopes: Vec<ScopeSummary>,
+    pub refused: Vec<Refused>,
+}
+
+impl RebuildReport {
+    pub fn is_clean(&self) -> bool {
+        self.refused.is_empty() && self.scopes.iter().all(|s| s.source_error.is_none())
+    }
+
+    pub fn print(&self) {
+        println!("Rebuilt {} scopes:", self.scopes.len());
+        for summary in self.scopes.iter() {
+            let head = summary
+                .head
+                .map(|h| to_base64(&h))
+                .unwrap_or_else(|| "<no head>".to_string());
+            println!(
+                "  🌐 {:?}: {}/{} replayed, head {}",
+                summary.scope, summary.replayed, summary.records, head
+            );
+            if let Some(e) = &summary.source_error {
+                println!("    - stopped reading: {}", e);
+            }
+        }
+        if self.refused.is_empty() {
+            return;
+        }
+        println!("Refused {} records:", self.refused.len());
+        for refused in self.refused.iter() {
+            let hash = refused
+                .current_hash
+                .map(|h| to_base64(&h))
+                .unwrap_or_else(|| "<no current_hash>".to_string());
+            println!(
+                "  ❌ {:?} #{} {} {}",
+                refused.scope, refused.index, refused.record_type, hash
+            );
+            println!("    - {}", refused.reason);
+        }
+    }
+}
+
+/// Replay every scope in `store` into `cache`, which should start out
+/// empty.
+pub fn rebuild(
+    cache: &Cache,
+    store: &dyn RhexStore,
+    config: &Arc<Config>,
+    keymaster: &Keymaster,
+) -> Result<RebuildReport, anyhow::Error> {
+    let mut report = RebuildReport::default();
+    let mut scopes = store.scopes()?;
+    // Parents first, so a child's genesis finds its scope:create
+    scopes.sort_by(|a, b| {
+        scope_lineage(a)
+            .len()
+            .cmp(&scope_lineage(b).len())
+            .then_with(|| a.cmp(b))
+    });
+    for scope in scopes {
+        let summary = replay_scope(scope, cache, store, config, keymaster, &mut report.refused)?;
+        report.scopes.push(summary);
+    }
+    Ok(report)
+}
+
+fn replay_scope(
+    scope: String,
+    cache: &Cache,
+    store: &dyn RhexStore,
+    config: &Arc<Config>,
+    keymaster: &Keymaster,
+    refused: &mut Vec<Refused>,
+) -> Result<ScopeSummary, anyhow::Error> {
+    let mut summary = ScopeSummary {
+        scope,
+        records: 0,
+        replayed: 0,
+        head: None,
+        source_error: None,
+    };
+    let records = store.chain(&summary.scope).unwrap_or_else(|e| {
+        summary.source_error = Some(format!("{:#}", e));
+        Vec::new()
+    });
+    summary.records = records.len();
+
+    // Signatures in one batch; a record with a bad one goes through the
+    // full checks, which refuse it.
+    let sigs = verify_records(&records);
+    for (index, rhex) in records.iter().enumerate() {
+        let scope = &rhex.intent.scope;
+        if db::head::get_head(cache, scope).is_err() {
+            db::head::set_head(cache, scope, &[0u8; 32])?;
+        }
+        let output = if sigs.record_ok(index) {
+            process::replay_verified(rhex, cache, store, config, keymaster)
+        } else {
+            process::process_rhex(rhex, false, cache, store, config, keymaster)
+        };
+
+        // The processor advances the head for each record it takes
+        let head = db::head::get_head(cache, scope).ok();
+        let reason = match output {
+            Err(e) => Some(format!("{:#}", e)),
+            Ok(_) if rhex.current_hash.is_none() => Some("not finalized".to_string()),
+            Ok(output) if head != rhex.current_hash => Some(refusal(&output)),
+            Ok(_) => None,
+        };
+        if let Some(reason) = reason {
+            refused.push(Refused {
+                scope: scope.clone(),
+                index,
+                record_type: rhex.intent.record_type.clone(),
+                current_hash: rhex.current_hash,
+                reason,
+            });
+            break;
+        }
+        summary.replayed += 1;
+        summary.head = head;
+    }
+    Ok(summary)
+}
+
+/// What the processor said about a record it didn't take.
+fn refusal(output: &[Rhex]) -> String {
+    output
+        .iter()
+        .find(|rhex| rhex.intent.record_type == "response:error")
+        .map(|error| {
+            let data = &error.intent.data;
+            let message = data["message"].as_str().unwrap_or_default();
+            match data["type"].as_array() {
+                Some(codes) => {
+                    let codes: Vec<&str> = codes.iter().filter_map(|c| c.as_str()).collect();
+                    format!("{}: {}", codes.join(", "), message)
+                }
+                None => message.to_string(),
+            }
+        })
+        .unwrap_or_else(|| "processor failed".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Ledger;
+    use hl_io::store::RhexStore;
+    use serde_json::json;
+
+    fn policy_after(ledger: &Ledger, previous: &Rhex, note: &str) -> Rhex {
+        let pk = ledger.key.pk.unwrap();
+        let mut rhex = Rhex::new();
+        rhex.intent.scope = previous.intent.scope.clone();
+        rhex.intent.previous_hash = previous.current_hash;
+        rhex.intent.nonce = hl_core::Intent::gen_nonce();
+        rhex.intent.author_pk = pk;
+        rhex.intent.usher_pk = pk;
+        rhex.intent.record_type = "policy:set".to_string();
+        rhex.intent.data = json!({
+            "note": note,
+            "quorum_ttl": 1_000_000_000u64,
+            "rules": [{
+                "record_types": ["policy:set"],
+                "append_roles": ["authority"],
+                "quorum_k": 1,
+                "quorum_roles": ["authority"],
+                "rate_per_mark": 1000,
+            }],
+        });
+        rhex.context.at = ledger.now_at("");
+        ledger.finalize(rhex)
+    }
+
+    /// A root chain of two and an `acme` chain of three, in the store.
+    fn two_scopes(ledger: &Ledger, tamper: bool) -> Vec<Rhex> {
+        let root = ledger.store.chain("").unwrap();
+        let root_policy = policy_after(ledger, &root[0], "root");
+        let genesis = ledger.genesis("acme");
+        let mut first = policy_after(ledger, &genesis, "first");
+        let second = policy_after(ledger, &first, "second");
+        if tamper {
+            first.intent.data["note"] = json!("forged");
+        }
+        let records = vec![root_policy, genesis, first, second];
+        for rhex in records.iter() {
+            ledger.store.append(rhex).unwrap();
+        }
+        records
+    }
+
+    fn fresh_cache() -> Cache {
+        let cache = Cache::open_in_memory().unwrap();
+        db::migrate::migrate(&cache).unwrap();
+        cache
+    }
+
+    fn rebuild_fresh(ledger: &Ledger, cache: &Cache) -> RebuildReport {
+        rebuild(cache, &ledger.store, &ledger.config, &ledger.keymaster).unwrap()
+    }
+
+    #[test]
+    fn replays_parent_then_child() {
+        let ledger = Ledger::new("rebuild");
+        let records = two_scopes(&ledger, false);
+        let cache = fresh_cache();
+        let report = rebuild_fresh(&ledger, &cache);
+
+        assert!(report.is_clean(), "{:?}", report);
+        let scopes: Vec<_> = report.scopes.iter().map(|s| s.scope.as_str()).collect();
+        assert_eq!(scopes, ["", "acme"]);
+        assert_eq!(report.scopes[0].replayed, 2);
+        assert_eq!(report.scopes[1].replayed, 3);
+        assert_eq!(db::head::get_head(&cache, "").ok(), records[0].current_hash);
+        assert_eq!(
+            db::head::get_head(&cache, "acme").ok(),
+            records[3].current_hash
+        );
+    }
+
+    #[test]
+    fn tampered_record_stops_its_scope() {
+        let ledger = Ledger::new("rebuild-tampered");
+        let records = two_scopes(&ledger, true);
+        let cache = fresh_cache();
+        let report = rebuild_fresh(&ledger, &cache);
+
+        assert!(!report.is_clean());
+        assert_eq!(report.refused.len(), 1);
+        let refused = &report.refused[0];
+        assert_eq!((refused.scope.as_str(), refused.index), ("acme", 1));
+        assert_eq!(refused.current_hash, records[2].current_hash);
+
+        // The root is untouched; acme stops at its genesis
+        assert_eq!(report.scopes[0].replayed, 2);
+        assert_eq!(report.scopes[1].records, 3);
+        assert_eq!(report.scopes[1].replayed, 1);
+        assert_eq!(
+            db::head::get_head(&cache, "acme").ok(),
+            records[1].current_hash
+        );
+    }
+}
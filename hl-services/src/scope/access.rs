@@ -1,8 +1,8 @@
 use hl_io::db;
-use rusqlite::Connection;
+use hl_io::db::Cache;
 
 pub fn can_access(
-    cache: &Connection,
+    cache: &Cache,
     scope: &str,
     key: &[u8; 32],
     record_type: &str,
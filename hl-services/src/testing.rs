@@ -0,0 +1,127 @@
+//! A fresh root ledger for tests: one genesis in a `MemoryStore`,
+//! replayed into an in-memory cache.
+
+use std::{path::PathBuf, sync::Arc};
+
+use hl_core::{Config, Key, Rhex, keymaster::keymaster::Keymaster, to_base64};
+use hl_io::{
+    db::{self, Cache},
+    store::{MemoryStore, RhexStore},
+};
+use serde_json::json;
+
+use crate::{build, process, rebuild};
+
+pub struct Ledger {
+    pub cache: Cache,
+    pub store: MemoryStore,
+    pub config: Arc<Config>,
+    pub keymaster: Keymaster,
+    /// Genesis author, usher and only authority.
+    pub key: Key,
+    dir: PathBuf,
+}
+
+impl Ledger {
+    /// `name` keeps the journal dir apart from other tests'.
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("hl-services-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut config = Config::new();
+        config.journal_dir = dir.to_string_lossy().into_owned();
+        config.store = "memory".to_string();
+
+        let key = Key::generated();
+        let mut keymaster = Keymaster::new();
+        keymaster.load_keys(std::slice::from_ref(&key)).unwrap();
+        keymaster.set_primary_key(&key.pk.unwrap()).unwrap();
+
+        let cache = Cache::open_in_memory().unwrap();
+        db::migrate::migrate(&cache).unwrap();
+        let ledger = Self {
+            cache,
+            store: MemoryStore::new(),
+            config: Arc::new(config),
+            keymaster,
+            key,
+            dir,
+        };
+        let genesis = ledger.genesis("");
+        ledger.store.append(&genesis).unwrap();
+        let report = ledger.rebuild();
+        assert!(report.is_clean(), "{:?}", report);
+        ledger
+    }
+
+    /// A finalized `scope:genesis` for `scope` by our key. Needs the root
+    /// replayed first unless `scope` is the root.
+    pub fn genesis(&self, scope: &str) -> Rhex {
+        let pk = self.key.pk.unwrap();
+        let mut rhex = Rhex::new();
+        rhex.intent.scope = scope.to_string();
+        rhex.intent.nonce = hl_core::Intent::gen_nonce();
+        rhex.intent.author_pk = pk;
+        rhex.intent.usher_pk = pk;
+        rhex.intent.record_type = "scope:genesis".to_string();
+        rhex.intent.data = json!({
+            "note": "test",
+            "unix_ms": self.config.clock.now_unix_ms() as u64 - 60_000,
+            "public_key": to_base64(&pk),
+        });
+        // Stamped by the parent's usher, as `scope:request` does
+        if !scope.is_empty() {
+            rhex.context.at = self.now_at(scope);
+        }
+        self.finalize(rhex)
+    }
+
+    /// Author, usher and quorum sign with our key, then finalize.
+    pub fn finalize(&self, rhex: Rhex) -> Rhex {
+        let rhex = build::author_sign(&rhex, &self.key).unwrap();
+        let rhex = build::usher_sign(&rhex, &self.key).unwrap();
+        let rhex = build::quorum_sign(&rhex, &self.key).unwrap();
+        build::finalize(&rhex).unwrap()
+    }
+
+    /// Chain `rhex` onto its scope's head, authored by `author` and
+    /// ushered and quorum signed by us, and process it as a first-time
+    /// append. `context.at` defaults to now.
+    pub fn append(&self, mut rhex: Rhex, author: &Key) -> Result<Vec<Rhex>, anyhow::Error> {
+        let scope = rhex.intent.scope.clone();
+        rhex.intent.previous_hash = self.store.head(&scope).unwrap();
+        rhex.intent.nonce = hl_core::Intent::gen_nonce();
+        rhex.intent.author_pk = author.pk.unwrap();
+        rhex.intent.usher_pk = self.key.pk.unwrap();
+        if rhex.context.at == 0 {
+            rhex.context.at = self.now_at(&scope);
+        }
+        let rhex = build::author_sign(&rhex, author).unwrap();
+        let rhex = build::usher_sign(&rhex, &self.key).unwrap();
+        let rhex = build::quorum_sign(&rhex, &self.key).unwrap();
+        let rhex = build::finalize(&rhex).unwrap();
+        process::process_rhex(
+            &rhex,
+            true,
+            &self.cache,
+            &self.store,
+            &self.config,
+            &self.keymaster,
+        )
+    }
+
+    pub fn now_at(&self, scope: &str) -> u64 {
+        db::epoch::now_at(&self.cache, scope, self.config.clock.as_ref()).unwrap()
+    }
+
+    /// Replay the store into our cache.
+    pub fn rebuild(&self) -> rebuild::RebuildReport {
+        rebuild::rebuild(&self.cache, &self.store, &self.config, &self.keymaster).unwrap()
+    }
+}
+
+impl Drop for Ledger {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
@@ -17,18 +17,58 @@ pub enum Commands {
     Generate(GenerateArgs),
     View(ViewArgs),
     Base64(B64Args),
+    /// Upgrade an HKYV1 key file to HKYV2 in place
+    Migrate(MigrateArgs),
+    /// Split a key into M-of-N Shamir shares, one paper file each
+    Split(SplitArgs),
+    /// Rebuild a key from paper shares
+    Combine(CombineArgs),
+    /// Write a key out as a checksummed paper backup
+    PaperExport(PaperExportArgs),
+    /// Restore a key from a paper backup
+    PaperImport(PaperImportArgs),
+    /// Issue a delegation certificate to a scoped sub-key
+    Delegate(DelegateArgs),
+    /// Re-encrypt a key file under a new password
+    Rekey(RekeyArgs),
 }
 
 #[derive(Args, Debug)]
 pub struct KeyOpts {
     #[arg(short, long)]
-    pub keyfile: String,
-    #[arg(short, long)]
-    pub password: Option<String>,
+    pub keyfile: Option<String>,
+    #[command(flatten)]
+    pub password: PasswordOpts,
     #[arg(long)]
     pub hot: bool,
 }
 
+// Where a key's password comes from. With none of these given it is
+// asked for on the terminal without echo.
+#[derive(Args, Debug)]
+pub struct PasswordOpts {
+    /// Visible in shell history and `ps`; prefer the options below
+    #[arg(short, long)]
+    pub password: Option<String>,
+    /// Read the password from this environment variable
+    #[arg(long, value_name = "VAR")]
+    pub password_env: Option<String>,
+    /// Read the password's first line from this open file descriptor
+    #[arg(long, value_name = "FD")]
+    pub password_fd: Option<u32>,
+    /// Read the password's first line from this file
+    #[arg(long, value_name = "FILE")]
+    pub password_file: Option<String>,
+}
+
+impl KeyOpts {
+    pub fn keyfile(&self) -> Result<&str, anyhow::Error> {
+        self.keyfile
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("--keyfile required"))
+    }
+}
+
 #[derive(Args, Debug)]
 pub struct SignArgs {
     #[command(flatten)]
@@ -42,6 +82,18 @@ pub struct SignArgs {
 
     #[arg(short, long, value_name = "DIR")]
     pub output: String,
+
+    /// Sign through the hl-agent on this socket instead of a key file
+    #[arg(long, value_name = "SOCKET", conflicts_with_all = ["keyfile", "hot", "password"])]
+    pub agent: Option<String>,
+
+    /// Which of the agent's keys to sign with; needed if it holds several
+    #[arg(long, value_name = "B64", requires = "agent")]
+    pub pk: Option<String>,
+
+    /// Ledger epoch (the genesis `unix_ms`); required to stamp usher context
+    #[arg(long, value_name = "MS")]
+    pub epoch_ms: Option<i128>,
 }
 
 #[derive(Args, Debug)]
@@ -50,15 +102,139 @@ pub struct VerifyArgs {
     pub input: String,
 }
 
+/// Metadata stored alongside an encrypted key
+#[derive(Args, Debug)]
+pub struct MetaOpts {
+    #[arg(long)]
+    pub label: Option<String>,
+    /// Scope the key is meant for
+    #[arg(long)]
+    pub scope: Option<String>,
+    /// Role the key is meant to hold; repeatable
+    #[arg(long = "role")]
+    pub roles: Vec<String>,
+}
+
 #[derive(Args, Debug)]
 pub struct GenerateArgs {
     #[command(flatten)]
     pub key: KeyOpts,
 
+    #[command(flatten)]
+    pub meta: MetaOpts,
+
     #[arg(long)]
     pub show_sk: bool,
 }
 
+#[derive(Args, Debug)]
+pub struct MigrateArgs {
+    #[command(flatten)]
+    pub key: KeyOpts,
+
+    #[command(flatten)]
+    pub meta: MetaOpts,
+}
+
+#[derive(Args, Debug)]
+pub struct RekeyArgs {
+    #[command(flatten)]
+    pub key: KeyOpts,
+
+    /// The new password: from this environment variable
+    #[arg(long, value_name = "VAR")]
+    pub new_password_env: Option<String>,
+    /// The new password: first line of this open file descriptor
+    #[arg(long, value_name = "FD")]
+    pub new_password_fd: Option<u32>,
+    /// The new password: first line of this file
+    #[arg(long, value_name = "FILE")]
+    pub new_password_file: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct SplitArgs {
+    #[command(flatten)]
+    pub key: KeyOpts,
+
+    /// Shares needed to rebuild the key
+    #[arg(short = 'm', long)]
+    pub threshold: u8,
+
+    /// Shares to write
+    #[arg(short = 'n', long)]
+    pub shares: u8,
+
+    #[arg(short, long, value_name = "DIR")]
+    pub output: String,
+}
+
+#[derive(Args, Debug)]
+pub struct CombineArgs {
+    /// Where to write the rebuilt key
+    #[command(flatten)]
+    pub key: KeyOpts,
+
+    #[command(flatten)]
+    pub meta: MetaOpts,
+
+    /// A share paper; repeat for each share
+    #[arg(long = "share", value_name = "FILE", required = true)]
+    pub shares: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct PaperExportArgs {
+    #[command(flatten)]
+    pub key: KeyOpts,
+
+    /// Write here instead of printing
+    #[arg(short, long)]
+    pub output: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct PaperImportArgs {
+    /// Where to write the restored key
+    #[command(flatten)]
+    pub key: KeyOpts,
+
+    #[command(flatten)]
+    pub meta: MetaOpts,
+
+    #[arg(short, long)]
+    pub input: String,
+}
+
+#[derive(Args, Debug)]
+pub struct DelegateArgs {
+    /// The issuing key
+    #[command(flatten)]
+    pub key: KeyOpts,
+
+    #[arg(long, value_name = "BASE64PK")]
+    pub subject: String,
+
+    #[arg(long)]
+    pub scope: String,
+
+    /// A record type or `prefix:*` pattern; repeat for more
+    #[arg(long = "record-type", required = true)]
+    pub record_types: Vec<String>,
+
+    /// Last micromark the certificate is good for
+    #[arg(long)]
+    pub exp: u64,
+
+    /// Also let the subject sign quorum for these record types
+    #[arg(long)]
+    pub quorum: bool,
+
+    /// Chain file to append the certificate to, one base64 CBOR per line
+    #[arg(short, long)]
+    pub output: String,
+}
+
 #[derive(Args, Debug)]
 pub struct ViewArgs {
     #[command(flatten)]
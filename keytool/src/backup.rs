@@ -0,0 +1,137 @@
+use hl_core::{
+    b32::{
+        b32::to_base32_crockford,
+        paper::{PAPER_KEY, PAPER_SHARE, from_paper, to_paper},
+    },
+    key::{
+        secret::SecretKey,
+        shamir::{self, Share},
+    },
+    to_base64,
+};
+use hl_io::fs::{
+    self as hl_fs,
+    authority::{KdfParams, KeyMeta},
+};
+use std::{fs, path::PathBuf, str::FromStr};
+
+use crate::argv::{CombineArgs, KeyOpts, MetaOpts, PaperExportArgs, PaperImportArgs, SplitArgs};
+
+/// Splits a key into share papers `share-<i>.txt` under the output dir.
+pub fn split(args: &SplitArgs) -> Result<(), anyhow::Error> {
+    let sk = load(&args.key)?;
+    let shares = shamir::split(&sk, args.threshold, args.shares)?;
+    let dir = PathBuf::from_str(&args.output)?;
+    fs::create_dir_all(&dir)?;
+    for share in shares.iter() {
+        let path = dir.join(format!("share-{}.txt", share.index));
+        let header = format!(
+            "# HodeauxLedger key share {} of {}, {} needed\n# key {} group {}\n",
+            share.index,
+            args.shares,
+            share.threshold,
+            to_base64(&sk.public_key()),
+            to_base32_crockford(&share.group)
+        );
+        fs::write(
+            &path,
+            header + &to_paper(PAPER_SHARE, &share.to_bytes()) + "\n",
+        )?;
+        println!("Wrote share {} to {}", share.index, path.display());
+    }
+    println!("Showing public key: {}", to_base64(&sk.public_key()));
+    Ok(())
+}
+
+/// Rebuilds a key from share papers and saves it.
+pub fn combine(args: &CombineArgs) -> Result<(), anyhow::Error> {
+    let mut shares = Vec::new();
+    for path in args.shares.iter() {
+        let mut payload = read_paper(path, PAPER_SHARE)?;
+        shares.push(Share::from_bytes(&payload)?);
+        payload.fill(0);
+    }
+    let sk = shamir::combine(&shares)?;
+    store(&args.key, &args.meta, &sk)?;
+    println!("Showing public key: {}", to_base64(&sk.public_key()));
+    Ok(())
+}
+
+/// Writes a whole key as a single paper.
+pub fn paper_export(args: &PaperExportArgs) -> Result<(), anyhow::Error> {
+    let sk = load(&args.key)?;
+    let text = format!(
+        "# HodeauxLedger key {}\n{}\n",
+        to_base64(&sk.public_key()),
+        to_paper(PAPER_KEY, sk.expose_secret())
+    );
+    match &args.output {
+        Some(output) => {
+            fs::write(output, text)?;
+            println!("Wrote paper backup to {}", output);
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+/// Restores a key from a paper and saves it.
+pub fn paper_import(args: &PaperImportArgs) -> Result<(), anyhow::Error> {
+    let mut payload = read_paper(&args.input, PAPER_KEY)?;
+    let mut bytes: [u8; 32] = payload
+        .as_slice()
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("paper key must be 32 bytes, got {}", payload.len()))?;
+    payload.fill(0);
+    let sk = SecretKey::from_bytes(&mut bytes);
+    store(&args.key, &args.meta, &sk)?;
+    println!("Showing public key: {}", to_base64(&sk.public_key()));
+    Ok(())
+}
+
+/// The paper's payload, skipping `#` comment lines.
+fn read_paper(path: &str, kind: u8) -> Result<Vec<u8>, anyhow::Error> {
+    let text = fs::read_to_string(path)?;
+    let body: String = text
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .collect();
+    let (found, payload) = from_paper(&body)?;
+    if found != kind {
+        anyhow::bail!("{} is not the expected kind of paper backup", path);
+    }
+    Ok(payload)
+}
+
+pub(crate) fn load(opts: &KeyOpts) -> Result<SecretKey, anyhow::Error> {
+    let pb = PathBuf::from_str(opts.keyfile()?)?;
+    if opts.hot {
+        return hl_fs::authority::load_key_hot(&pb);
+    }
+    hl_fs::authority::load_key(&pb, &opts.password()?)
+}
+
+fn store(opts: &KeyOpts, meta: &MetaOpts, sk: &SecretKey) -> Result<(), anyhow::Error> {
+    let pb = PathBuf::from_str(opts.keyfile()?)?;
+    if pb.exists() {
+        anyhow::bail!("{} already exists", pb.display());
+    }
+    if opts.hot {
+        return hl_fs::authority::save_key_hot(&pb, sk.expose_secret());
+    }
+    let password = opts.new_password()?;
+    // When the key was first made didn't survive the backup
+    let meta = KeyMeta {
+        label: meta.label.clone(),
+        created_at: None,
+        scope: meta.scope.clone(),
+        roles: meta.roles.clone(),
+    };
+    hl_fs::authority::save_key_with(
+        &pb,
+        &password,
+        sk.expose_secret(),
+        &KdfParams::default(),
+        &meta,
+    )
+}
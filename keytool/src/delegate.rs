@@ -0,0 +1,33 @@
+use std::{fs::OpenOptions, io::Write};
+
+use hl_core::{
+    Key, authority::delegation::Delegation, b64::b64::from_base64_to_32,
+    key::fingerprint::fingerprint, to_base64,
+};
+
+use crate::{argv::DelegateArgs, backup};
+
+/// Signs a certificate for the subject key and appends it to the chain
+/// file, so a sub-key can itself delegate further down.
+pub fn delegate(args: &DelegateArgs) -> Result<(), anyhow::Error> {
+    let issuer = Key::from_secret(backup::load(&args.key)?);
+    let subject = from_base64_to_32(&args.subject)?;
+    let mut cert = Delegation::new(subject, &args.scope, args.record_types.clone(), args.exp);
+    cert.quorum = args.quorum;
+    cert.sign(&issuer)?;
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&args.output)?;
+    writeln!(file, "{}", to_base64(&cert.to_cbor()?))?;
+    println!(
+        "Delegated {:?} in {} from {} to {} until {}",
+        cert.record_types,
+        cert.scope,
+        fingerprint(&cert.issuer_pk),
+        fingerprint(&cert.subject_pk),
+        cert.exp
+    );
+    Ok(())
+}
@@ -1,38 +1,61 @@
-use hl_core::{Key, to_base64};
-use hl_io::fs as hl_fs;
+use hl_core::{
+    key::secret::SecretKey,
+    time::clock::{EPOCH_AT_UNIX_MS, GTClock, SystemClock},
+    to_base64,
+};
+use hl_io::fs::{
+    self as hl_fs,
+    authority::{KdfParams, KeyMeta},
+};
 use std::{fs, path::PathBuf, str::FromStr};
 
-use crate::argv::GenerateArgs;
+use crate::{
+    argv::{GenerateArgs, MetaOpts},
+    view::print_identity,
+};
 
 /// Generates a keypair and returns them as tuple.
 pub fn generate_keypair(args: &GenerateArgs) -> Result<(), anyhow::Error> {
-    let keypath = &args.key.keyfile;
+    let keypath = args.key.keyfile()?;
     let hot = &args.key.hot;
     let show_sk = &args.show_sk;
-    let mut key = Key::new();
-    key.generate()?;
+    let sk = SecretKey::generate();
     if *hot {
         // We have a hot key, save it to disk as is
         println!("Saving hot key to file system");
         let pb = PathBuf::from_str(keypath)?;
-        hl_fs::authority::save_key_hot(&pb, &key.sk.unwrap())?;
+        hl_fs::authority::save_key_hot(&pb, sk.expose_secret())?;
     } else {
         // We are requesting to save it encrypted
-        let password = &args.key.password;
-        if password.is_none() {
-            anyhow::bail!("Password required")
-        }
-        let password = password.as_ref().unwrap();
+        let password = args.key.new_password()?;
         println!("Saving encrypted key to file system");
         let pb = PathBuf::from_str(keypath)?;
         if pb.exists() {
             fs::remove_file(&pb)?;
         }
-        hl_fs::authority::save_key(&pb, password, &key.sk.unwrap())?;
+        let meta = key_meta(&args.meta)?;
+        hl_fs::authority::save_key_with(
+            &pb,
+            &password,
+            sk.expose_secret(),
+            &KdfParams::default(),
+            &meta,
+        )?;
     };
-    if *show_sk && key.sk.is_some() {
-        println!("Showing secret key: {}", to_base64(&key.sk.unwrap()));
+    if *show_sk {
+        println!("Showing secret key: {}", to_base64(sk.expose_secret()));
     };
-    println!("Showing public key: {}", to_base64(&key.pk.unwrap()));
+    print_identity(&sk.public_key());
     Ok(())
 }
+
+/// Metadata for a key created now. Keys aren't tied to one ledger, so
+/// `created_at` is on the default epoch.
+pub fn key_meta(opts: &MetaOpts) -> Result<KeyMeta, anyhow::Error> {
+    Ok(KeyMeta {
+        label: opts.label.clone(),
+        created_at: Some(GTClock::new(EPOCH_AT_UNIX_MS).micromarks_on(&SystemClock)?),
+        scope: opts.scope.clone(),
+        roles: opts.roles.clone(),
+    })
+}
@@ -4,7 +4,12 @@ use crate::argv::Commands;
 
 mod argv;
 mod b64;
+mod backup;
+mod delegate;
 mod generate;
+mod migrate;
+mod password;
+mod rekey;
 mod sign;
 mod verify;
 mod view;
@@ -32,5 +37,22 @@ fn main() {
         Commands::Verify(verify_args) => verify::verify(&verify_args).expect("Failed to verify"),
         Commands::View(view_args) => view::view(&view_args).expect("Failed to view"),
         Commands::Base64(b64_args) => b64::base64convert(&b64_args).expect("Failed to convert"),
+        Commands::Migrate(migrate_args) => {
+            migrate::migrate(&migrate_args).expect("Failed to migrate")
+        }
+        Commands::Split(split_args) => backup::split(&split_args).expect("Failed to split"),
+        Commands::Combine(combine_args) => {
+            backup::combine(&combine_args).expect("Failed to combine")
+        }
+        Commands::PaperExport(export_args) => {
+            backup::paper_export(&export_args).expect("Failed to export")
+        }
+        Commands::PaperImport(import_args) => {
+            backup::paper_import(&import_args).expect("Failed to import")
+        }
+        Commands::Delegate(delegate_args) => {
+            delegate::delegate(&delegate_args).expect("Failed to delegate")
+        }
+        Commands::Rekey(rekey_args) => rekey::rekey(&rekey_args).expect("Failed to rekey"),
     }
 }
@@ -0,0 +1,27 @@
+use hl_io::fs::authority::{self as authority_store, KeyMeta};
+use std::{path::PathBuf, str::FromStr};
+
+use crate::argv::MigrateArgs;
+
+/// Upgrades an HKYV1 key file to HKYV2, keeping its password.
+pub fn migrate(args: &MigrateArgs) -> Result<(), anyhow::Error> {
+    if args.key.hot {
+        anyhow::bail!("Hot keys are not encrypted; nothing to migrate")
+    }
+    let password = args.key.password()?;
+    let keyfile = args.key.keyfile()?;
+    let pb = PathBuf::from_str(keyfile)?;
+    // HKYV1 never recorded when the key was made, so leave that unknown
+    let meta = KeyMeta {
+        label: args.meta.label.clone(),
+        created_at: None,
+        scope: args.meta.scope.clone(),
+        roles: args.meta.roles.clone(),
+    };
+    if authority_store::migrate_key(&pb, &password, &meta)? {
+        println!("Migrated {} to HKYV2", keyfile);
+    } else {
+        println!("{} is already HKYV2", keyfile);
+    }
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use std::{
+    env,
+    fs::File,
+    io::{BufRead, BufReader},
+};
+
+use anyhow::bail;
+use zeroize::Zeroizing;
+
+use crate::argv::{KeyOpts, PasswordOpts, RekeyArgs};
+
+/// A password, wiped when dropped.
+pub type Password = Zeroizing<String>;
+
+/// Non-interactive places a password can come from.
+enum Source<'a> {
+    Literal(&'a str),
+    Env(&'a str),
+    Fd(u32),
+    File(&'a str),
+}
+
+impl KeyOpts {
+    /// Password to open the existing key file.
+    pub fn password(&self) -> Result<Password, anyhow::Error> {
+        let prompt = format!("Password for {}: ", self.keyfile()?);
+        read(self.password.source()?, &prompt, false)
+    }
+
+    /// Password for a key file about to be written; typed twice when
+    /// asked for on the terminal.
+    pub fn new_password(&self) -> Result<Password, anyhow::Error> {
+        let prompt = format!("New password for {}: ", self.keyfile()?);
+        read(self.password.source()?, &prompt, true)
+    }
+}
+
+impl PasswordOpts {
+    fn source(&self) -> Result<Option<Source<'_>>, anyhow::Error> {
+        if self.password.is_some() {
+            eprintln!("⚠️ --password is visible in shell history and ps");
+        }
+        one_of([
+            self.password.as_deref().map(Source::Literal),
+            self.password_env.as_deref().map(Source::Env),
+            self.password_fd.map(Source::Fd),
+            self.password_file.as_deref().map(Source::File),
+        ])
+    }
+}
+
+impl RekeyArgs {
+    /// The password the key file is re-encrypted under.
+    pub fn new_password(&self) -> Result<Password, anyhow::Error> {
+        let source = one_of([
+            self.new_password_env.as_deref().map(Source::Env),
+            self.new_password_fd.map(Source::Fd),
+            self.new_password_file.as_deref().map(Source::File),
+        ])?;
+        let prompt = format!("New password for {}: ", self.key.keyfile()?);
+        read(source, &prompt, true)
+    }
+}
+
+fn one_of<const N: usize>(
+    sources: [Option<Source<'_>>; N],
+) -> Result<Option<Source<'_>>, anyhow::Error> {
+    let mut given = sources.into_iter().flatten();
+    let source = given.next();
+    if given.next().is_some() {
+        bail!("Give the password one way only")
+    }
+    Ok(source)
+}
+
+fn read(source: Option<Source>, prompt: &str, confirm: bool) -> Result<Password, anyhow::Error> {
+    let password = match source {
+        Some(Source::Literal(password)) => Zeroizing::new(password.to_string()),
+        Some(Source::Env(var)) => {
+            Zeroizing::new(env::var(var).map_err(|_| anyhow::anyhow!("{} is not set", var))?)
+        }
+        // Reopening through /dev/fd works for pipes and process substitution
+        Some(Source::Fd(fd)) => first_line(&format!("/dev/fd/{}", fd))?,
+        Some(Source::File(path)) => first_line(path)?,
+        None => {
+            let password = Zeroizing::new(rpassword::prompt_password(prompt)?);
+            if confirm {
+                let again = Zeroizing::new(rpassword::prompt_password("Again: ")?);
+                if again != password {
+                    bail!("Passwords did not match")
+                }
+            }
+            password
+        }
+    };
+    if password.is_empty() {
+        bail!("Password required")
+    }
+    Ok(password)
+}
+
+fn first_line(path: &str) -> Result<Password, anyhow::Error> {
+    let file = File::open(path).map_err(|e| anyhow::anyhow!("open {}: {}", path, e))?;
+    let mut line = Zeroizing::new(String::new());
+    BufReader::new(file).read_line(&mut line)?;
+    let len = line.trim_end_matches(['\r', '\n']).len();
+    line.truncate(len);
+    Ok(line)
+}
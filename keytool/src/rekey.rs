@@ -0,0 +1,18 @@
+use hl_io::fs::authority as authority_store;
+use std::{path::PathBuf, str::FromStr};
+
+use crate::argv::RekeyArgs;
+
+/// Re-encrypts a key file under a new password, replacing it atomically.
+pub fn rekey(args: &RekeyArgs) -> Result<(), anyhow::Error> {
+    if args.key.hot {
+        anyhow::bail!("Hot keys are not encrypted; nothing to rekey")
+    }
+    let keyfile = args.key.keyfile()?;
+    let pb = PathBuf::from_str(keyfile)?;
+    let password = args.key.password()?;
+    let new_password = args.new_password()?;
+    authority_store::rekey_key(&pb, &password, &new_password)?;
+    println!("Rekeyed {}", keyfile);
+    Ok(())
+}
@@ -1,29 +1,49 @@
 use crate::argv::ViewArgs;
-use hl_core::to_base64;
+use hl_core::{
+    key::fingerprint::{fingerprint, visual_hash},
+    time::gt,
+    to_base64,
+};
 use hl_io::fs::authority as authority_store;
 use std::{path::PathBuf, str::FromStr};
 
 pub fn view(view_args: &ViewArgs) -> Result<(), anyhow::Error> {
-    let input = &view_args.key.keyfile;
+    let input = view_args.key.keyfile()?;
     let show_sk = &view_args.show_sk;
     let hot = &view_args.key.hot;
-    let password = &view_args.key.password;
     let pb = PathBuf::from_str(input)?;
 
     let key = if *hot {
         authority_store::load_key_hot(&pb)?
     } else {
-        if password.is_none() {
-            anyhow::bail!("Password required")
+        let password = view_args.key.password()?;
+        let file = authority_store::open_key(&pb, &password)?;
+        println!("Key file: HKYV{}", file.version);
+        let meta = &file.meta;
+        if let Some(label) = &meta.label {
+            println!("Label: {}", label);
         }
-        let password = password.as_ref().unwrap();
-        authority_store::load_key(&pb, password)?
+        if let Some(created_at) = meta.created_at {
+            println!("Created: {}", gt::format(created_at as i128));
+        }
+        if let Some(scope) = &meta.scope {
+            println!("Scope: {:?}", scope);
+        }
+        if !meta.roles.is_empty() {
+            println!("Roles: {}", meta.roles.join(", "));
+        }
+        file.sk
     };
     if *show_sk {
-        println!("Showing secret key: {}", to_base64(&key));
+        println!("Showing secret key: {}", to_base64(key.expose_secret()));
     }
-    let sign_key = ed25519_dalek::SigningKey::from_bytes(&key);
-    let key = sign_key.verifying_key().to_bytes();
-    println!("Showing public key: {}", to_base64(&key));
+    print_identity(&key.public_key());
     Ok(())
 }
+
+/// Public key, fingerprint and visual hash, for comparing keys by eye.
+pub fn print_identity(pk: &[u8; 32]) {
+    println!("Showing public key: {}", to_base64(pk));
+    println!("Fingerprint: {}", fingerprint(pk));
+    println!("{}", visual_hash(pk));
+}
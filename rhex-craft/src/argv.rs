@@ -51,6 +51,9 @@ pub struct CraftArgs {
     pub record_type: String,
     #[arg(short, long, value_name = "FILE")]
     pub data: String,
+    /// Delegation chain from `keytool delegate` for a sub-key author
+    #[arg(long, value_name = "FILE")]
+    pub delegation: Option<String>,
 }
 
 #[derive(Args, Debug)]
@@ -61,8 +64,12 @@ pub struct FinalizeArgs {
 
 #[derive(Args, Debug)]
 pub struct ValidateArgs {
-    #[command(flatten)]
-    pub io: Io,
+    #[arg(short, long, value_name = "DIR")]
+    pub input: Option<String>,
+    #[arg(long, value_name = "DB")]
+    pub cache: Option<String>,
+    #[arg(short, long)]
+    pub scope: Option<String>,
 }
 
 #[derive(Args, Debug)]
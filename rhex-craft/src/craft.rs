@@ -1,5 +1,7 @@
 use crate::argv::CraftArgs;
-use hl_core::{Context, Intent, Rhex, from_base64};
+use hl_core::{
+    Context, Intent, Rhex, authority::delegation::Delegation, from_base64, rhex::rhex::RHEX_MAGIC,
+};
 use std::{path::PathBuf, str::FromStr};
 
 use anyhow::anyhow;
@@ -43,6 +45,10 @@ pub fn craft(craft_args: &CraftArgs) -> Result<(), anyhow::Error> {
     } else {
         Some(Intent::gen_nonce())
     };
+    let delegation = match &craft_args.delegation {
+        Some(path) => Some(read_chain(path)?),
+        None => None,
+    };
     let rhex_intent = Intent {
         previous_hash: ph_opt,
         scope: scope.to_string(),
@@ -51,10 +57,11 @@ pub fn craft(craft_args: &CraftArgs) -> Result<(), anyhow::Error> {
         usher_pk: usher_pk?,
         record_type: record_type.to_string(),
         data: data_json,
+        delegation,
     };
 
     let rhex = Rhex {
-        magic: *b"RHEX\x00\x00",
+        magic: RHEX_MAGIC,
         intent: rhex_intent,
         context: Context {
             at: 0,
@@ -71,6 +78,15 @@ pub fn craft(craft_args: &CraftArgs) -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+/// One base64 CBOR certificate per line.
+fn read_chain(path: &str) -> anyhow::Result<Vec<Delegation>> {
+    std::fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Delegation::from_cbor(&from_base64(line.trim())?))
+        .collect()
+}
+
 fn vec_to_arr32(v: Vec<u8>) -> anyhow::Result<[u8; 32]> {
     v.try_into()
         .map_err(|v: Vec<u8>| anyhow!("expected 32 bytes, got {}", v.len()))
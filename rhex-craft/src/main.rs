@@ -33,7 +33,14 @@ fn main() {
             }
         }
         argv::Commands::Validate(validate_args) => {
-            validate::validate(&validate_args);
+            let status = validate::validate(&validate_args);
+            match status {
+                Ok(_) => {}
+                Err(e) => {
+                    println!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
         }
         argv::Commands::View(view_args) => {
             let status = view::view(&view_args);
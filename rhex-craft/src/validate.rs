@@ -1,5 +1,30 @@
 use crate::argv::ValidateArgs;
+use anyhow::bail;
+use hl_io::{
+    db::{Cache, rhex::CacheSource},
+    fs::rhex::DirSource,
+};
+use hl_services::audit::audit_chain;
+use std::{path::PathBuf, str::FromStr};
 
-pub fn validate(validate_args: &ValidateArgs) {
-    println!("{:?}", validate_args);
+/// Audit a whole scope chain, either from its directory or from a cache.db.
+pub fn validate(validate_args: &ValidateArgs) -> Result<(), anyhow::Error> {
+    let report = match (&validate_args.input, &validate_args.cache) {
+        (Some(dir), None) => {
+            let mut source = DirSource::new(PathBuf::from_str(dir)?)?;
+            audit_chain(&mut source)
+        }
+        (None, Some(db)) => {
+            let scope = validate_args.scope.clone().unwrap_or_default();
+            let cache = Cache::open(db)?;
+            let mut source = CacheSource::new(&cache, scope);
+            audit_chain(&mut source)
+        }
+        _ => bail!("Specify exactly one of --input <DIR> or --cache <DB>"),
+    };
+    report.print();
+    if !report.is_clean() {
+        bail!("audit failed");
+    }
+    Ok(())
 }
@@ -51,6 +51,10 @@ pub struct RequestArgs {
 
     #[arg(short, long)]
     pub keyfile: String,
+
+    /// Ledger epoch (the genesis `unix_ms`) the request is stamped on
+    #[arg(long, value_name = "MS")]
+    pub epoch_ms: i128,
 }
 
 #[derive(Args, Debug)]
@@ -1,13 +1,13 @@
 use crate::argv::RequestArgs;
 use hl_core::{
-    Rhex, Signature,
+    Key, Rhex, Signature,
     keymaster::keymaster::Keymaster,
     rhex::{context::Context, intent::Intent, signature::SigType},
     time::clock::GTClock,
 };
-use hl_io::{net::net::Transport, screen::print::pretty_print};
+use hl_io::{fs::authority::load_key_hot, net::net::Transport, screen::print::pretty_print};
 use serde_json::json;
-use std::{fs, time::Duration};
+use std::{path::Path, time::Duration};
 pub async fn request(request_args: &RequestArgs) -> Result<(), anyhow::Error> {
     let host = &request_args.host;
     let port = &request_args.port;
@@ -20,8 +20,8 @@ pub async fn request(request_args: &RequestArgs) -> Result<(), anyhow::Error> {
     );
 
     let mut keymaster = Keymaster::new();
-    let key_bytes = fs::read(keyfile)?;
-    keymaster.load_keys(&vec![key_bytes.try_into().unwrap()])?;
+    let key = load_key_hot(Path::new(keyfile))?;
+    keymaster.load_keys(&[Key::from_secret(key)])?;
 
     let author_key = keymaster.hot_keys.get(0).ok_or_else(|| {
         anyhow::anyhow!("No keys found in keyfile. At least one key is required.")
@@ -35,9 +35,11 @@ pub async fn request(request_args: &RequestArgs) -> Result<(), anyhow::Error> {
         usher_pk: author_key.public_key_bytes()?, // For now, usher is self
         record_type: "request:rhex".to_string(),
         data: json!({}),
+        delegation: None,
     };
 
-    let clock = GTClock::new(0);
+    // Stamp on the ledger's epoch, which the caller must supply
+    let clock = GTClock::new(request_args.epoch_ms);
     let context = Context::from_at(clock.now_micromarks_u64());
 
     let mut request_rhex = Rhex::new();
@@ -17,6 +17,7 @@ pub struct Cli {
 pub enum Commands {
     Listen(ListenArgs),
     Rebuild(RebuildArgs),
+    Audit(AuditArgs),
 }
 
 #[derive(Args, Debug, Clone)]
@@ -27,6 +28,9 @@ pub struct ListenArgs {
     pub host: String,
     #[arg(short, long)]
     pub config: Option<String>,
+    /// Append a Merkle checkpoint to each scope this often, in seconds
+    #[arg(long)]
+    pub checkpoint_secs: Option<u64>,
 }
 
 #[derive(Args, Debug)]
@@ -34,3 +38,12 @@ pub struct RebuildArgs {
     #[arg(short, long)]
     pub config: Option<String>,
 }
+
+#[derive(Args, Debug)]
+pub struct AuditArgs {
+    #[arg(short, long)]
+    pub config: Option<String>,
+    /// Audit only this scope; every scope in the store otherwise
+    #[arg(short, long)]
+    pub scope: Option<String>,
+}
@@ -0,0 +1,33 @@
+use anyhow::{Error, bail};
+use hl_io::store::StoreSource;
+use hl_services::audit::audit_chain;
+
+use crate::argv::AuditArgs;
+
+/// Audit the chains in the store our config points at, end to end.
+pub fn audit(audit_args: &AuditArgs) -> Result<(), Error> {
+    let Some(config_file) = &audit_args.config else {
+        println!("No config file specified");
+        bail!("Config file is required");
+    };
+    let config = hl_services::config::load_config(config_file)?;
+    let store = hl_io::store::open(&config)?;
+
+    let scopes = match &audit_args.scope {
+        Some(scope) => vec![scope.clone()],
+        None => store.scopes()?,
+    };
+    let mut failed = 0;
+    for scope in scopes {
+        let mut source = StoreSource::new(&*store, scope);
+        let report = audit_chain(&mut source);
+        report.print();
+        if !report.is_clean() {
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        bail!("{} scope(s) failed audit", failed);
+    }
+    Ok(())
+}
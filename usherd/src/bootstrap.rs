@@ -1,42 +1,76 @@
 use std::{path::PathBuf, str::FromStr, sync::Arc, vec};
 
-use hl_core::{Key, keymaster::keymaster::Keymaster, policy::rule::Rule};
+use hl_core::{
+    Config, keymaster::keymaster::Keymaster, policy::rule::Rule, rhex::batch::verify_records,
+};
 use hl_io::{
-    db::{self, connect_db, flush_all, head::set_head},
-    fs::{self},
+    db::{self, Cache, flush_all, head::set_head},
+    fs::{self, journal::Recovered},
     screen::print::pretty_print,
-    source::RhexSource,
+    store,
 };
 
 use hl_services::{config::load_config, process};
 
 use crate::argv::ListenArgs;
 
-pub fn bootstrap(listen_args: &ListenArgs) -> Result<(), anyhow::Error> {
-    println!("Bootstrapping usher...");
-
-    let config_file = &listen_args.config;
-    let config = config_file
+/// Config and signers, loaded once for everything the daemon runs;
+/// unlocking encrypted signers is deliberately slow.
+pub fn load(listen_args: &ListenArgs) -> Result<(Arc<Config>, Arc<Keymaster>), anyhow::Error> {
+    let config_file = listen_args
+        .config
         .clone()
         .unwrap_or("usherd_config.json".to_string());
-    let config = load_config(&config)?;
+    let config = load_config(&config_file)?;
+    let mut keymaster = Keymaster::new();
+    keymaster.load_keys(&config.hot_keys)?;
+    Ok((Arc::new(config), Arc::new(keymaster)))
+}
 
-    // Flushing cache.db
-    flush_all(&config.cache_db)?;
+/// Create the cache or migrate it to this build's schema. Fails on a
+/// cache written by a newer build, which we must not run against.
+pub fn migrate_cache(config: &Config) -> Result<(), anyhow::Error> {
+    let cache = Cache::from_config(config)?;
+    let found = db::migrate::migrate(&cache)?;
+    if found < db::migrate::SCHEMA_VERSION {
+        println!(
+            "Migrated cache schema v{} -> v{}",
+            found,
+            db::migrate::SCHEMA_VERSION
+        );
+    }
+    Ok(())
+}
 
-    // Create keymaster and load keys
-    println!("Setting up keymaster...");
-    let mut keymaster = Keymaster::new();
-    for key in config.hot_keys.iter() {
-        keymaster.hot_keys.push(Key::from_bytes(*key));
+/// Settle appends a crash left half done, before bootstrap flushes the
+/// heads that tell us which of them committed.
+pub fn repair(config: &Config) -> Result<(), anyhow::Error> {
+    let cache = Cache::from_config(config)?;
+    let store = store::open(config)?;
+    let root_dir = PathBuf::from_str(&config.fs_dir)?;
+    if root_dir.is_dir() {
+        fs::remove_temps(&root_dir)?;
+    }
+    let journal_dir = PathBuf::from_str(&config.journal_dir)?;
+    for recovered in fs::journal::recover(&journal_dir, &cache, &*store)? {
+        match recovered {
+            Recovered::Completed(file) => println!("Completed interrupted append {:?}", file),
+            Recovered::RolledBack(file) => println!("Rolled back interrupted append {:?}", file),
+        }
     }
+    Ok(())
+}
+
+pub fn bootstrap(config: &Arc<Config>, keymaster: &Keymaster) -> Result<(), anyhow::Error> {
+    println!("Bootstrapping usher...");
+
+    let cache = Cache::from_config(config)?;
+
+    // Flushing cache.db
+    flush_all(&cache)?;
 
     println!("Loading root scope...");
-    let root_dir = PathBuf::from_str(&config.fs_dir)?;
-    println!("Loading scope: {:?}", root_dir.to_str());
-    let mut dir_source = fs::rhex::DirSource::new(root_dir)?;
-    let config = Arc::new(config);
-    let cache = connect_db(&config.cache_db)?;
+    let store = store::open(config)?;
     // add scope "" current_hash none head
     let status = set_head(&cache, "", &[0u8; 32]);
     match status {
@@ -54,21 +88,23 @@ pub fn bootstrap(listen_args: &ListenArgs) -> Result<(), anyhow::Error> {
     rule.quorum_roles = vec!["authority".to_string()];
     rule.rate_per_mark = 1;
     db::rule::store_rule(&cache, "", &rule)?;
+    let records = store.chain("").unwrap_or_else(|e| {
+        println!("Error loading rhex: {:?}", e);
+        Vec::new()
+    });
+
+    // Check the whole chain's signatures in batches; only records with a
+    // bad one go back through the one-at-a-time checks.
+    let sigs = verify_records(&records);
+    for failure in sigs.failures.iter() {
+        println!(
+            "Bad signature {} on record {}: {:?}",
+            failure.signature, failure.record, failure.outcome
+        );
+    }
+
     let mut head = [0u8; 32];
-    loop {
-        let rhex_opt = dir_source.next();
-        let rhex_opt = match rhex_opt {
-            Ok(r) => r,
-            Err(e) => {
-                println!("Error loading rhex: {:?}", e);
-                break;
-            }
-        };
-        //println!("{:?}", rhex_opt);
-        if rhex_opt.is_none() {
-            break;
-        }
-        let rhex = rhex_opt.unwrap();
+    for (index, rhex) in records.iter().enumerate() {
         if rhex.current_hash.is_some() {
             //println!("Loaded rhex: {}", to_base64(&rhex.current_hash.unwrap()));
             head = rhex.current_hash.unwrap();
@@ -76,7 +112,11 @@ pub fn bootstrap(listen_args: &ListenArgs) -> Result<(), anyhow::Error> {
             println!("Loaded rhex with no current_hash");
         }
 
-        let output = process::process_rhex(&rhex, false, &config, &keymaster);
+        let output = if sigs.record_ok(index) {
+            process::replay_verified(rhex, &cache, &*store, config, keymaster)
+        } else {
+            process::process_rhex(rhex, false, &cache, &*store, config, keymaster)
+        };
         let output = match output {
             Ok(o) => o,
             Err(e) => {
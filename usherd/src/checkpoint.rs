@@ -0,0 +1,68 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Error;
+use hl_core::{Config, keymaster::keymaster::Keymaster, to_base64};
+use hl_io::{db::Cache, store};
+use hl_services::checkpoint;
+
+use crate::quorum;
+
+/// Checkpoint every scope we hold on a fixed interval. Scopes with
+/// nothing new since their last checkpoint are skipped.
+pub async fn checkpoint_loop(
+    config: Arc<Config>,
+    keymaster: Arc<Keymaster>,
+    every_secs: u64,
+    verbose: bool,
+) {
+    let mut interval = tokio::time::interval(Duration::from_secs(every_secs.max(1)));
+    loop {
+        interval.tick().await;
+        if let Err(e) = checkpoint_once(&config, &keymaster, verbose).await {
+            eprintln!("⚠️ checkpoint error: {e}");
+        }
+    }
+}
+
+async fn checkpoint_once(
+    config: &Arc<Config>,
+    keymaster: &Keymaster,
+    verbose: bool,
+) -> Result<(), Error> {
+    let scopes = store::open(config)?.scopes()?;
+    for scope in scopes {
+        let rhex = {
+            let cache = Cache::from_config(config)?;
+            let store = store::open(config)?;
+            checkpoint::build_checkpoint(&cache, &*store, config, keymaster, &scope)?
+        };
+        let Some(rhex) = rhex else {
+            continue;
+        };
+        // Quorum comes from the scope's members like for any record; with
+        // nothing left to gather this just finalizes and appends
+        let out = quorum::gather(rhex, config, keymaster, verbose).await?;
+        if let Some(refused) = out
+            .iter()
+            .find(|r| r.intent.record_type == "response:error")
+        {
+            eprintln!(
+                "⚠️ checkpoint for scope {:?} refused: {}",
+                scope, refused.intent.data
+            );
+            continue;
+        }
+        let Some(appended) = out.first() else {
+            eprintln!("⚠️ checkpoint for scope {:?} came back with nothing", scope);
+            continue;
+        };
+        if verbose {
+            println!(
+                "🌳 checkpoint {} for scope {:?}",
+                to_base64(&appended.current_hash.unwrap_or_default()),
+                scope
+            );
+        }
+    }
+    Ok(())
+}
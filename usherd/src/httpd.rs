@@ -4,10 +4,18 @@ use axum::{
     routing::post,
 };
 use serde::{Deserialize, Serialize};
-use std::{io::Cursor, sync::Arc};
+use std::sync::Arc;
 
-use crate::argv::ListenArgs;
-use hl_core::{Rhex, from_base64, to_base64};
+use hl_core::{
+    Config, Rhex, b64::b64::from_base64_to_32, from_base64, keymaster::keymaster::Keymaster,
+    merkle::merkle::InclusionProof, to_base64,
+};
+
+/// What the handlers share, loaded once at startup.
+struct AppState {
+    config: Arc<Config>,
+    keymaster: Arc<Keymaster>,
+}
 
 #[derive(Deserialize)]
 struct AppendRequest {
@@ -23,25 +31,17 @@ struct AppendResponse {
     error: Option<String>,
 }
 
-async fn process(rhex: &Rhex, args: Arc<ListenArgs>) -> anyhow::Result<Vec<Rhex>> {
-    // Do nothing for now
-    use hl_core::{Key, keymaster::keymaster::Keymaster};
-    use hl_services::{config::load_config, process as hl_process};
-
-    let config_file = &args.config;
-    let config_file = if config_file.is_some() {
-        config_file.as_ref().unwrap()
-    } else {
-        &"config.json".to_string()
-    };
-    let config = Arc::new(load_config(config_file)?);
-
-    let mut keymaster = Keymaster::new();
-    for key in config.hot_keys.iter() {
-        keymaster.hot_keys.push(Key::from_bytes(*key));
-    }
-
-    let processed_rhex = hl_process::process_rhex(rhex, true, &config, &keymaster)?;
+async fn process(rhex: &Rhex, state: &AppState) -> anyhow::Result<Vec<Rhex>> {
+    let cache = hl_io::db::Cache::from_config(&state.config)?;
+    let store = hl_io::store::open(&state.config)?;
+    let processed_rhex = hl_services::process::process_rhex(
+        rhex,
+        true,
+        &cache,
+        &*store,
+        &state.config,
+        &state.keymaster,
+    )?;
     // For now, we assume only one Rhex is returned
     // In a real scenario, you might need to handle multiple Rhex outputs
     if processed_rhex.len() == 0 {
@@ -52,7 +52,7 @@ async fn process(rhex: &Rhex, args: Arc<ListenArgs>) -> anyhow::Result<Vec<Rhex>
 }
 
 async fn append_handler(
-    State(listen_args): State<Arc<ListenArgs>>,
+    State(state): State<Arc<AppState>>,
     Json(req): Json<AppendRequest>,
 ) -> Json<AppendResponse> {
     // Step 1: decode base64
@@ -68,8 +68,8 @@ async fn append_handler(
         }
     };
 
-    // Step 2: decode CBOR -> Rhex using ciborium
-    let rhex: Rhex = match ciborium::de::from_reader(Cursor::new(&raw_bytes)) {
+    // Step 2: decode canonical CBOR -> Rhex
+    let rhex: Rhex = match Rhex::from_cbor(&raw_bytes) {
         Ok(r) => r,
         Err(e) => {
             return Json(AppendResponse {
@@ -82,7 +82,7 @@ async fn append_handler(
     };
 
     // Step 3: process
-    match process(&rhex, listen_args).await {
+    match process(&rhex, &state).await {
         Ok(r) => {
             // serialize hash back to base64
 
@@ -102,8 +102,89 @@ async fn append_handler(
     }
 }
 
-pub async fn start_http_server(listen_args: ListenArgs) {
-    let shared = Arc::new(listen_args);
+#[derive(Deserialize)]
+struct ProofRequest {
+    scope: String,
+    // base64 current_hash of the record to prove
+    hash: String,
+}
+
+#[derive(Serialize)]
+struct ProofResponse {
+    ok: bool,
+    proof: Option<InclusionProof>,
+    // base64 CBOR of the checkpoint the proof leads to
+    checkpoint_b64: Option<String>,
+    error: Option<String>,
+}
+
+fn prove(req: &ProofRequest, config: &Config) -> anyhow::Result<(InclusionProof, Rhex)> {
+    let hash = from_base64_to_32(&req.hash)?;
+    let store = hl_io::store::open(config)?;
+    hl_services::checkpoint::prove(&*store, &req.scope, &hash)
+}
+
+async fn proof_handler(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ProofRequest>,
+) -> Json<ProofResponse> {
+    let proven = prove(&req, &state.config)
+        .and_then(|(proof, checkpoint)| Ok((proof, to_base64(&checkpoint.into_cbor()?))));
+    match proven {
+        Ok((proof, checkpoint_b64)) => Json(ProofResponse {
+            ok: true,
+            proof: Some(proof),
+            checkpoint_b64: Some(checkpoint_b64),
+            error: None,
+        }),
+        Err(e) => Json(ProofResponse {
+            ok: false,
+            proof: None,
+            checkpoint_b64: None,
+            error: Some(e.to_string()),
+        }),
+    }
+}
+
+#[derive(Deserialize)]
+struct ResolveRequest {
+    // e.g. HK1-7Q2M-C4ZD-0W8N-VR3K
+    fingerprint: String,
+}
+
+#[derive(Serialize)]
+struct ResolveResponse {
+    ok: bool,
+    public_key: Option<String>,
+    error: Option<String>,
+}
+
+fn resolve(req: &ResolveRequest, config: &Config) -> anyhow::Result<[u8; 32]> {
+    let cache = hl_io::db::Cache::from_config(config)?;
+    hl_io::db::authority::resolve_fingerprint(&cache, &req.fingerprint)?
+        .ok_or_else(|| anyhow::anyhow!("no authority key with fingerprint {}", req.fingerprint))
+}
+
+async fn resolve_handler(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ResolveRequest>,
+) -> Json<ResolveResponse> {
+    match resolve(&req, &state.config) {
+        Ok(pk) => Json(ResolveResponse {
+            ok: true,
+            public_key: Some(to_base64(&pk)),
+            error: None,
+        }),
+        Err(e) => Json(ResolveResponse {
+            ok: false,
+            public_key: None,
+            error: Some(e.to_string()),
+        }),
+    }
+}
+
+pub async fn start_http_server(config: Arc<Config>, keymaster: Arc<Keymaster>) {
+    let shared = Arc::new(AppState { config, keymaster });
 
     let cors = tower_http::cors::CorsLayer::new()
         .allow_origin(tower_http::cors::Any)
@@ -112,6 +193,8 @@ pub async fn start_http_server(listen_args: ListenArgs) {
 
     let app = Router::new()
         .route("/append", post(append_handler))
+        .route("/proof", post(proof_handler))
+        .route("/resolve", post(resolve_handler))
         .with_state(shared)
         .layer(cors);
 
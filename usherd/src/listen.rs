@@ -1,15 +1,15 @@
 use anyhow::Error;
 use bytes::BytesMut;
 use futures::{SinkExt, StreamExt};
-use hl_core::{Config, Key, Rhex, keymaster::keymaster::Keymaster};
-use hl_io::net::codec::RhexCodec;
+use hl_core::{Config, Rhex, key::fingerprint::fingerprint, keymaster::keymaster::Keymaster};
+use hl_io::{db::Cache, net::codec::RhexCodec, store};
 use hl_services::process;
-use std::sync::Arc;
+use std::{collections::VecDeque, sync::Arc};
 use tokio::net::TcpListener;
 use tokio::time::Instant;
 use tokio_util::codec::{Encoder, Framed};
 
-use crate::argv::ListenArgs;
+use crate::{argv::ListenArgs, checkpoint, quorum};
 
 pub struct ConnStats {
     pub bytes_sent: u64,
@@ -43,7 +43,12 @@ async fn setup_listener(host: &str, port: &str) -> Result<TcpListener, Error> {
 }
 
 // NOTE: take Arc<Config> by value, not &Config
-async fn accept_loop(listener: TcpListener, verbose: bool, config: Arc<Config>) {
+async fn accept_loop(
+    listener: TcpListener,
+    verbose: bool,
+    config: Arc<Config>,
+    keymaster: Arc<Keymaster>,
+) {
     // you can use `&*config` here if you need it in this scope
     loop {
         match listener.accept().await {
@@ -51,8 +56,9 @@ async fn accept_loop(listener: TcpListener, verbose: bool, config: Arc<Config>)
                 println!("📡🟢 -> {addr}");
                 // clone the Arc into the task so it satisfies 'static
                 let cfg = Arc::clone(&config);
+                let keys = Arc::clone(&keymaster);
                 tokio::spawn(async move {
-                    if let Err(e) = handle_conn(stream, addr, verbose, cfg).await {
+                    if let Err(e) = handle_conn(stream, addr, verbose, cfg, keys).await {
                         eprintln!("⚠️ {addr} error: {e}");
                     }
                     println!("📡🔴 -> {addr}");
@@ -72,12 +78,15 @@ async fn handle_conn(
     addr: std::net::SocketAddr,
     verbose: bool,
     config: Arc<Config>,
+    keymaster: Arc<Keymaster>,
 ) -> Result<(), Error> {
     let framed = Framed::new(stream, RhexCodec::new());
     let (mut sink, mut stream) = framed.split();
 
     let mut stats = ConnStats::new();
     let mut codec = RhexCodec::new();
+    let cache = Cache::from_config(&config)?;
+    let store = store::open(&config)?;
 
     // example: read from config, no clone needed
     let _bind_info = &config.host; // or whatever fields you have
@@ -93,17 +102,26 @@ async fn handle_conn(
 
         if verbose {
             println!(
-                "📥 {addr} in: {} bytes | record_type: {}",
-                in_len, rhex_in.intent.record_type
+                "📥 {addr} in: {} bytes | record_type: {} | author: {}",
+                in_len,
+                rhex_in.intent.record_type,
+                fingerprint(&rhex_in.intent.author_pk)
             );
         }
 
         // do your real handling here, using `config` if needed
-        let mut keymaster = Keymaster::new();
-        for key in config.hot_keys.iter() {
-            keymaster.hot_keys.push(Key::from_bytes(*key));
+        let mut out_rhex = Vec::new();
+        // Appending a gathered record can make more to gather, e.g. a
+        // scope:create and then the child's genesis
+        let mut queue: VecDeque<Rhex> =
+            process::process_rhex(&rhex_in, true, &cache, &*store, &config, &keymaster)?.into();
+        while let Some(rhex) = queue.pop_front() {
+            if quorum::pending(&rhex, &cache, &keymaster)? {
+                queue.extend(quorum::gather(rhex, &config, &keymaster, verbose).await?);
+            } else {
+                out_rhex.push(rhex);
+            }
         }
-        let out_rhex = process::process_rhex(&rhex_in, true, &config, &keymaster)?;
         //let out_rhex = vec![rhex_in];
 
         for rhex in out_rhex {
@@ -133,16 +151,29 @@ async fn handle_conn(
     Ok(())
 }
 
-pub async fn listen(listen_args: &ListenArgs, verbose: bool) -> Result<(), Error> {
+pub async fn listen(
+    listen_args: &ListenArgs,
+    config: Arc<Config>,
+    keymaster: Arc<Keymaster>,
+    verbose: bool,
+) -> Result<(), Error> {
     let port = &listen_args.port;
     let host = &listen_args.host;
-    let config_file = &listen_args.config;
-    let config_file = if config_file.is_some() {
-        config_file.as_ref().unwrap()
-    } else {
-        &"config.json".to_string()
-    };
-    let config = Arc::new(hl_services::config::load_config(config_file)?); // ← wrap in Arc
+
+    if let Some(every_secs) = listen_args.checkpoint_secs {
+        tokio::spawn(checkpoint::checkpoint_loop(
+            Arc::clone(&config),
+            Arc::clone(&keymaster),
+            every_secs,
+            verbose,
+        ));
+    }
+
+    for key in config.hot_keys.iter() {
+        if let Some(pk) = &key.pk {
+            println!("🔑 {}", fingerprint(pk));
+        }
+    }
 
     let listener = setup_listener(host, port).await?;
     println!("[LISTENING {host}:{port}]");
@@ -154,7 +185,7 @@ pub async fn listen(listen_args: &ListenArgs, verbose: bool) -> Result<(), Error
     });
 
     tokio::select! {
-        _ = accept_loop(listener, verbose, Arc::clone(&config)) => {}
+        _ = accept_loop(listener, verbose, Arc::clone(&config), keymaster) => {}
         _ = shutdown => {}
     }
 
@@ -1,11 +1,15 @@
 use clap::Parser;
+use std::sync::Arc;
 
 use crate::{argv::Commands, httpd::start_http_server};
 
 mod argv;
+mod audit;
 mod bootstrap;
+mod checkpoint;
 mod httpd;
 mod listen;
+mod quorum;
 mod rebuild;
 
 fn print_banner() {
@@ -21,10 +25,25 @@ async fn main() {
 
     match parsed.command {
         Commands::Listen(listen_args) => {
+            let ready = bootstrap::load(&listen_args).and_then(|(config, keymaster)| {
+                bootstrap::migrate_cache(&config)?;
+                bootstrap::repair(&config)?;
+                Ok((config, keymaster))
+            });
+            let (config, keymaster) = match ready {
+                Ok(ready) => ready,
+                Err(e) => {
+                    println!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            };
             // Bootstrap ourselves into a ledger
-            let _ = bootstrap::bootstrap(&listen_args);
-            let http_server_handle = tokio::spawn(start_http_server(listen_args.clone()));
-            let status = listen::listen(&listen_args, parsed.verbose).await;
+            let _ = bootstrap::bootstrap(&config, &keymaster);
+            let http_server_handle = tokio::spawn(start_http_server(
+                Arc::clone(&config),
+                Arc::clone(&keymaster),
+            ));
+            let status = listen::listen(&listen_args, config, keymaster, parsed.verbose).await;
             if status.is_err() {
                 http_server_handle.abort();
                 println!("Error: {:?}", status.err().unwrap());
@@ -34,9 +53,19 @@ async fn main() {
                 std::process::exit(0);
             }
         }
+        Commands::Audit(audit_args) => {
+            let status = audit::audit(&audit_args);
+            if let Err(e) = status {
+                println!("Error: {}", e);
+                std::process::exit(1);
+            } else {
+                std::process::exit(0);
+            }
+        }
         Commands::Rebuild(rebuild_args) => {
             let status = rebuild::rebuild(&rebuild_args);
-            if status.is_err() {
+            if let Err(e) = status {
+                println!("Error: {}", e);
                 std::process::exit(1);
             } else {
                 std::process::exit(0);
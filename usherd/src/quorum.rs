@@ -0,0 +1,114 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Error;
+use futures::{StreamExt, stream::FuturesUnordered};
+use hl_core::{
+    Config, Rhex, Usher, key::fingerprint::fingerprint, keymaster::keymaster::Keymaster,
+};
+use hl_io::{db::Cache, net::net::Transport, store};
+use hl_services::{process, quorum};
+
+/// Whether `rhex` is ours to usher and still short of quorum.
+pub fn pending(rhex: &Rhex, cache: &Cache, keymaster: &Keymaster) -> Result<bool, Error> {
+    if rhex.current_hash.is_some()
+        || rhex.signatures.len() < 2
+        || keymaster.get_matching(&rhex.intent.usher_pk).is_err()
+    {
+        return Ok(false);
+    }
+    Ok(quorum::needed(cache, rhex)? > 0)
+}
+
+/// Forward an UsherSigned `rhex` to the other quorum members' ushers and
+/// collect signatures until `quorum_k` is met or `quorum_ttl` runs out.
+/// Returns what goes back to the author: the finalized record and
+/// whatever appending it produced, or an `E_QUORUM_INSUFFICIENT` error.
+/// Opens its own cache handles, since a borrowed one can't be held
+/// across the waits.
+pub async fn gather(
+    mut rhex: Rhex,
+    config: &Arc<Config>,
+    keymaster: &Keymaster,
+    verbose: bool,
+) -> Result<Vec<Rhex>, Error> {
+    let (peers, k, deadline) = {
+        let cache = Cache::from_config(config)?;
+        let k = quorum::count(&rhex) + quorum::needed(&cache, &rhex)?;
+        let peers = quorum::peers(&cache, &rhex, keymaster)?;
+        (peers, k, quorum::deadline_ms(&cache, &rhex)?)
+    };
+    let remaining = (deadline - config.clock.now_unix_ms()).max(0) as u64;
+    if verbose {
+        println!(
+            "🤝 gathering quorum {}/{} from {} ushers, {} ms left",
+            quorum::count(&rhex),
+            k,
+            peers.len(),
+            remaining
+        );
+    }
+
+    let mut asks: FuturesUnordered<_> = peers
+        .into_iter()
+        .filter(|usher| usher.proto == "rhex")
+        .map(|usher| ask(usher, rhex.clone()))
+        .collect();
+    let collect = async {
+        while let Some(reply) = asks.next().await {
+            let reply = match reply {
+                Ok(Some(reply)) => reply,
+                Ok(None) => continue,
+                Err(e) => {
+                    eprintln!("⚠️ quorum peer error: {e}");
+                    continue;
+                }
+            };
+            let cache = Cache::from_config(config)?;
+            quorum::merge(&cache, &mut rhex, &reply)?;
+            if quorum::count(&rhex) >= k {
+                break;
+            }
+        }
+        Ok::<(), Error>(())
+    };
+    if let Ok(result) = tokio::time::timeout(Duration::from_millis(remaining), collect).await {
+        result?;
+    }
+
+    let have = quorum::count(&rhex);
+    if have < k {
+        return Ok(vec![quorum::insufficient(&rhex, have, k)?]);
+    }
+    rhex.finalize()?;
+    let cache = Cache::from_config(config)?;
+    let store = store::open(config)?;
+    let mut out = process::process_rhex(&rhex, true, &cache, &*store, config, keymaster)?;
+    out.insert(0, rhex);
+    Ok(out)
+}
+
+/// Hand `rhex` to `usher` and wait for it to come back with another
+/// quorum signature on it.
+async fn ask(usher: Usher, rhex: Rhex) -> Result<Option<Rhex>, Error> {
+    let mut transport = Transport::new();
+    transport
+        .connect(&usher.host, &usher.port.to_string())
+        .await?;
+    transport.send_rhex(&rhex).await?;
+    while let Some(reply) = transport.recv_next().await? {
+        if reply.intent.record_type == "response:error" {
+            eprintln!(
+                "⚠️ usher {} declined quorum: {}",
+                fingerprint(&usher.public_key),
+                reply.intent.data
+            );
+            break;
+        }
+        if quorum::count(&reply) > quorum::count(&rhex) {
+            transport.close().await;
+            return Ok(Some(reply));
+        }
+    }
+    transport.close().await;
+    Ok(None)
+}
@@ -1,7 +1,11 @@
+use std::{path::Path, sync::Arc};
+
 use anyhow::{Error, bail};
+use hl_core::keymaster::keymaster::Keymaster;
 
-use crate::argv::RebuildArgs;
+use crate::{argv::RebuildArgs, bootstrap};
 
+/// Throw the cache away and replay every scope on disk into a new one.
 pub fn rebuild(rebuild_args: &RebuildArgs) -> Result<(), Error> {
     let config_file = &rebuild_args.config;
     if config_file.is_none() {
@@ -10,6 +14,26 @@ pub fn rebuild(rebuild_args: &RebuildArgs) -> Result<(), Error> {
     }
     let config_file = config_file.clone().unwrap();
     let config = hl_services::config::load_config(&config_file)?;
-    hl_io::db::create_db(&config.cache_db)?;
+
+    // The old cache's heads say which interrupted appends committed
+    if Path::new(&config.cache_db).exists() {
+        bootstrap::repair(&config)?;
+    }
+    hl_io::db::delete_db(&config.cache_db)?;
+    let cache = hl_io::db::create_db(&config.cache_db)?;
+
+    let mut keymaster = Keymaster::new();
+    keymaster.load_keys(&config.hot_keys)?;
+    // Refusals are reported through error records from our own key
+    if let Some(pk) = config.hot_keys.first().and_then(|key| key.pk) {
+        keymaster.set_primary_key(&pk)?;
+    }
+    let store = hl_io::store::open(&config)?;
+    let config = Arc::new(config);
+    let report = hl_services::rebuild::rebuild(&cache, &*store, &config, &keymaster)?;
+    report.print();
+    if !report.is_clean() {
+        bail!("Rebuild refused records");
+    }
     Ok(())
 }